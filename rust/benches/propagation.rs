@@ -0,0 +1,186 @@
+// Loading, propagation, coordinate conversion and pass prediction for a
+// fixed sample of 500 element sets, so changes to the orbit and geometry
+// code can be compared run to run. The sample in sample-omm.json is
+// synthetic: mostly LEO, with some MEO, Molniya-type and geostationary
+// orbits so the deep-space SDP4 path gets exercised too. BENCHMARKS.md has
+// the numbers from the last time someone looked.
+//
+//   cargo bench --bench propagation
+
+use std::hint::black_box;
+
+use chrono::{DateTime, Utc};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use rayon::prelude::*;
+
+use whatsoverhead::coords::{self, Atmosphere, Observer};
+use whatsoverhead::data::{self, OmmRecord, Satellite};
+use whatsoverhead::passes::{self, PassSearchConfig};
+use whatsoverhead::query;
+
+const SAMPLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/benches/sample-omm.json");
+
+fn sample() -> Vec<OmmRecord> {
+    data::load_omm(SAMPLE).unwrap()
+}
+
+fn satellites() -> Vec<Satellite> {
+    sample()
+        .iter()
+        .filter_map(|r| Satellite::from_record(r).ok())
+        .collect()
+}
+
+fn observer() -> Observer {
+    Observer {
+        lat: 34.56,
+        lon: -118.76,
+        alt_m: 0.0,
+        atmosphere: Some(Atmosphere::STANDARD),
+    }
+}
+
+fn time() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339("2026-03-01T00:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+// Reading a 1000-record catalog: the sample twice over.
+fn load(c: &mut Criterion) {
+    let records: Vec<serde_json::Value> =
+        serde_json::from_str(&std::fs::read_to_string(SAMPLE).unwrap()).unwrap();
+    let doubled = records.iter().chain(&records).collect::<Vec<_>>();
+    let json = serde_json::to_vec(&doubled).unwrap();
+    let mut group = c.benchmark_group("load");
+    group.throughput(Throughput::Elements(doubled.len() as u64));
+    group.bench_function("read_catalog", |b| {
+        b.iter(|| black_box(data::read_catalog(black_box(json.as_slice())).unwrap()))
+    });
+    group.finish();
+}
+
+// Turning elements into SGP4 constants, which happens once per satellite
+// when the catalog is loaded.
+fn from_elements(c: &mut Criterion) {
+    let records = sample();
+    let mut group = c.benchmark_group("from_elements");
+    group.throughput(Throughput::Elements(records.len() as u64));
+    group.bench_function("sgp4", |b| {
+        b.iter(|| {
+            for r in &records {
+                black_box(sgp4::Constants::from_elements(&r.elements).ok());
+            }
+        })
+    });
+    group.bench_function("satellite", |b| {
+        b.iter(|| {
+            for r in &records {
+                black_box(Satellite::from_record(r).ok());
+            }
+        })
+    });
+    group.finish();
+}
+
+// Propagating to a moment and converting to ECEF, which happens for every
+// satellite on every query.
+fn propagate(c: &mut Criterion) {
+    let satellites = satellites();
+    let t = time();
+    let mut group = c.benchmark_group("propagate");
+    group.throughput(Throughput::Elements(satellites.len() as u64));
+    group.bench_function("single_threaded", |b| {
+        b.iter(|| black_box(query::propagate(&satellites, black_box(t))))
+    });
+    group.bench_function("rayon", |b| {
+        b.iter(|| {
+            black_box(
+                satellites
+                    .par_iter()
+                    .filter_map(|sat| sat.ecef_at(black_box(t)))
+                    .collect::<Vec<_>>(),
+            )
+        })
+    });
+    group.finish();
+}
+
+// Everything after propagation: look angles, refraction, sunlight and
+// brightness for one observer.
+fn geometry(c: &mut Criterion) {
+    let satellites = satellites();
+    let propagated = query::propagate(&satellites, time());
+    let observer = observer();
+    let mut group = c.benchmark_group("geometry");
+    group.throughput(Throughput::Elements(propagated.len() as u64));
+    group.bench_function("overhead", |b| {
+        b.iter(|| {
+            black_box(query::overhead_propagated(
+                &propagated,
+                black_box(&observer),
+                -90.0,
+            ))
+        })
+    });
+    group.finish();
+}
+
+// Converting 10,000 TEME positions, those of the sample at 20 times a minute
+// apart, to latitude, longitude and altitude.
+fn teme_to_geodetic(c: &mut Criterion) {
+    let t = time();
+    let positions = sample()
+        .iter()
+        .filter_map(|r| sgp4::Constants::from_elements(&r.elements).ok())
+        .flat_map(|constants| {
+            (0..20)
+                .filter_map(|minutes| constants.propagate(minutes as f64).ok())
+                .map(|prediction| prediction.position)
+                .collect::<Vec<_>>()
+        })
+        .take(10_000)
+        .collect::<Vec<_>>();
+    let mut group = c.benchmark_group("teme_to_geodetic");
+    group.throughput(Throughput::Elements(positions.len() as u64));
+    group.bench_function("convert", |b| {
+        b.iter(|| {
+            for &p in &positions {
+                black_box(coords::teme_to_geodetic(black_box(p), t, 0.0));
+            }
+        })
+    });
+    group.finish();
+}
+
+// A day of passes over one observer for the first 100 satellites.
+fn pass_prediction(c: &mut Criterion) {
+    let satellites = satellites().into_iter().take(100).collect::<Vec<_>>();
+    let observer = observer();
+    let config = PassSearchConfig::default();
+    let mut group = c.benchmark_group("passes");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(satellites.len() as u64));
+    group.bench_function("predict_all_passes", |b| {
+        b.iter(|| {
+            black_box(passes::predict_all_passes(
+                &satellites,
+                &observer,
+                time(),
+                &config,
+            ))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    load,
+    from_elements,
+    propagate,
+    geometry,
+    teme_to_geodetic,
+    pass_prediction
+);
+criterion_main!(benches);
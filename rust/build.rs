@@ -0,0 +1,70 @@
+// Turns data/cities.csv into a static array of locations::City, sorted the
+// way locations::find searches it, and data/egm96-5deg.csv into the grid
+// geoid::undulation interpolates.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+fn main() {
+    cities();
+    geoid();
+}
+
+fn cities() {
+    let source = "data/cities.csv";
+    println!("cargo:rerun-if-changed={}", source);
+
+    let mut cities = Vec::new();
+    let mut reader = csv::Reader::from_path(source).unwrap();
+    for record in reader.records() {
+        let record = record.unwrap();
+        let lat: f64 = record[3].parse().unwrap();
+        let lon: f64 = record[4].parse().unwrap();
+        cities.push((
+            record[0].to_string(),
+            record[1].to_string(),
+            record[2].to_string(),
+            lat,
+            lon,
+        ));
+    }
+    cities.sort_by_key(|c| (c.0.to_ascii_lowercase(), c.2.clone()));
+
+    let mut out = String::from("pub static CITIES: &[City] = &[\n");
+    for (name, region, country, lat, lon) in &cities {
+        writeln!(
+            out,
+            "    City {{ name: {:?}, region: {:?}, country: {:?}, lat: {:?}, lon: {:?} }},",
+            name, region, country, lat, lon
+        )
+        .unwrap();
+    }
+    out.push_str("];\n");
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("cities.rs");
+    fs::write(dest, out).unwrap();
+}
+
+fn geoid() {
+    let source = "data/egm96-5deg.csv";
+    println!("cargo:rerun-if-changed={}", source);
+
+    let mut reader = csv::Reader::from_path(source).unwrap();
+    // Some samples happen to look like 3.14 or 6.28 to clippy.
+    let mut out = String::from(
+        "#[allow(clippy::approx_constant)]\nstatic UNDULATION: [[f64; GRID_LONS]; GRID_LATS] = [\n",
+    );
+    for record in reader.records() {
+        let record = record.unwrap();
+        let row = record
+            .iter()
+            .skip(1)
+            .map(|n| format!("{:?}", n.parse::<f64>().unwrap()))
+            .collect::<Vec<_>>();
+        writeln!(out, "    [{}],", row.join(", ")).unwrap();
+    }
+    out.push_str("];\n");
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("geoid.rs");
+    fs::write(dest, out).unwrap();
+}
@@ -0,0 +1,56 @@
+// The optional config file with the observer's usual location and
+// preferences. Settings are resolved in this order, first one wins:
+//
+//   1. Command-line flags
+//   2. The config file (--config, or config.toml in the user's config
+//      directory, e.g. ~/.config/whatsoverhead/config.toml)
+//   3. Built-in defaults
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use whatsoverhead::geoid::AltitudeDatum;
+use whatsoverhead::units::Units;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// A city name, as for --location.
+    pub location: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub alt: Option<f64>,
+    pub alt_datum: Option<AltitudeDatum>,
+    pub units: Option<Units>,
+    pub min_elevation: Option<f64>,
+    /// More aliases for --sat and --notable, from name to NORAD ID, which
+    /// replace any built-in ones with the same name.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, u64>,
+    /// A WMM.COF file, as for --wmm.
+    pub wmm: Option<PathBuf>,
+}
+
+// Where we look for the config file when --config isn't given.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("whatsoverhead").join("config.toml"))
+}
+
+// Loads the config file. An explicitly requested file has to exist, but it's
+// fine for the default one not to. Either way, a file that exists and can't
+// be parsed is an error.
+pub fn load(explicit: Option<&Path>) -> Result<Config> {
+    let path = match explicit {
+        Some(path) => path.to_path_buf(),
+        None => match default_path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        },
+    };
+    let text = fs::read_to_string(&path)
+        .with_context(|| format!("Unable to read config file {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("Malformed config file {}", path.display()))
+}
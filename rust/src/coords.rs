@@ -0,0 +1,353 @@
+//! Coordinate frames and the conversions between them.
+//!
+//! SGP4 gives positions in TEME (True Equator, Mean Equinox), an inertial
+//! frame. To say anything about where a satellite is relative to the ground
+//! we rotate into ECEF (Earth-centered, Earth-fixed), then into geodetic
+//! latitude, longitude and height on the WGS84 ellipsoid, or into the
+//! observer's local east/north/up frame for look angles.
+//!
+//! Positions are in km, velocities in km/s, and angles in degrees unless a
+//! name says otherwise.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::geo::normalize_longitude;
+
+/// WGS84 equatorial radius in km.
+pub const WGS84_A: f64 = 6378.137;
+/// WGS84 flattening.
+pub const WGS84_F: f64 = 1.0 / 298.257223563;
+/// Earth's rotation rate in rad/s.
+pub const EARTH_ROTATION_RATE: f64 = 7.2921151467e-5;
+/// Earth's gravitational parameter in km^3/s^2.
+pub const EARTH_MU: f64 = 398600.4418;
+
+/// Someone looking at the sky.
+#[derive(Debug, Clone)]
+pub struct Observer {
+    /// Geodetic latitude in degrees.
+    pub lat: f64,
+    /// Longitude in degrees.
+    pub lon: f64,
+    /// Height above the WGS84 ellipsoid in meters.
+    pub alt_m: f64,
+    /// Air conditions for refraction, or None for purely geometric look
+    /// angles.
+    pub atmosphere: Option<Atmosphere>,
+}
+
+/// The air at the observer's location, which bends light from objects near
+/// the horizon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Atmosphere {
+    pub temperature_c: f64,
+    pub pressure_hpa: f64,
+}
+
+impl Atmosphere {
+    /// The conditions refraction tables are usually given for.
+    pub const STANDARD: Atmosphere = Atmosphere {
+        temperature_c: 10.0,
+        pressure_hpa: 1010.0,
+    };
+}
+
+/// A point given by geodetic latitude and longitude in degrees and height
+/// above the WGS84 ellipsoid in km. Longitudes from `ecef_to_geodetic` are
+/// in [-180, 180).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geodetic {
+    pub lat: f64,
+    pub lon: f64,
+    pub alt_km: f64,
+}
+
+/// Where something is in the observer's sky.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LookAngles {
+    /// Degrees clockwise from true north.
+    pub azimuth: f64,
+    /// Degrees above the horizon.
+    pub elevation: f64,
+    /// Straight-line distance in km.
+    pub range_km: f64,
+}
+
+/// Greenwich mean sidereal time in radians at UTC time `t`, given UT1 - UTC
+/// in seconds.
+///
+/// All the times we handle are UTC. That's what TLE and OMM epochs are given
+/// in, and SGP4 only needs the time since the epoch, so leap seconds and the
+/// like don't come into it there. Where the time scale does matter is the
+/// Earth's rotation angle: sidereal time follows UT1, which drifts from UTC
+/// by up to 0.9 s between leap seconds. That's about 0.004 degrees of
+/// longitude, or 400 m at the equator, which is less than SGP4's own error
+/// for most elements, so 0 is fine unless you have the current value from
+/// IERS Bulletin A.
+pub fn gmst(t: DateTime<Utc>, delta_ut1: f64) -> f64 {
+    let ut1 = t + Duration::nanoseconds((delta_ut1 * 1e9).round() as i64);
+    sgp4::iau_epoch_to_sidereal_time(sgp4::julian_years_since_j2000(&ut1.naive_utc()))
+}
+
+/// Rotates a TEME position into the Earth-fixed frame, given the sidereal
+/// time in radians. This ignores polar motion, which is well below the
+/// accuracy of SGP4.
+pub fn teme_to_ecef(p: [f64; 3], gmst: f64) -> [f64; 3] {
+    let (s, c) = gmst.sin_cos();
+    [c * p[0] + s * p[1], -s * p[0] + c * p[1], p[2]]
+}
+
+/// Rotates a TEME velocity into the Earth-fixed frame. The frame itself is
+/// rotating, so we have to subtract ω × r as well as rotate.
+pub fn teme_to_ecef_velocity(p: [f64; 3], v: [f64; 3], gmst: f64) -> [f64; 3] {
+    let r = teme_to_ecef(p, gmst);
+    let v = teme_to_ecef(v, gmst);
+    [
+        v[0] + EARTH_ROTATION_RATE * r[1],
+        v[1] - EARTH_ROTATION_RATE * r[0],
+        v[2],
+    ]
+}
+
+/// Speed in an inertial frame in km/s, given an ECEF position and velocity.
+/// This adds back the ω × r that `teme_to_ecef_velocity` took out; the
+/// rotation itself doesn't change the magnitude.
+pub fn inertial_speed(p: [f64; 3], v: [f64; 3]) -> f64 {
+    let w = [
+        v[0] - EARTH_ROTATION_RATE * p[1],
+        v[1] + EARTH_ROTATION_RATE * p[0],
+        v[2],
+    ];
+    (w[0] * w[0] + w[1] * w[1] + w[2] * w[2]).sqrt()
+}
+
+/// Converts an ECEF position to geodetic coordinates.
+pub fn ecef_to_geodetic(p: [f64; 3]) -> Geodetic {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let lon = p[1].atan2(p[0]);
+    let rxy = p[0].hypot(p[1]);
+    // Iterate on latitude; this converges to well under a meter in a few
+    // rounds for anything from the ground up to GEO.
+    let mut lat = p[2].atan2(rxy * (1.0 - e2));
+    let mut n = WGS84_A;
+    for _ in 0..5 {
+        n = WGS84_A / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+        lat = (p[2] + n * e2 * lat.sin()).atan2(rxy);
+    }
+    // rxy / cos(lat) - n is simpler, but falls apart near the poles where
+    // both go to zero. This form is exact for the same latitude everywhere.
+    let (sin_lat, cos_lat) = lat.sin_cos();
+    let alt = rxy * cos_lat + p[2] * sin_lat + n * e2 * sin_lat * sin_lat - n;
+    Geodetic {
+        lat: lat.to_degrees(),
+        lon: normalize_longitude(lon.to_degrees()),
+        alt_km: alt,
+    }
+}
+
+/// Converts a TEME position at time `t` to geodetic coordinates, with UT1 -
+/// UTC as for `gmst`.
+pub fn teme_to_geodetic(p: [f64; 3], t: DateTime<Utc>, delta_ut1: f64) -> Geodetic {
+    ecef_to_geodetic(teme_to_ecef(p, gmst(t, delta_ut1)))
+}
+
+/// The observer's position in ECEF. The altitude is treated as height above
+/// the WGS84 ellipsoid.
+pub fn observer_ecef(observer: &Observer) -> [f64; 3] {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let (lat, lon) = (observer.lat.to_radians(), observer.lon.to_radians());
+    let n = WGS84_A / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+    let h = observer.alt_m / 1000.0;
+    [
+        (n + h) * lat.cos() * lon.cos(),
+        (n + h) * lat.cos() * lon.sin(),
+        (n * (1.0 - e2) + h) * lat.sin(),
+    ]
+}
+
+/// Converts a geodetic latitude (the angle between the ellipsoid's normal
+/// and the equator, which GPS and maps give) to a geocentric one (the angle
+/// between the line to Earth's center and the equator), in degrees, for a
+/// point on the WGS84 ellipsoid: tan(geocentric) = (1 - e²) tan(geodetic).
+/// They agree at the equator and the poles, and differ most, by 0.19°, at
+/// 45°.
+pub fn geodetic_to_geocentric_latitude(lat: f64) -> f64 {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    ((1.0 - e2) * lat.to_radians().tan()).atan().to_degrees()
+}
+
+/// The inverse of [`geodetic_to_geocentric_latitude`]: tan(geodetic) =
+/// tan(geocentric) / (1 - e²).
+pub fn geocentric_to_geodetic_latitude(lat: f64) -> f64 {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    (lat.to_radians().tan() / (1.0 - e2)).atan().to_degrees()
+}
+
+/// Rotates an ECEF vector into local east, north, up components at the
+/// given geodetic latitude and longitude.
+pub fn ecef_to_enu(d: [f64; 3], lat: f64, lon: f64) -> [f64; 3] {
+    let (slat, clat) = lat.to_radians().sin_cos();
+    let (slon, clon) = lon.to_radians().sin_cos();
+    [
+        -slon * d[0] + clon * d[1],
+        -slat * clon * d[0] - slat * slon * d[1] + clat * d[2],
+        clat * clon * d[0] + clat * slon * d[1] + slat * d[2],
+    ]
+}
+
+/// Azimuth, elevation and slant range from the observer to an ECEF position.
+/// If the observer has an atmosphere, the elevation is the apparent one,
+/// corrected for refraction.
+pub fn look_angles(observer: &Observer, sat: [f64; 3]) -> LookAngles {
+    let d = sub(sat, observer_ecef(observer));
+    let [e, n, u] = ecef_to_enu(d, observer.lat, observer.lon);
+    let range = (e * e + n * n + u * u).sqrt();
+    let mut elevation = (u / range).asin().to_degrees();
+    if let Some(air) = observer.atmosphere {
+        elevation += refraction_correction_deg(elevation, air.temperature_c, air.pressure_hpa);
+    }
+    LookAngles {
+        azimuth: e.atan2(n).to_degrees().rem_euclid(360.0),
+        elevation,
+        range_km: range,
+    }
+}
+
+/// How fast the distance from the observer to a satellite is changing, in
+/// km/s, given the satellite's ECEF position and velocity. The observer is
+/// fixed in ECEF, so this is just the satellite's velocity along the line
+/// of sight. Positive means the satellite is moving away, the usual sign
+/// convention for Doppler shift.
+pub fn range_rate(observer: &Observer, sat: [f64; 3], velocity: [f64; 3]) -> f64 {
+    let d = sub(sat, observer_ecef(observer));
+    let range = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+    (d[0] * velocity[0] + d[1] * velocity[1] + d[2] * velocity[2]) / range
+}
+
+/// How fast a satellite's geometric elevation is changing, in degrees per
+/// second, given its ECEF position and velocity. Positive while it's
+/// rising. Right at the zenith it's at the top of its arc, so it's 0.
+pub fn elevation_rate(observer: &Observer, sat: [f64; 3], velocity: [f64; 3]) -> f64 {
+    let [e, n, u] = ecef_to_enu(
+        sub(sat, observer_ecef(observer)),
+        observer.lat,
+        observer.lon,
+    );
+    let [ve, vn, vu] = ecef_to_enu(velocity, observer.lat, observer.lon);
+    // The derivative of atan2(u, h), with h the horizontal distance, which
+    // divides by h. As for azimuth_rate, within a millimeter of the zenith
+    // line e and n are rounding error.
+    let h = e.hypot(n);
+    if h < 1e-6 {
+        return 0.0;
+    }
+    let range2 = h * h + u * u;
+    ((vu * h * h - u * (e * ve + n * vn)) / (h * range2)).to_degrees()
+}
+
+/// How fast a satellite's azimuth is changing, in degrees per second,
+/// given its ECEF position and velocity. Positive is clockwise, from north
+/// towards east. This grows without bound as the satellite passes straight
+/// overhead, where azimuth isn't defined; right at the zenith it's 0.
+pub fn azimuth_rate(observer: &Observer, sat: [f64; 3], velocity: [f64; 3]) -> f64 {
+    let [e, n, _] = ecef_to_enu(
+        sub(sat, observer_ecef(observer)),
+        observer.lat,
+        observer.lon,
+    );
+    let [ve, vn, _] = ecef_to_enu(velocity, observer.lat, observer.lon);
+    // The derivative of atan2(e, n). Within a millimeter of the zenith
+    // line, e and n are rounding error.
+    let h2 = e * e + n * n;
+    if h2 < 1e-12 {
+        return 0.0;
+    }
+    ((ve * n - e * vn) / h2).to_degrees()
+}
+
+/// Azimuth and elevation rates together in degrees per second, as an
+/// antenna rotator needs them to track a satellite. These are worked out
+/// from the ECEF velocity rather than by differencing look angles, so
+/// there's no step size to choose. Rotators often top out around 2 degrees
+/// per second, which a low satellite passing close to overhead exceeds.
+pub fn look_angle_rates(observer: &Observer, sat: [f64; 3], velocity: [f64; 3]) -> (f64, f64) {
+    (
+        azimuth_rate(observer, sat, velocity),
+        elevation_rate(observer, sat, velocity),
+    )
+}
+
+/// How much higher than its geometric elevation an object appears because
+/// of atmospheric refraction, in degrees.
+///
+/// This is Bennett's formula in the form Sæmundsson inverted to take the
+/// geometric rather than the apparent elevation, scaled for temperature and
+/// pressure: R = 1.02 / tan(h + 10.3 / (h + 5.11)) arcminutes. It's good to
+/// a few arcseconds above the horizon. Below -1 degree the formula breaks
+/// down, and nothing there is visible anyway, so it returns 0.
+pub fn refraction_correction_deg(
+    geometric_elevation_deg: f64,
+    temperature_c: f64,
+    pressure_hpa: f64,
+) -> f64 {
+    let h = geometric_elevation_deg;
+    if h < -1.0 {
+        return 0.0;
+    }
+    let arcmin = 1.02 / (h + 10.3 / (h + 5.11)).to_radians().tan();
+    let scale = (pressure_hpa / 1010.0) * (283.0 / (273.0 + temperature_c));
+    arcmin * scale / 60.0
+}
+
+/// The direction a subpoint at `lat`, `lon` is moving over the ground given
+/// the satellite's ECEF velocity, in degrees from north.
+pub fn ground_heading(lat: f64, lon: f64, velocity: [f64; 3]) -> f64 {
+    let [e, n, _] = ecef_to_enu(velocity, lat, lon);
+    e.atan2(n).to_degrees().rem_euclid(360.0)
+}
+
+/// Component-wise `a - b`.
+pub fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+/// The angle between two vectors in degrees. This uses atan2 of their cross
+/// and dot products rather than acos of the dot product, which loses
+/// precision for nearly parallel vectors.
+pub fn angle_between(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let cross = [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ];
+    let dot = a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let sin = (cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]).sqrt();
+    sin.atan2(dot).to_degrees()
+}
+
+/// The angle on the sky between two directions given as azimuth and
+/// elevation, in degrees. Like [`angle_between`], it stays precise for
+/// nearly parallel directions, the case we care most about.
+pub fn angular_separation(az1: f64, el1: f64, az2: f64, el2: f64) -> f64 {
+    let unit = |az: f64, el: f64| {
+        let (saz, caz) = az.to_radians().sin_cos();
+        let (sel, cel) = el.to_radians().sin_cos();
+        [cel * saz, cel * caz, sel]
+    };
+    angle_between(unit(az1, el1), unit(az2, el2))
+}
+
+/// The angle on the sky between two satellites, in degrees, straight from
+/// ECEF positions in km: the angle between the observer's lines of sight to
+/// them. Like [`angular_separation`], it stays precise for nearly parallel
+/// lines.
+pub fn line_of_sight_separation(
+    observer_ecef: [f64; 3],
+    sat_a_ecef: [f64; 3],
+    sat_b_ecef: [f64; 3],
+) -> f64 {
+    angle_between(
+        sub(sat_a_ecef, observer_ecef),
+        sub(sat_b_ecef, observer_ecef),
+    )
+}
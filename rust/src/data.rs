@@ -0,0 +1,646 @@
+//! Loading satellite catalogs.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::de::{SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::coords::{gmst, teme_to_ecef, teme_to_ecef_velocity, EARTH_MU, WGS84_A};
+use crate::magnitude::standard_magnitude;
+use crate::orbit::OrbitClass;
+
+/// Objects with a perigee lower than this (km) are flagged as decaying.
+pub const DECAYING_PERIGEE_KM: f64 = 200.0;
+/// Objects whose mean motion is increasing faster than this (rev/day^2) are
+/// flagged as decaying. Like a low perigee, this means the orbit will only
+/// last days to weeks.
+pub const DECAYING_MEAN_MOTION_DOT: f64 = 0.01;
+
+/// An OMM record as it appears in the catalog. sgp4::Elements only has the
+/// fields SGP4 needs, so this keeps the extra ones we care about.
+#[derive(Deserialize)]
+pub struct OmmRecord {
+    #[serde(flatten)]
+    pub elements: sgp4::Elements,
+    #[serde(rename = "DECAY_DATE", default)]
+    pub decay_date: Option<NaiveDate>,
+}
+
+/// A satellite with its SGP4 constants computed up front, so it can be
+/// propagated to many different times cheaply, plus the catalog metadata
+/// we report on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Satellite {
+    pub name: String,
+    pub norad_id: u64,
+    pub epoch: DateTime<Utc>,
+    pub constants: sgp4::Constants,
+    pub decay_date: Option<NaiveDate>,
+    /// B* drag term in 1/earth radii.
+    pub bstar: f64,
+    /// Mean motion in rev/day.
+    pub mean_motion: f64,
+    /// First derivative of mean motion in rev/day^2.
+    pub mean_motion_dot: f64,
+    /// Second derivative of mean motion in rev/day^3.
+    pub mean_motion_ddot: f64,
+    pub eccentricity: f64,
+    /// Inclination in degrees.
+    pub inclination: f64,
+    /// Right ascension of the ascending node in degrees.
+    pub right_ascension: f64,
+    /// Argument of perigee in degrees.
+    pub argument_of_perigee: f64,
+    /// Mean anomaly at epoch in degrees.
+    pub mean_anomaly: f64,
+    pub element_set_number: u64,
+    /// Orbits completed by the epoch.
+    pub revolution_number: u64,
+    pub international_designator: Option<String>,
+    pub perigee_km: f64,
+    pub standard_magnitude: Option<f64>,
+}
+
+/// The satellites from a catalog file, ready to propagate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Catalog {
+    /// How many records the file had, including rejected ones.
+    pub records: usize,
+    pub satellites: Vec<Satellite>,
+    /// Records that were skipped, in the order they appeared.
+    pub rejected: Vec<Rejected>,
+}
+
+/// A catalog record we couldn't use.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rejected {
+    /// The record's NORAD_CAT_ID, if it had a readable one.
+    pub norad_id: Option<u64>,
+    pub reason: String,
+}
+
+/// Why SGP4 couldn't work out where a satellite is at some moment. This
+/// usually means the object has decayed, or the elements are so old that
+/// the orbit they describe no longer makes sense.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropagationError {
+    pub norad_id: u64,
+    pub name: String,
+    /// What went wrong, in a few words. Satellites that failed the same way
+    /// have the same kind.
+    pub kind: String,
+    /// SGP4's own description, with the numbers involved.
+    pub detail: String,
+}
+
+// A few words for each kind of SGP4 failure.
+fn error_kind(e: &sgp4::Error) -> &'static str {
+    match e {
+        sgp4::Error::OutOfRangeEpochEccentricity { .. } => "eccentricity out of range at epoch",
+        sgp4::Error::OutOfRangeEccentricity { .. }
+        | sgp4::Error::OutOfRangePerturbedEccentricity { .. } => "eccentricity out of range",
+        sgp4::Error::NegativeBrouwerMeanMotion | sgp4::Error::NegativeKozaiMeanMotion => {
+            "negative mean motion"
+        }
+        sgp4::Error::NegativeSemiLatusRectum { .. } => "orbit collapsed",
+        sgp4::Error::DatetimeDeltaOverflow { .. } => "time out of range",
+        sgp4::Error::Tle { .. } => "malformed TLE",
+    }
+}
+
+/// Bump this whenever `Catalog` or `Satellite` change shape, so stale cache
+/// files are ignored instead of misread.
+const CACHE_VERSION: u32 = 5;
+
+/// Reads a JSON array of OMM records, as downloaded from space-track.org.
+pub fn load_omm(path: impl AsRef<Path>) -> Result<Vec<OmmRecord>> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("Unable to read {}", path.display()))?;
+    serde_json::from_reader(BufReader::new(file)).context("JSON was not well-formatted")
+}
+
+/// Reads an OMM catalog and builds a `Satellite` for each record. Records
+/// are converted as they're parsed, so only one is in memory at a time
+/// rather than the whole file's worth.
+///
+/// Real catalogs occasionally have records with missing or garbled fields.
+/// Those are skipped and listed in `Catalog::rejected` rather than failing
+/// the whole load; only a file that isn't a JSON array is an error.
+pub fn load_catalog(path: impl AsRef<Path>) -> Result<Catalog> {
+    load_catalog_with_progress(path, || {})
+}
+
+/// Like [`load_catalog`], but calls `on_record` each time a record has been
+/// read, so a long load can show its progress.
+pub fn load_catalog_with_progress(
+    path: impl AsRef<Path>,
+    on_record: impl FnMut(),
+) -> Result<Catalog> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("Unable to read {}", path.display()))?;
+    read_catalog_with_progress(BufReader::new(file), on_record)
+}
+
+/// Like [`load_catalog`], for a catalog that's already open.
+pub fn read_catalog(reader: impl io::Read) -> Result<Catalog> {
+    read_catalog_with_progress(reader, || {})
+}
+
+fn read_catalog_with_progress(reader: impl io::Read, on_record: impl FnMut()) -> Result<Catalog> {
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let catalog = de
+        .deserialize_seq(CatalogVisitor(on_record))
+        .and_then(|catalog| de.end().map(|_| catalog))
+        .context("JSON was not well-formatted")?;
+    Ok(catalog)
+}
+
+/// Element sets for a handful of bright, well-known satellites, built into
+/// the binary so there's something to look at before downloading a
+/// catalog. They're only as current as the build.
+pub const BUNDLED_OMM: &str = include_str!("../data/bundled-omm.json");
+
+/// Reads [`BUNDLED_OMM`].
+pub fn bundled_catalog() -> Catalog {
+    read_catalog(BUNDLED_OMM.as_bytes()).expect("the bundled element sets are well-formed")
+}
+
+// Calls its function after each record.
+struct CatalogVisitor<F>(F);
+
+impl<'de, F: FnMut()> Visitor<'de> for CatalogVisitor<F> {
+    type Value = Catalog;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array of OMM records")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<Catalog, A::Error> {
+        let mut catalog = Catalog {
+            records: 0,
+            satellites: Vec::with_capacity(seq.size_hint().unwrap_or(0)),
+            rejected: Vec::new(),
+        };
+        // Going through Value means a bad record only costs us that record.
+        while let Some(value) = seq.next_element::<serde_json::Value>()? {
+            catalog.records += 1;
+            let norad_id = omm_norad_id(&value);
+            let reject = |reason: String| Rejected { norad_id, reason };
+            match OmmRecord::deserialize(value) {
+                Ok(record) => match Satellite::from_record(&record) {
+                    Ok(sat) => catalog.satellites.push(sat),
+                    Err(e) => catalog.rejected.push(reject(format!(
+                        "SGP4 can't use these elements: {}",
+                        error_kind(&e)
+                    ))),
+                },
+                Err(e) => catalog.rejected.push(reject(e.to_string())),
+            }
+            (self.0)();
+        }
+        Ok(catalog)
+    }
+}
+
+/// Where the cache for a catalog lives: next to it, with `.cache` appended.
+pub fn cache_path(catalog: impl AsRef<Path>) -> PathBuf {
+    let mut path = catalog.as_ref().as_os_str().to_owned();
+    path.push(".cache");
+    PathBuf::from(path)
+}
+
+/// Identifies a version of the catalog file. If the size or modification
+/// time has changed since the cache was written, the cache is stale.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheHeader {
+    version: u32,
+    source_len: u64,
+    source_modified_secs: u64,
+    source_modified_nanos: u32,
+}
+
+impl CacheHeader {
+    fn for_source(source: &Path) -> Result<CacheHeader> {
+        let meta =
+            fs::metadata(source).with_context(|| format!("Unable to read {}", source.display()))?;
+        let modified = meta.modified()?.duration_since(UNIX_EPOCH)?;
+        Ok(CacheHeader {
+            version: CACHE_VERSION,
+            source_len: meta.len(),
+            source_modified_secs: modified.as_secs(),
+            source_modified_nanos: modified.subsec_nanos(),
+        })
+    }
+}
+
+/// Loads the cached catalog for `source`, or returns None if there's no
+/// cache, it was written for a different version of the file, or it can't
+/// be read for any other reason.
+pub fn read_cache(cache: impl AsRef<Path>, source: impl AsRef<Path>) -> Option<Catalog> {
+    let expected = CacheHeader::for_source(source.as_ref()).ok()?;
+    let mut r = BufReader::new(File::open(cache).ok()?);
+    let header: CacheHeader = bincode::deserialize_from(&mut r).ok()?;
+    if header != expected {
+        return None;
+    }
+    bincode::deserialize_from(&mut r).ok()
+}
+
+/// Writes `catalog` to `cache`, stamped with the current size and
+/// modification time of `source`.
+pub fn write_cache(
+    cache: impl AsRef<Path>,
+    source: impl AsRef<Path>,
+    catalog: &Catalog,
+) -> Result<()> {
+    let cache = cache.as_ref();
+    let header = CacheHeader::for_source(source.as_ref())?;
+    let file =
+        File::create(cache).with_context(|| format!("Unable to write {}", cache.display()))?;
+    let mut w = BufWriter::new(file);
+    bincode::serialize_into(&mut w, &header)?;
+    bincode::serialize_into(&mut w, catalog)?;
+    w.flush()?;
+    Ok(())
+}
+
+impl Catalog {
+    /// Makes satellites out of elements from anywhere, such as TLEs parsed
+    /// with `sgp4::Elements::from_tle`, rejecting the ones SGP4 can't use
+    /// as loading a catalog file would.
+    pub fn from_elements(elements: impl IntoIterator<Item = sgp4::Elements>) -> Catalog {
+        let mut catalog = Catalog {
+            records: 0,
+            satellites: Vec::new(),
+            rejected: Vec::new(),
+        };
+        for elements in elements {
+            catalog.records += 1;
+            let norad_id = elements.norad_id;
+            match Satellite::from_record(&OmmRecord {
+                elements,
+                decay_date: None,
+            }) {
+                Ok(sat) => catalog.satellites.push(sat),
+                Err(e) => catalog.rejected.push(Rejected {
+                    norad_id: Some(norad_id),
+                    reason: format!("SGP4 can't use these elements: {}", error_kind(&e)),
+                }),
+            }
+        }
+        catalog
+    }
+}
+
+impl AsRef<[Satellite]> for Catalog {
+    fn as_ref(&self) -> &[Satellite] {
+        &self.satellites
+    }
+}
+
+impl Satellite {
+    /// Returns an error if SGP4 can't handle the elements.
+    pub fn from_record(record: &OmmRecord) -> Result<Satellite, sgp4::Error> {
+        let sat = &record.elements;
+        let name = sat.object_name.clone().unwrap_or_default();
+        Ok(Satellite {
+            standard_magnitude: standard_magnitude(sat.norad_id, &name),
+            name,
+            norad_id: sat.norad_id,
+            epoch: chrono::Utc.from_utc_datetime(&sat.datetime),
+            constants: sgp4::Constants::from_elements(sat)?,
+            decay_date: record.decay_date,
+            bstar: sat.drag_term,
+            mean_motion: sat.mean_motion,
+            mean_motion_dot: sat.mean_motion_dot,
+            mean_motion_ddot: sat.mean_motion_ddot,
+            eccentricity: sat.eccentricity,
+            inclination: sat.inclination,
+            right_ascension: sat.right_ascension,
+            argument_of_perigee: sat.argument_of_perigee,
+            mean_anomaly: sat.mean_anomaly,
+            element_set_number: sat.element_set_number,
+            revolution_number: sat.revolution_number,
+            international_designator: sat.international_designator.clone(),
+            perigee_km: perigee_altitude(sat.mean_motion, sat.eccentricity),
+        })
+    }
+
+    /// Time for one orbit in minutes, from the mean motion.
+    pub fn period_minutes(&self) -> f64 {
+        1440.0 / self.mean_motion
+    }
+
+    /// Which regime the orbit is in: LEO, MEO, GEO or HEO.
+    pub fn orbit_class(&self) -> OrbitClass {
+        OrbitClass::classify(self.mean_motion, self.eccentricity)
+    }
+
+    /// True if the catalog says the object re-entered before the given time.
+    pub fn has_decayed_by(&self, t: DateTime<Utc>) -> bool {
+        match self.decay_date {
+            Some(date) => Utc.from_utc_datetime(&date.and_time(chrono::NaiveTime::MIN)) < t,
+            None => false,
+        }
+    }
+
+    /// True if the orbit looks like it's about to decay: a very low perigee,
+    /// or drag pulling it down fast enough to show up in the mean motion.
+    pub fn is_decaying(&self) -> bool {
+        self.perigee_km < DECAYING_PERIGEE_KM
+            || (self.bstar > 0.0 && self.mean_motion_dot > DECAYING_MEAN_MOTION_DOT)
+    }
+
+    /// Propagates to the given time and returns the ECEF position (km) and
+    /// velocity (km/s), taking UT1 to be UTC.
+    pub fn ecef_at(&self, t: DateTime<Utc>) -> Option<([f64; 3], [f64; 3])> {
+        self.try_ecef_at(t).ok()
+    }
+
+    /// Like `ecef_at`, but says why propagation failed.
+    pub fn try_ecef_at(&self, t: DateTime<Utc>) -> Result<([f64; 3], [f64; 3]), PropagationError> {
+        self.try_ecef_at_ut1(t, 0.0)
+    }
+
+    /// Like `try_ecef_at`, turning the Earth to UT1 given UT1 - UTC in
+    /// seconds; see [`gmst`].
+    pub fn try_ecef_at_ut1(
+        &self,
+        t: DateTime<Utc>,
+        delta_ut1: f64,
+    ) -> Result<([f64; 3], [f64; 3]), PropagationError> {
+        let time_diff = t - self.epoch;
+        let epoch_minutes = (time_diff.num_milliseconds() as f64) / 60_000.0;
+        let prediction = self
+            .constants
+            .propagate(epoch_minutes)
+            .map_err(|e| PropagationError {
+                norad_id: self.norad_id,
+                name: self.name.clone(),
+                kind: error_kind(&e).to_string(),
+                detail: format!("{:?}", e),
+            })?;
+        // The sgp4 docs say "The position and velocity are given in the True
+        // Equator, Mean Equinox (TEME) of epoch reference frame" but we need
+        // to convert to lat, lon, altitude.
+        let theta = gmst(t, delta_ut1);
+        Ok((
+            teme_to_ecef(prediction.position, theta),
+            teme_to_ecef_velocity(prediction.position, prediction.velocity, theta),
+        ))
+    }
+}
+
+/// Finds one satellite by NORAD ID, or by name ignoring case. A satellite
+/// whose name is exactly the query wins, unless there are several of them;
+/// otherwise the query can be any part of the name, as long as only one
+/// satellite matches.
+pub fn find_satellite<'a>(satellites: &'a [Satellite], query: &str) -> Result<&'a Satellite> {
+    let query = query.trim();
+    if let Ok(id) = query.parse::<u64>() {
+        return satellites
+            .iter()
+            .find(|sat| sat.norad_id == id)
+            .with_context(|| format!("No satellite has NORAD ID {}", id));
+    }
+    let (exact, matches) = match_names(satellites.iter().map(|sat| sat.name.as_str()), query);
+    match matches[..] {
+        _ if exact == 1 => Ok(&satellites[matches[0]]),
+        _ if exact > 1 => bail!(
+            "{} satellites are named \"{}\"; use a NORAD ID to pick one:\n{}",
+            exact,
+            query,
+            list_satellites(matches[..exact].iter().map(|&i| &satellites[i]))
+        ),
+        [i] => Ok(&satellites[i]),
+        [] => bail!("No satellite's name contains \"{}\"", query),
+        _ => bail!(
+            "\"{}\" matches {} satellites; use a NORAD ID to pick one:\n{}",
+            query,
+            matches.len(),
+            list_satellites(matches.iter().map(|&i| &satellites[i]))
+        ),
+    }
+}
+
+/// The indices of the element sets whose names contain `query`, ignoring
+/// case, with the ones whose names are exactly `query` first. Otherwise
+/// they're in the order they're given.
+pub fn find_by_name(elements: &[sgp4::Elements], query: &str) -> Vec<usize> {
+    let names = elements
+        .iter()
+        .map(|e| e.object_name.as_deref().unwrap_or(""));
+    match_names(names, query.trim()).1
+}
+
+/// [`find_by_name`] for satellites.
+pub fn find_satellites_by_name(satellites: &[Satellite], query: &str) -> Vec<usize> {
+    match_names(satellites.iter().map(|sat| sat.name.as_str()), query.trim()).1
+}
+
+/// One line per satellite, with its name and NORAD ID, for listing
+/// ambiguous matches.
+pub fn list_satellites<'a>(satellites: impl IntoIterator<Item = &'a Satellite>) -> String {
+    satellites
+        .into_iter()
+        .map(|sat| format!("  {} ({})", sat.name, sat.norad_id))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// The indices of the names containing `query`, ignoring case, exact matches
+// first, and how many of them are exact.
+fn match_names<'a>(names: impl Iterator<Item = &'a str>, query: &str) -> (usize, Vec<usize>) {
+    let lower = query.to_lowercase();
+    let (mut exact, mut partial) = (Vec::new(), Vec::new());
+    for (i, name) in names.enumerate() {
+        let name = name.to_lowercase();
+        if name == lower {
+            exact.push(i);
+        } else if name.contains(&lower) {
+            partial.push(i);
+        }
+    }
+    let count = exact.len();
+    exact.extend(partial);
+    (count, exact)
+}
+
+/// Reads a list of NORAD catalog numbers, one per line. Blank lines and
+/// anything after a # are ignored.
+pub fn read_norad_ids(path: impl AsRef<Path>) -> Result<Vec<u64>> {
+    let path = path.as_ref();
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Unable to read {}", path.display()))?;
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            (!line.is_empty()).then_some((i, line))
+        })
+        .map(|(i, line)| {
+            line.parse().with_context(|| {
+                format!(
+                    "{} line {}: \"{}\" isn't a NORAD ID",
+                    path.display(),
+                    i + 1,
+                    line
+                )
+            })
+        })
+        .collect()
+}
+
+/// Removes all but the newest element set for each NORAD ID, keeping the
+/// catalog's order otherwise, and returns how many were removed. Of two
+/// element sets with the same epoch, the later one in the catalog wins.
+pub fn drop_duplicates(satellites: &mut Vec<Satellite>) -> usize {
+    let mut newest = HashMap::new();
+    for (i, sat) in satellites.iter().enumerate() {
+        newest
+            .entry(sat.norad_id)
+            .and_modify(|best: &mut (usize, DateTime<Utc>)| {
+                if sat.epoch >= best.1 {
+                    *best = (i, sat.epoch);
+                }
+            })
+            .or_insert((i, sat.epoch));
+    }
+    let before = satellites.len();
+    let mut i = 0;
+    satellites.retain(|sat| {
+        let keep = newest[&sat.norad_id].0 == i;
+        i += 1;
+        keep
+    });
+    before - satellites.len()
+}
+
+// An OMM record's NORAD_CAT_ID, which some sources send as a string.
+fn omm_norad_id(record: &serde_json::Value) -> Option<u64> {
+    match record.get("NORAD_CAT_ID")? {
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        id => id.as_u64(),
+    }
+}
+
+/// Merges catalogs that may share objects, the way several CelesTrak groups
+/// do, into one list of OMM records as JSON objects. Only the record with
+/// the newest EPOCH is kept for each NORAD_CAT_ID, which may be a number or
+/// a string. Records are ordered by NORAD ID, and ones without a readable
+/// ID are all kept, at the end, since there's no telling what they
+/// duplicate.
+pub fn merge_omm(
+    catalogs: impl IntoIterator<Item = Vec<serde_json::Value>>,
+) -> Vec<serde_json::Value> {
+    // OMM epochs are ISO 8601 UTC, which sort as strings.
+    fn epoch(record: &serde_json::Value) -> &str {
+        record.get("EPOCH").and_then(|e| e.as_str()).unwrap_or("")
+    }
+    let mut newest = BTreeMap::new();
+    let mut unidentified = Vec::new();
+    for record in catalogs.into_iter().flatten() {
+        let Some(id) = omm_norad_id(&record) else {
+            unidentified.push(record);
+            continue;
+        };
+        match newest.get(&id) {
+            Some(kept) if epoch(kept) > epoch(&record) => {}
+            _ => {
+                newest.insert(id, record);
+            }
+        }
+    }
+    newest.into_values().chain(unidentified).collect()
+}
+
+/// Removes satellites that the catalog says re-entered before `t` and
+/// returns how many were removed.
+pub fn drop_decayed(satellites: &mut Vec<Satellite>, t: DateTime<Utc>) -> usize {
+    let before = satellites.len();
+    satellites.retain(|sat| !sat.has_decayed_by(t));
+    before - satellites.len()
+}
+
+/// The upper ends, in days, of the epoch age ranges [`CatalogStats`]
+/// counts; the last range has no upper end.
+pub const EPOCH_AGE_BUCKET_DAYS: [f64; 4] = [1.0, 3.0, 7.0, 30.0];
+
+/// A summary of what's in a catalog, for judging whether it's fresh enough
+/// to trust.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogStats {
+    pub satellites: usize,
+    pub oldest_epoch: Option<DateTime<Utc>>,
+    pub newest_epoch: Option<DateTime<Utc>>,
+    /// The median age of the elements at the time the stats are for, in
+    /// days.
+    pub median_epoch_age_days: Option<f64>,
+    /// How many element sets are at most each of [`EPOCH_AGE_BUCKET_DAYS`]
+    /// old but older than the one before, then how many are older than all
+    /// of them. Elements from after the time count as new.
+    pub epoch_ages: Vec<usize>,
+    pub orbit_classes: BTreeMap<OrbitClass, usize>,
+    /// How many look like they're about to re-enter, by
+    /// [`Satellite::is_decaying`].
+    pub decaying: usize,
+}
+
+/// Summarizes `satellites` as of `t`, without propagating them.
+pub fn catalog_stats(satellites: &[Satellite], t: DateTime<Utc>) -> CatalogStats {
+    let mut ages = satellites
+        .iter()
+        .map(|sat| (t - sat.epoch).num_milliseconds() as f64 / 86_400_000.0)
+        .collect::<Vec<_>>();
+    ages.sort_by(f64::total_cmp);
+    let median_epoch_age_days = match ages.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(ages[n / 2]),
+        n => Some((ages[n / 2 - 1] + ages[n / 2]) / 2.0),
+    };
+    let mut epoch_ages = vec![0; EPOCH_AGE_BUCKET_DAYS.len() + 1];
+    for age in &ages {
+        let bucket = EPOCH_AGE_BUCKET_DAYS
+            .iter()
+            .position(|&max| *age <= max)
+            .unwrap_or(EPOCH_AGE_BUCKET_DAYS.len());
+        epoch_ages[bucket] += 1;
+    }
+    let mut orbit_classes = BTreeMap::new();
+    for sat in satellites {
+        *orbit_classes.entry(sat.orbit_class()).or_insert(0) += 1;
+    }
+    CatalogStats {
+        satellites: satellites.len(),
+        oldest_epoch: satellites.iter().map(|sat| sat.epoch).min(),
+        newest_epoch: satellites.iter().map(|sat| sat.epoch).max(),
+        median_epoch_age_days,
+        epoch_ages,
+        orbit_classes,
+        decaying: satellites.iter().filter(|sat| sat.is_decaying()).count(),
+    }
+}
+
+/// Perigee height above the equatorial radius in km, from the mean motion
+/// (rev/day) and eccentricity.
+pub fn perigee_altitude(mean_motion: f64, eccentricity: f64) -> f64 {
+    semi_major_axis(mean_motion) * (1.0 - eccentricity) - WGS84_A
+}
+
+/// Apogee height above the equatorial radius in km, from the mean motion
+/// (rev/day) and eccentricity.
+pub fn apogee_altitude(mean_motion: f64, eccentricity: f64) -> f64 {
+    semi_major_axis(mean_motion) * (1.0 + eccentricity) - WGS84_A
+}
+
+/// Semi-major axis in km, from the mean motion in rev/day.
+pub fn semi_major_axis(mean_motion: f64) -> f64 {
+    let n = mean_motion * 2.0 * std::f64::consts::PI / 86400.0;
+    (EARTH_MU / (n * n)).cbrt()
+}
@@ -0,0 +1,51 @@
+//! Plain-English descriptions of sightings, for reading out loud.
+
+use crate::geo::cardinal_direction;
+use crate::query::SatelliteReport;
+use crate::units::NumberStyle;
+
+/// Turns a sighting into something like "Right now the ISS is 45 degrees up
+/// in the northeast, 680 km away and moving southeast."
+pub fn describe(s: &SatelliteReport, style: NumberStyle) -> String {
+    // "ISS (ZARYA)" reads better as "the ISS".
+    let name = match s.name.find(" (") {
+        Some(i) => &s.name[..i],
+        None => &s.name,
+    };
+    let position = if s.elevation >= 80.0 {
+        "directly overhead".to_string()
+    } else {
+        let height = if s.elevation < 15.0 {
+            "low".to_string()
+        } else if s.elevation > 60.0 {
+            "high".to_string()
+        } else {
+            format!("{:.0} degrees up", s.elevation)
+        };
+        format!("{} in the {}", height, cardinal_direction(s.azimuth))
+    };
+    let mut sentence = format!(
+        "Right now the {} is {}, {} away and moving {}.",
+        name,
+        position,
+        style.distance(s.range_km),
+        cardinal_direction(s.heading)
+    );
+    if let (true, Some(t)) = (s.rising(), s.culmination_in_seconds) {
+        let minutes = (t / 60.0).round();
+        if minutes < 1.0 {
+            sentence.push_str(" It's rising and will be highest in a few seconds.");
+        } else if minutes == 1.0 {
+            sentence.push_str(" It's rising and will be highest in about a minute.");
+        } else {
+            sentence.push_str(&format!(
+                " It's rising and will be highest in about {} minutes.",
+                minutes
+            ));
+        }
+    }
+    if s.decaying {
+        sentence.push_str(" Its orbit is decaying and it could re-enter soon.");
+    }
+    sentence
+}
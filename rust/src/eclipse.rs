@@ -0,0 +1,108 @@
+//! Predicting when satellites pass into and out of Earth's shadow.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::coords::ecef_to_geodetic;
+use crate::data::Satellite;
+use crate::passes::{crossing, seconds};
+use crate::sun::{shadow, Shadow};
+
+// How often to check which shadow the satellite is in, in seconds. LEO
+// penumbra crossings take around ten seconds, so this can't be much coarser
+// without two crossings landing in one step, which is still handled but
+// costs a second search.
+const STEP_SECONDS: f64 = 30.0;
+
+// How precisely to find each event, in seconds.
+const RESOLUTION_SECONDS: f64 = 0.1;
+
+/// Which shadow boundary a satellite crossed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EclipseKind {
+    PenumbraEntry,
+    UmbraEntry,
+    UmbraExit,
+    PenumbraExit,
+}
+
+/// A satellite crossing into or out of part of Earth's shadow.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EclipseEvent {
+    pub time: DateTime<Utc>,
+    pub kind: EclipseKind,
+    /// Height above the WGS84 ellipsoid in km at the time of the event.
+    pub alt_km: f64,
+}
+
+/// Every time `sat` enters or leaves the penumbra or umbra in the
+/// `duration_hours` after `start`, in order. `sun_ecef` gives the Sun's ECEF
+/// position in km at a time, usually [`crate::sun::sun_ecef`]. Times SGP4
+/// can't propagate to count as sunlit.
+///
+/// Only crossings during the window are events, so a satellite that starts
+/// out in shadow begins with an exit.
+pub fn find_eclipse_events(
+    sat: &Satellite,
+    sun_ecef: impl Fn(DateTime<Utc>) -> [f64; 3],
+    start: DateTime<Utc>,
+    duration_hours: f64,
+) -> Vec<EclipseEvent> {
+    let state = |t| match sat.ecef_at(t) {
+        Some((position, _)) => shadow(position, sun_ecef(t)),
+        None => Shadow::Sunlit,
+    };
+    let in_shadow = |t| state(t) != Shadow::Sunlit;
+    let in_umbra = |t| state(t) == Shadow::Umbra;
+    let step = seconds(STEP_SECONDS);
+    let resolution = seconds(RESOLUTION_SECONDS);
+    let end = start + seconds(duration_hours * 3600.0);
+
+    let mut events = Vec::new();
+    let mut event = |time, kind| {
+        let alt_km = sat
+            .ecef_at(time)
+            .map_or(f64::NAN, |(position, _)| ecef_to_geodetic(position).alt_km);
+        events.push(EclipseEvent { time, kind, alt_km });
+    };
+    let mut t = start;
+    let mut before = state(t);
+    while t < end {
+        let next = (t + step).min(end);
+        let after = state(next);
+        if after != before {
+            // Going out through the umbra's edge comes before the
+            // penumbra's, and going in after it.
+            let (was_shadowed, is_shadowed) = (before != Shadow::Sunlit, after != Shadow::Sunlit);
+            let (was_umbra, is_umbra) = (before == Shadow::Umbra, after == Shadow::Umbra);
+            if !was_shadowed && is_shadowed {
+                event(
+                    crossing(in_shadow, t, next, resolution),
+                    EclipseKind::PenumbraEntry,
+                );
+            }
+            if !was_umbra && is_umbra {
+                event(
+                    crossing(in_umbra, t, next, resolution),
+                    EclipseKind::UmbraEntry,
+                );
+            }
+            if was_umbra && !is_umbra {
+                event(
+                    crossing(|t| !in_umbra(t), t, next, resolution),
+                    EclipseKind::UmbraExit,
+                );
+            }
+            if was_shadowed && !is_shadowed {
+                event(
+                    crossing(|t| !in_shadow(t), t, next, resolution),
+                    EclipseKind::PenumbraExit,
+                );
+            }
+        }
+        before = after;
+        t = next;
+    }
+    events
+}
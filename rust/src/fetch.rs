@@ -0,0 +1,413 @@
+//! Downloading element sets.
+//!
+//! CelesTrak publishes its groups, like "stations" or "starlink", as OMM
+//! JSON to anyone who asks. space-track.org wants a login before it will
+//! hand out the catalog: a POST of the username and password to
+//! `/ajaxauth/login` sets a session cookie, and queries made with that
+//! cookie return OMM JSON. HTTP is done with reqwest, over rustls, so
+//! downloading needs nothing installed beyond this program.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::Deserialize;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::data::{merge_omm, OmmRecord};
+
+/// Where CelesTrak lives.
+pub const CELESTRAK_URL: &str = "https://celestrak.org";
+
+/// Where space-track.org lives.
+pub const SPACETRACK_URL: &str = "https://www.space-track.org";
+
+/// The latest element set for every object that hasn't decayed and has
+/// been tracked in the last month. This is the query space-track.org
+/// suggests for keeping a full catalog current.
+pub const DEFAULT_SPACETRACK_QUERY: &str = "/basicspacedata/query/class/gp/decay_date/null-val/epoch/%3Enow-30/orderby/norad_cat_id/format/json";
+
+/// A space-track.org login. The password is left out of `Debug` output so
+/// it can't end up in a log by accident.
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Credentials {
+    pub username: String,
+    password: String,
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<hidden>")
+            .finish()
+    }
+}
+
+impl Credentials {
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Credentials {
+        Credentials {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Reads `SPACETRACK_USERNAME` and `SPACETRACK_PASSWORD`, if both are
+    /// set.
+    pub fn from_env() -> Option<Credentials> {
+        let username = std::env::var("SPACETRACK_USERNAME").ok()?;
+        let password = std::env::var("SPACETRACK_PASSWORD").ok()?;
+        Some(Credentials::new(username, password))
+    }
+
+    /// Reads a TOML file with `username` and `password` keys.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Credentials> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Unable to read {}", path.display()))?;
+        toml::from_str(&text)
+            .with_context(|| format!("{} isn't a valid credentials file", path.display()))
+    }
+
+    /// Where [`Credentials::load`] looks for a credentials file when it
+    /// isn't told: spacetrack.toml in the user's config directory, e.g.
+    /// ~/.config/whatsoverhead/spacetrack.toml.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("whatsoverhead").join("spacetrack.toml"))
+    }
+
+    /// Finds a login: the environment variables if they're set, otherwise
+    /// the given credentials file, otherwise the default one.
+    pub fn load(file: Option<&Path>) -> Result<Credentials> {
+        if let Some(credentials) = Credentials::from_env() {
+            return Ok(credentials);
+        }
+        let path = match file {
+            Some(path) => path.to_path_buf(),
+            None => match Credentials::default_path() {
+                Some(path) if path.exists() => path,
+                _ => bail!(
+                    "space-track.org needs a login; set SPACETRACK_USERNAME and \
+                     SPACETRACK_PASSWORD, or put username and password in {}",
+                    Credentials::default_path().map_or("a credentials file".to_string(), |p| p
+                        .display()
+                        .to_string())
+                ),
+            },
+        };
+        Credentials::from_file(path)
+    }
+}
+
+/// How hard to try when a download fails in a way that might not happen
+/// again: a network error, 429 Too Many Requests, or a 5xx server error.
+/// Waits double after each attempt, starting from `base_delay`, with
+/// random jitter so that many clients that failed together don't all
+/// retry together. A Retry-After header on a 429 or 503 overrides that.
+/// No wait is ever longer than `max_delay`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts after the first; 0 gives up straight away.
+    pub retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// How long any one attempt can take before it counts as a failure.
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            retries: 3,
+            base_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(120),
+            // The full space-track.org catalog is tens of megabytes.
+            timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // How long to wait before retry number `attempt` (from 0).
+    fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(wait) = retry_after {
+            return wait.min(self.max_delay);
+        }
+        let backoff = self
+            .base_delay
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max_delay);
+        // Between half and all of the backoff. RandomState is seeded
+        // randomly per process, which is plenty for spreading retries out.
+        let random = RandomState::new().build_hasher().finish();
+        backoff.mul_f64(0.5 + 0.5 * (random as f64 / u64::MAX as f64))
+    }
+}
+
+// An HTTP response, as far as we care about one. Header names are
+// lowercased.
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    // Reads the whole body, which can fail if the connection drops or the
+    // timeout passes partway through.
+    fn read(response: reqwest::blocking::Response) -> reqwest::Result<Response> {
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+                (name.as_str().to_string(), value)
+            })
+            .collect();
+        let body = response.bytes()?.to_vec();
+        Ok(Response {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    // A Retry-After header as a wait: either a number of seconds or an HTTP
+    // date.
+    fn retry_after(&self) -> Option<Duration> {
+        let value = self.header("retry-after")?;
+        if let Ok(seconds) = value.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+        let when = DateTime::parse_from_rfc2822(value).ok()?;
+        (when.with_timezone(&Utc) - Utc::now()).to_std().ok()
+    }
+}
+
+// Whether a response is worth trying again.
+fn transient(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+/// Logs into space-track.org at `base_url` (normally [`SPACETRACK_URL`])
+/// and runs `query`, returning the element sets and the raw JSON they came
+/// from, so the caller can save exactly what was downloaded.
+pub fn fetch_spacetrack(
+    base_url: &str,
+    credentials: &Credentials,
+    query: &str,
+    retry: &RetryPolicy,
+) -> Result<(Vec<OmmRecord>, Vec<u8>)> {
+    let base_url = base_url.trim_end_matches('/');
+    let client = client(retry)?;
+    let login = request(
+        "space-track.org login",
+        retry,
+        client
+            .post(format!("{base_url}/ajaxauth/login"))
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(format!(
+                "identity={}&password={}",
+                percent_encode(&credentials.username),
+                percent_encode(&credentials.password)
+            )),
+    )?;
+    // A bad password still gets a 200, with {"Login":"Failed"} as the body.
+    let failed = String::from_utf8_lossy(&login.body).contains("Failed");
+    if login.status == 401 || login.status == 403 || failed {
+        bail!(
+            "space-track.org rejected the login for {}; check the username and password",
+            credentials.username
+        );
+    }
+    if login.status != 200 {
+        bail!(
+            "space-track.org login failed with HTTP status {}",
+            login.status
+        );
+    }
+    let cookies: Vec<&str> = login
+        .headers
+        .iter()
+        .filter(|(name, _)| name == "set-cookie")
+        .map(|(_, value)| value.split(';').next().unwrap_or("").trim())
+        .collect();
+    if cookies.is_empty() {
+        bail!("space-track.org accepted the login but didn't start a session");
+    }
+
+    let query = if query.starts_with('/') {
+        query.to_string()
+    } else {
+        format!("/{query}")
+    };
+    let response = request(
+        "space-track.org query",
+        retry,
+        client
+            .get(format!("{base_url}{query}"))
+            .header(reqwest::header::COOKIE, cookies.join("; ")),
+    )?;
+    match response.status {
+        200 => {}
+        401 | 403 => bail!("space-track.org didn't accept the session for the query"),
+        status => bail!("space-track.org query failed with HTTP status {}", status),
+    }
+    let records = serde_json::from_slice(&response.body)
+        .context("space-track.org didn't send a JSON array of OMM records")?;
+    Ok((records, response.body))
+}
+
+/// Downloads one CelesTrak group from `base_url` (normally
+/// [`CELESTRAK_URL`]) and returns its OMM records as JSON objects, with
+/// every field CelesTrak sent.
+pub fn fetch_celestrak_group(
+    base_url: &str,
+    group: &str,
+    retry: &RetryPolicy,
+) -> Result<Vec<serde_json::Value>> {
+    let url = format!(
+        "{}/NORAD/elements/gp.php?GROUP={}&FORMAT=json",
+        base_url.trim_end_matches('/'),
+        percent_encode(group)
+    );
+    let what = format!("CelesTrak download of {group}");
+    let response = request(&what, retry, client(retry)?.get(url))?;
+    if response.status != 200 {
+        bail!("{} failed with HTTP status {}", what, response.status);
+    }
+    // CelesTrak answers a group it doesn't have with a 200 and a line of
+    // text, "No GP data found".
+    serde_json::from_slice(&response.body).with_context(|| {
+        let text = String::from_utf8_lossy(&response.body);
+        format!(
+            "CelesTrak didn't send OMM JSON for {}: {}",
+            group,
+            text.lines().next().unwrap_or("").trim()
+        )
+    })
+}
+
+/// Downloads a CelesTrak group, like "active" for every working payload or
+/// "stations", from [`CELESTRAK_URL`] as elements ready for SGP4. No
+/// account is needed. Failures are retried as [`RetryPolicy::default`]
+/// says.
+pub fn load_from_celestrak_groups(group: &str) -> Result<Vec<sgp4::Elements>> {
+    load_celestrak_group(CELESTRAK_URL, group, &RetryPolicy::default())
+}
+
+/// [`load_from_celestrak_groups`] from any server that answers like
+/// CelesTrak.
+pub fn load_celestrak_group(
+    base_url: &str,
+    group: &str,
+    retry: &RetryPolicy,
+) -> Result<Vec<sgp4::Elements>> {
+    let records = fetch_celestrak_group(base_url, group, retry)?;
+    serde_json::from_value(serde_json::Value::Array(records))
+        .with_context(|| format!("CelesTrak sent elements for {group} that SGP4 can't read"))
+}
+
+/// What [`fetch_celestrak_groups`] got: the merged records, and the groups
+/// it couldn't download with the reason why.
+#[derive(Debug)]
+pub struct GroupDownload {
+    pub records: Vec<serde_json::Value>,
+    pub failed: Vec<(String, anyhow::Error)>,
+}
+
+/// Downloads several CelesTrak groups, one after another, and merges them
+/// with [`merge_omm`], so an object in two groups appears once. A group
+/// that fails even after retrying is left out and listed in `failed`
+/// rather than making the whole download fail.
+pub fn fetch_celestrak_groups(
+    base_url: &str,
+    groups: &[String],
+    retry: &RetryPolicy,
+) -> GroupDownload {
+    let mut downloaded = Vec::new();
+    let mut failed = Vec::new();
+    for group in groups {
+        match fetch_celestrak_group(base_url, group, retry) {
+            Ok(records) => downloaded.push(records),
+            Err(e) => failed.push((group.clone(), e)),
+        }
+    }
+    GroupDownload {
+        records: merge_omm(downloaded),
+        failed,
+    }
+}
+
+// A client that gives up on any one attempt after `retry.timeout`. Header
+// names go out capitalized the usual way, as curl and browsers send them.
+fn client(retry: &RetryPolicy) -> Result<Client> {
+    Client::builder()
+        .timeout(retry.timeout)
+        .http1_title_case_headers()
+        .build()
+        .context("Couldn't set up an HTTP client")
+}
+
+// Sends `request`, retrying transient failures as `retry` says. Once the
+// retries run out it's an error, naming `what` failed and the last status.
+fn request(what: &str, retry: &RetryPolicy, request: RequestBuilder) -> Result<Response> {
+    let send = || {
+        request
+            .try_clone()
+            .expect("request bodies are never streamed")
+            .send()
+            .and_then(Response::read)
+    };
+    for attempt in 0..retry.retries {
+        let retry_after = match send() {
+            Ok(response) if !transient(response.status) => return Ok(response),
+            Ok(response) if matches!(response.status, 429 | 503) => response.retry_after(),
+            Ok(_) | Err(_) => None,
+        };
+        thread::sleep(retry.delay(attempt, retry_after));
+    }
+    let attempts = retry.retries + 1;
+    match send() {
+        Ok(response) if transient(response.status) => bail!(
+            "{} failed with HTTP status {} after {} attempts",
+            what,
+            response.status,
+            attempts
+        ),
+        Ok(response) => Ok(response),
+        Err(e) => {
+            Err(anyhow::Error::new(e)
+                .context(format!("{} failed after {} attempts", what, attempts)))
+        }
+    }
+}
+
+// Percent-encodes a form field value.
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
@@ -0,0 +1,148 @@
+//! Great-circle math on a spherical Earth.
+//!
+//! These are for ground distances and directions between two lat/lon points,
+//! where a sphere of mean radius is plenty accurate (errors are under 0.5%).
+//! Anything that needs the real shape of the Earth, like look angles, uses
+//! the WGS84 ellipsoid instead.
+
+// Mean radius of the Earth in km.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+// Points closer than this (in km) are treated as coincident, and the bearing
+// between them as undefined.
+const COINCIDENT_KM: f64 = 1.0;
+
+/// Wraps a longitude in degrees into [-180, 180), so 359.2 becomes -0.8.
+pub fn normalize_longitude(lon: f64) -> f64 {
+    let l = (lon + 180.0).rem_euclid(360.0);
+    // As in normalize_bearing, rem_euclid can round up to exactly 360.
+    if l >= 360.0 {
+        -180.0
+    } else {
+        l - 180.0
+    }
+}
+
+/// How far east the second longitude is from the first, in degrees in
+/// [-180, 180). Going from 179.9 to -179.9 is 0.2 degrees east, not 359.8
+/// west.
+pub fn longitude_difference(lon1: f64, lon2: f64) -> f64 {
+    normalize_longitude(lon2 - lon1)
+}
+
+/// Great-circle distance in km between two coordinates.
+pub fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = longitude_difference(lon1, lon2).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+    // Rounding can push a just past 1 for nearly antipodal points.
+    let a = a.clamp(0.0, 1.0);
+    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+    EARTH_RADIUS_KM * c
+}
+
+/// Initial great-circle bearing from the first point to the second, in
+/// degrees clockwise from true north in [0, 360). This is the direction to
+/// set off in along the ground to travel `haversine_distance` to the second
+/// point, not the azimuth you'd look in to see something above it.
+///
+/// The bearing is meaningless when the points coincide (this returns 0) and
+/// when starting at a pole, where every direction is south (or north).
+pub fn bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lon = longitude_difference(lon1, lon2).to_radians();
+    let y = d_lon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+    normalize_bearing(y.atan2(x).to_degrees())
+}
+
+/// Wraps an angle in degrees into [0, 360).
+pub fn normalize_bearing(bearing: f64) -> f64 {
+    let b = bearing.rem_euclid(360.0);
+    // rem_euclid rounds tiny negative angles up to exactly 360.
+    if b >= 360.0 {
+        0.0
+    } else {
+        b
+    }
+}
+
+/// Like `bearing`, but returns None when the points are within a
+/// kilometer of each other and the direction isn't meaningful.
+pub fn ground_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> Option<f64> {
+    if haversine_distance(lat1, lon1, lat2, lon2) < COINCIDENT_KM {
+        None
+    } else {
+        Some(bearing(lat1, lon1, lat2, lon2))
+    }
+}
+
+/// Converts a bearing in degrees to one of the eight compass directions.
+pub fn cardinal_direction(bearing: f64) -> &'static str {
+    const DIRECTIONS: [&str; 8] = [
+        "north",
+        "northeast",
+        "east",
+        "southeast",
+        "south",
+        "southwest",
+        "west",
+        "northwest",
+    ];
+    DIRECTIONS[((normalize_bearing(bearing) + 22.5) / 45.0) as usize % 8]
+}
+
+/// A latitude/longitude box in degrees. A box whose `min_lon` is greater
+/// than its `max_lon` wraps across the antimeridian: 170 to -170 is the 20
+/// degrees around 180, not the 340 degrees around 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+}
+
+impl BoundingBox {
+    /// True if the point is inside the box or on its edge.
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        if lat < self.min_lat || lat > self.max_lat {
+            return false;
+        }
+        // How far east of min_lon the box reaches, and the point is.
+        let span = if self.min_lon <= self.max_lon {
+            self.max_lon - self.min_lon
+        } else {
+            self.max_lon - self.min_lon + 360.0
+        };
+        span >= 360.0 || (lon - self.min_lon).rem_euclid(360.0) <= span
+    }
+}
+
+/// A region of the ground that satellites' subpoints can be in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Area {
+    /// Everything within `radius_km` of a point, measured along the ground.
+    /// Unlike a box this stays round near the poles, where a degree of
+    /// longitude shrinks to nothing.
+    Circle {
+        lat: f64,
+        lon: f64,
+        radius_km: f64,
+    },
+    Box(BoundingBox),
+}
+
+impl Area {
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        match self {
+            Area::Circle {
+                lat: center_lat,
+                lon: center_lon,
+                radius_km,
+            } => haversine_distance(*center_lat, *center_lon, lat, lon) <= *radius_km,
+            Area::Box(b) => b.contains(lat, lon),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! The difference between height above the WGS84 ellipsoid, which is what
+//! the orbit math uses, and height above mean sea level, which is what maps
+//! and most people mean by altitude.
+//!
+//! The geoid undulation N is how far the geoid (mean sea level) is above
+//! the ellipsoid: h_ellipsoid = h_msl + N. It ranges from about -105 m
+//! south of India to +85 m over New Guinea. data/egm96-5deg.csv samples
+//! the EGM96 model (public domain, from the NGA) every 5 degrees, and we interpolate between the samples,
+//! which is good to a few meters almost everywhere. That's plenty for an
+//! observer's altitude; for satellites the difference doesn't matter.
+
+use serde::Deserialize;
+use std::str::FromStr;
+
+const GRID_STEP_DEG: f64 = 5.0;
+const GRID_LATS: usize = 37;
+const GRID_LONS: usize = 73;
+
+// Rows run from 90 to -90 degrees latitude, columns from -180 to 180
+// degrees longitude.
+include!(concat!(env!("OUT_DIR"), "/geoid.rs"));
+
+/// What an altitude is measured from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AltitudeDatum {
+    /// The WGS84 ellipsoid, as GPS receivers report internally.
+    Ellipsoid,
+    /// Mean sea level, as on maps.
+    #[default]
+    Msl,
+}
+
+impl FromStr for AltitudeDatum {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ellipsoid" => Ok(AltitudeDatum::Ellipsoid),
+            "msl" => Ok(AltitudeDatum::Msl),
+            _ => Err(format!(
+                "unknown altitude datum \"{}\" (expected ellipsoid or msl)",
+                s
+            )),
+        }
+    }
+}
+
+impl AltitudeDatum {
+    /// Converts an altitude in meters above this datum to height above the
+    /// ellipsoid.
+    pub fn to_ellipsoidal(self, lat: f64, lon: f64, alt_m: f64) -> f64 {
+        match self {
+            AltitudeDatum::Ellipsoid => alt_m,
+            AltitudeDatum::Msl => orthometric_to_ellipsoidal(lat, lon, alt_m),
+        }
+    }
+}
+
+/// The geoid undulation in meters at a latitude and longitude in degrees.
+pub fn undulation(lat: f64, lon: f64) -> f64 {
+    let row = ((90.0 - lat.clamp(-90.0, 90.0)) / GRID_STEP_DEG).min((GRID_LATS - 1) as f64);
+    let col = (lon + 180.0).rem_euclid(360.0) / GRID_STEP_DEG;
+    let (r0, c0) = (row.floor() as usize, col.floor() as usize);
+    let (r1, c1) = ((r0 + 1).min(GRID_LATS - 1), (c0 + 1).min(GRID_LONS - 1));
+    let (fr, fc) = (row - r0 as f64, col - c0 as f64);
+    let top = UNDULATION[r0][c0] * (1.0 - fc) + UNDULATION[r0][c1] * fc;
+    let bottom = UNDULATION[r1][c0] * (1.0 - fc) + UNDULATION[r1][c1] * fc;
+    top * (1.0 - fr) + bottom * fr
+}
+
+/// Converts a height above mean sea level to a height above the ellipsoid,
+/// both in meters.
+pub fn orthometric_to_ellipsoidal(lat: f64, lon: f64, h_msl: f64) -> f64 {
+    h_msl + undulation(lat, lon)
+}
+
+/// Converts a height above the ellipsoid to a height above mean sea level,
+/// both in meters.
+pub fn ellipsoidal_to_orthometric(lat: f64, lon: f64, h_ellipsoidal: f64) -> f64 {
+    h_ellipsoidal - undulation(lat, lon)
+}
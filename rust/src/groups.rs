@@ -0,0 +1,97 @@
+//! Grouping satellites into constellations by name, for summaries like "37
+//! STARLINK, 3 ONEWEB", and into formations by their orbits.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::data::semi_major_axis;
+
+/// The group for objects whose names don't start with a word.
+pub const OTHER: &str = "OTHER";
+
+/// Guesses the constellation or family a satellite belongs to from its
+/// name: the first word, before any space, dash or parenthesis, in upper
+/// case and without trailing digits. So "STARLINK-1007" and "Starlink 5000"
+/// are STARLINK, "FLOCK 4V-7" is FLOCK and "ISS (ZARYA)" is ISS. Names
+/// that don't start with letters, like bare international designators, are
+/// OTHER.
+pub fn constellation(name: &str) -> String {
+    let word = name
+        .trim()
+        .split([' ', '-', '(', '/'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(|c: char| c.is_ascii_digit());
+    if word.is_empty() || !word.starts_with(|c: char| c.is_alphabetic()) {
+        return OTHER.to_string();
+    }
+    word.to_uppercase()
+}
+
+/// Counts the names in each constellation.
+pub fn count<'a>(names: impl IntoIterator<Item = &'a str>) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for name in names {
+        *counts.entry(constellation(name)).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Groups satellites flying in the same shell, like the planes of a
+/// mega-constellation, by their inclination and semi-major axis. Each group
+/// is a list of indices into `elements`, in order, and the groups are
+/// ordered by their first index. Every satellite is in exactly one group,
+/// on its own if nothing else is nearby.
+///
+/// This is grid-based: orbits are binned into cells `inclination_tol_deg`
+/// by `altitude_tol_km` in size, and cells that touch, including
+/// diagonally, are joined. So satellites closer than the tolerances are
+/// always grouped together, and a chain of them can make a group wider
+/// than the tolerances.
+///
+/// Panics if either tolerance isn't positive.
+pub fn find_formations(
+    elements: &[sgp4::Elements],
+    inclination_tol_deg: f64,
+    altitude_tol_km: f64,
+) -> Vec<Vec<usize>> {
+    assert!(
+        inclination_tol_deg > 0.0 && altitude_tol_km > 0.0,
+        "formation tolerances must be positive"
+    );
+    let mut cells = HashMap::<(i64, i64), Vec<usize>>::new();
+    for (i, e) in elements.iter().enumerate() {
+        let cell = (
+            (e.inclination / inclination_tol_deg).floor() as i64,
+            (semi_major_axis(e.mean_motion) / altitude_tol_km).floor() as i64,
+        );
+        cells.entry(cell).or_default().push(i);
+    }
+
+    // Flood fill across neighbouring cells.
+    let mut groups = Vec::new();
+    let mut unvisited = cells.keys().copied().collect::<Vec<_>>();
+    unvisited.sort();
+    let mut seen = HashSet::new();
+    for start in unvisited {
+        if !seen.insert(start) {
+            continue;
+        }
+        let mut group = Vec::new();
+        let mut stack = vec![start];
+        while let Some((x, y)) = stack.pop() {
+            group.extend(&cells[&(x, y)]);
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    let next = (x + dx, y + dy);
+                    if cells.contains_key(&next) && seen.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+        group.sort();
+        groups.push(group);
+    }
+    groups.sort_by_key(|g| g[0]);
+    groups
+}
@@ -0,0 +1,74 @@
+//! Orbit propagation and coordinate math for working out which satellites
+//! are overhead.
+//!
+//! Load a catalog of [`Satellite`]s with [`load_catalog`] and ask
+//! [`overhead`] what's up for an [`Observer`], or [`predict_all_passes`]
+//! when things will rise and set:
+//!
+//! ```no_run
+//! use whatsoverhead::{load_catalog, overhead, Observer};
+//!
+//! let catalog = load_catalog("space-track-omm.json")?;
+//! let observer = Observer {
+//!     lat: 34.05,
+//!     lon: -118.24,
+//!     alt_m: 100.0,
+//!     atmosphere: None,
+//! };
+//! for report in overhead(&catalog.satellites, &observer, chrono::Utc::now(), 10.0) {
+//!     println!("{} is {:.0} km away", report.name, report.range_km);
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! For more control over what's reported, like ranking and brightness, build
+//! an [`OverheadQuery`]. To keep a set of satellites around for answering
+//! many queries, as an app would, wrap them in a [`Propagator`].
+//!
+//! The most commonly used types and functions are re-exported here. The
+//! modules have the rest:
+//!
+//! - [`data`]: reading OMM catalogs, the parsed-catalog cache, and
+//!   propagating a single [`Satellite`] with [`Satellite::ecef_at`]
+//! - [`coords`]: TEME, ECEF and geodetic frames, look angles and refraction
+//! - [`query`]: reports on what's overhead, close pairs and identifying
+//!   sightings
+//! - [`passes`]: rise, culmination and set times
+//! - [`eclipse`]: when satellites go into and out of Earth's shadow
+//! - [`fetch`]: downloading catalogs from space-track.org
+//! - [`selftest`]: checking propagation and the frame conversions against
+//!   published reference values
+//! - [`geo`], [`geoid`], [`magnetic`], [`sun`], [`magnitude`], [`orbit`]:
+//!   supporting geometry and physics
+//! - [`locations`], [`groups`], [`notable`], [`describe`], [`units`],
+//!   [`timeutil`]: things for presenting results to people
+
+pub mod coords;
+pub mod data;
+pub mod describe;
+pub mod eclipse;
+pub mod fetch;
+pub mod geo;
+pub mod geoid;
+pub mod groups;
+pub mod locations;
+pub mod magnetic;
+pub mod magnitude;
+pub mod notable;
+pub mod orbit;
+pub mod passes;
+pub mod query;
+pub mod selftest;
+pub mod sun;
+pub mod timeutil;
+pub mod units;
+
+pub use coords::{
+    ecef_to_geodetic, look_angles, observer_ecef, teme_to_ecef, teme_to_geodetic, Atmosphere,
+    Geodetic, LookAngles, Observer,
+};
+pub use data::{load_catalog, Catalog, Satellite};
+pub use passes::{contact_windows, find_passes, predict_all_passes, Pass, PassSearchConfig};
+pub use query::{
+    overhead, propagate, report, OverheadQuery, Propagated, Propagator, RankBy, SatelliteReport,
+};
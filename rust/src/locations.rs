@@ -0,0 +1,167 @@
+//! Named places, so people can say where they are without looking up
+//! coordinates.
+//!
+//! The list is data/cities.csv: about 900 large cities and national
+//! capitals, with coordinates from the GeoNames gazetteer (CC BY 4.0,
+//! <https://www.geonames.org>). build.rs compiles it into a static array
+//! sorted by name, so lookups are a binary search.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::Path;
+
+/// A city and where it is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct City {
+    pub name: &'static str,
+    /// State, province or similar, as GeoNames names it.
+    pub region: &'static str,
+    /// ISO 3166 two-letter country code.
+    pub country: &'static str,
+    /// Latitude in degrees.
+    pub lat: f64,
+    /// Longitude in degrees.
+    pub lon: f64,
+}
+
+include!(concat!(env!("OUT_DIR"), "/cities.rs"));
+
+impl std::fmt::Display for City {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.region.is_empty() || self.region == self.name {
+            write!(f, "{}, {}", self.name, self.country)
+        } else {
+            write!(f, "{}, {}, {}", self.name, self.region, self.country)
+        }
+    }
+}
+
+/// Every known city, sorted by name.
+pub fn all() -> &'static [City] {
+    CITIES
+}
+
+/// Looks up a city by name, ignoring case. If more than one city has the
+/// name, it can be narrowed down with a country code or region after a
+/// comma: "San Jose, CR" or "Hamilton, Ontario".
+pub fn find(query: &str) -> Result<&'static City> {
+    let (name, qualifier) = match query.split_once(',') {
+        Some((name, qualifier)) => (name.trim(), Some(qualifier.trim())),
+        None => (query.trim(), None),
+    };
+    let start = CITIES.partition_point(|c| cmp_ignore_case(c.name, name) == Ordering::Less);
+    let end = start
+        + CITIES[start..].partition_point(|c| cmp_ignore_case(c.name, name) == Ordering::Equal);
+    let matches = CITIES[start..end]
+        .iter()
+        .filter(|c| match qualifier {
+            Some(q) => c.country.eq_ignore_ascii_case(q) || c.region.eq_ignore_ascii_case(q),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+    match matches[..] {
+        [city] => Ok(city),
+        [] => bail!(
+            "Unknown location \"{}\" (run the locations subcommand to see the list)",
+            query
+        ),
+        _ => bail!(
+            "\"{}\" could be {}; add the country code to pick one",
+            query,
+            matches
+                .iter()
+                .map(|c| format!("\"{}, {}\"", c.name, c.country))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        ),
+    }
+}
+
+/// Somewhere to observe from, with a name to label its results with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Place {
+    pub name: String,
+    /// Latitude in degrees.
+    pub lat: f64,
+    /// Longitude in degrees.
+    pub lon: f64,
+    /// Altitude above sea level in meters, if known.
+    pub alt_m: Option<f64>,
+}
+
+impl From<&City> for Place {
+    fn from(city: &City) -> Place {
+        Place {
+            name: city.to_string(),
+            lat: city.lat,
+            lon: city.lon,
+            alt_m: None,
+        }
+    }
+}
+
+impl Place {
+    /// A place at the given coordinates, named after them.
+    pub fn at(lat: f64, lon: f64) -> Place {
+        Place {
+            name: format!("{}, {}", lat, lon),
+            lat,
+            lon,
+            alt_m: None,
+        }
+    }
+
+    /// Parses "lat, lon", "lat, lon, alt_m" or a city name as for
+    /// [`find`]. Any of them can start with a label and a colon, e.g.
+    /// "Goldstone: 35.4267, -116.89, 1000", to name the place something else.
+    pub fn parse(s: &str) -> Result<Place> {
+        let (label, rest) = match s.split_once(':') {
+            Some((label, rest)) => (Some(label.trim()), rest.trim()),
+            None => (None, s.trim()),
+        };
+        let numbers = rest
+            .split(',')
+            .map(|n| n.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>();
+        let mut place = match numbers.as_deref() {
+            Ok(&[lat, lon]) => Place::at(lat, lon),
+            Ok(&[lat, lon, alt_m]) => Place {
+                alt_m: Some(alt_m),
+                ..Place::at(lat, lon)
+            },
+            Ok(_) => bail!("\"{}\" should be lat, lon or lat, lon, altitude", s),
+            Err(_) => Place::from(find(rest)?),
+        };
+        if let Some(label) = label {
+            place.name = label.to_string();
+        }
+        Ok(place)
+    }
+}
+
+/// Reads a list of places, one per line, in any of the forms
+/// [`Place::parse`] understands. Blank lines and anything after a # are
+/// ignored.
+pub fn read_places(path: impl AsRef<Path>) -> Result<Vec<Place>> {
+    let path = path.as_ref();
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Unable to read {}", path.display()))?;
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            (!line.is_empty()).then_some((i, line))
+        })
+        .map(|(i, line)| {
+            Place::parse(line).with_context(|| format!("{} line {}", path.display(), i + 1))
+        })
+        .collect()
+}
+
+fn cmp_ignore_case(a: &str, b: &str) -> Ordering {
+    a.bytes()
+        .map(|c| c.to_ascii_lowercase())
+        .cmp(b.bytes().map(|c| c.to_ascii_lowercase()))
+}
@@ -0,0 +1,257 @@
+//! Magnetic declination from the World Magnetic Model, for pointing with a
+//! compass, which points at magnetic north rather than true north.
+//!
+//! The model's coefficients aren't bundled: NOAA publishes them as WMM.COF
+//! with each five-year release, and [`load`] reads that file. A model only
+//! describes the field from its epoch to five years later, and outside that
+//! range [`MagneticModel::field`] says so rather than extrapolating.
+
+use anyhow::{bail, ensure, Context, Result};
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use std::fs;
+use std::path::Path;
+
+use crate::coords::{self, Observer};
+
+/// The model's reference radius, in km.
+pub const REFERENCE_RADIUS_KM: f64 = 6371.2;
+
+/// How many years past its epoch a model is good for.
+pub const VALID_YEARS: f64 = 5.0;
+
+/// Spherical harmonic coefficients for the main field, in nT, and their
+/// rates of change, in nT per year.
+#[derive(Debug, Clone)]
+pub struct MagneticModel {
+    /// The model's name from the file, like "WMM-2025".
+    pub name: String,
+    /// The decimal year the coefficients are for.
+    pub epoch: f64,
+    degree: usize,
+    // Indexed by `index(n, m)`.
+    g: Vec<f64>,
+    h: Vec<f64>,
+    g_dot: Vec<f64>,
+    h_dot: Vec<f64>,
+}
+
+/// The field at a point, in nT, in the local geodetic frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MagneticField {
+    pub north: f64,
+    pub east: f64,
+    pub down: f64,
+}
+
+impl MagneticField {
+    /// Degrees from true north to magnetic north, positive to the east.
+    pub fn declination(&self) -> f64 {
+        self.east.atan2(self.north).to_degrees()
+    }
+
+    /// Degrees the field dips below the horizontal, positive downward.
+    pub fn inclination(&self) -> f64 {
+        self.down.atan2(self.north.hypot(self.east)).to_degrees()
+    }
+}
+
+impl MagneticModel {
+    /// The decimal year the model stops being valid.
+    pub fn valid_until(&self) -> f64 {
+        self.epoch + VALID_YEARS
+    }
+
+    /// Whether `t` is in the years the model describes.
+    pub fn covers(&self, t: DateTime<Utc>) -> bool {
+        let year = decimal_year(t);
+        year >= self.epoch && year < self.valid_until()
+    }
+
+    /// The field at a geodetic latitude and longitude in degrees and a
+    /// height above the WGS84 ellipsoid in km, or None if the model doesn't
+    /// cover `t`.
+    pub fn field(
+        &self,
+        lat: f64,
+        lon: f64,
+        alt_km: f64,
+        t: DateTime<Utc>,
+    ) -> Option<MagneticField> {
+        if !self.covers(t) {
+            return None;
+        }
+        let years = decimal_year(t) - self.epoch;
+        let p = coords::observer_ecef(&Observer {
+            lat,
+            lon,
+            alt_m: alt_km * 1000.0,
+            atmosphere: None,
+        });
+        let r = (p[0] * p[0] + p[1] * p[1] + p[2] * p[2]).sqrt();
+        let geocentric_lat = (p[2] / r).asin();
+        // Colatitude. At the poles the east component divides by zero, so
+        // stay a hair away from them, where declination means little
+        // anyway.
+        let (sin_theta, cos_theta) = (geocentric_lat.cos().max(1e-12), geocentric_lat.sin());
+        let (p_nm, dp_nm) = legendre(self.degree, sin_theta, cos_theta);
+        let lon = lon.to_radians();
+        let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+        for n in 1..=self.degree {
+            let scale = (REFERENCE_RADIUS_KM / r).powi(n as i32 + 2);
+            for m in 0..=n {
+                let i = index(n, m);
+                let g = self.g[i] + years * self.g_dot[i];
+                let h = self.h[i] + years * self.h_dot[i];
+                let (sin_ml, cos_ml) = (m as f64 * lon).sin_cos();
+                x += scale * (g * cos_ml + h * sin_ml) * dp_nm[i];
+                y += scale * m as f64 * (g * sin_ml - h * cos_ml) * p_nm[i] / sin_theta;
+                z -= scale * (n as f64 + 1.0) * (g * cos_ml + h * sin_ml) * p_nm[i];
+            }
+        }
+        // From the geocentric frame to the geodetic one, which are tilted
+        // by the difference between the two latitudes.
+        let (sin_psi, cos_psi) = (geocentric_lat - lat.to_radians()).sin_cos();
+        Some(MagneticField {
+            north: x * cos_psi - z * sin_psi,
+            east: y,
+            down: x * sin_psi + z * cos_psi,
+        })
+    }
+
+    /// Magnetic declination in degrees, positive east, or None if the model
+    /// doesn't cover `t`.
+    pub fn declination(&self, lat: f64, lon: f64, alt_km: f64, t: DateTime<Utc>) -> Option<f64> {
+        self.field(lat, lon, alt_km, t).map(|f| f.declination())
+    }
+}
+
+/// Turns an azimuth from true north into one from magnetic north, given
+/// the declination, both in degrees.
+pub fn magnetic_azimuth(true_azimuth: f64, declination: f64) -> f64 {
+    (true_azimuth - declination).rem_euclid(360.0)
+}
+
+/// The year, with the fraction of it that has passed, as the model's epoch
+/// and rates are given.
+pub fn decimal_year(t: DateTime<Utc>) -> f64 {
+    let year = t.year();
+    let days = if chrono::NaiveDate::from_ymd_opt(year, 2, 29).is_some() {
+        366.0
+    } else {
+        365.0
+    };
+    let seconds = t.ordinal0() as f64 * 86_400.0
+        + t.num_seconds_from_midnight() as f64
+        + t.nanosecond() as f64 * 1e-9;
+    year as f64 + seconds / (days * 86_400.0)
+}
+
+/// Reads a model in NOAA's WMM.COF format: a line with the epoch and the
+/// model's name, then one line per coefficient with n, m, g, h and their
+/// rates, ending with a line of 9s.
+pub fn parse_cof(text: &str) -> Result<MagneticModel> {
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let header = lines.next().context("The model is empty")?;
+    let mut fields = header.split_whitespace();
+    let epoch = fields
+        .next()
+        .and_then(|s| s.parse::<f64>().ok())
+        .with_context(|| {
+            format!(
+                "The model's first line should start with its epoch: {:?}",
+                header
+            )
+        })?;
+    let name = fields.next().unwrap_or("unnamed").to_string();
+    let mut terms = Vec::new();
+    for line in lines {
+        if line.trim_start().starts_with("9999") {
+            break;
+        }
+        let values = line.split_whitespace().collect::<Vec<_>>();
+        let parsed = (values.len() == 6)
+            .then(|| {
+                let n = values[0].parse::<usize>().ok()?;
+                let m = values[1].parse::<usize>().ok()?;
+                let mut c = [0.0; 4];
+                for (c, v) in c.iter_mut().zip(&values[2..]) {
+                    *c = v.parse().ok()?;
+                }
+                Some((n, m, c))
+            })
+            .flatten();
+        match parsed {
+            Some((n, m, c)) if n >= 1 && m <= n => terms.push((n, m, c)),
+            _ => bail!("Bad coefficient line in the model: {:?}", line),
+        }
+    }
+    let degree = terms.iter().map(|&(n, _, _)| n).max().unwrap_or(0);
+    ensure!(degree > 0, "The model has no coefficients");
+    let size = index(degree, degree) + 1;
+    let mut model = MagneticModel {
+        name,
+        epoch,
+        degree,
+        g: vec![0.0; size],
+        h: vec![0.0; size],
+        g_dot: vec![0.0; size],
+        h_dot: vec![0.0; size],
+    };
+    for (n, m, [g, h, g_dot, h_dot]) in terms {
+        let i = index(n, m);
+        model.g[i] = g;
+        model.h[i] = h;
+        model.g_dot[i] = g_dot;
+        model.h_dot[i] = h_dot;
+    }
+    Ok(model)
+}
+
+/// Reads a WMM.COF file.
+pub fn load(path: impl AsRef<Path>) -> Result<MagneticModel> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("Unable to read magnetic model {}", path.display()))?;
+    parse_cof(&text).with_context(|| format!("Malformed magnetic model {}", path.display()))
+}
+
+fn index(n: usize, m: usize) -> usize {
+    n * (n + 1) / 2 + m
+}
+
+// The Schmidt semi-normalized associated Legendre functions of cos(theta)
+// up to `degree`, and their derivatives with respect to theta.
+fn legendre(degree: usize, sin_theta: f64, cos_theta: f64) -> (Vec<f64>, Vec<f64>) {
+    let size = index(degree, degree) + 1;
+    let mut p = vec![0.0; size];
+    let mut dp = vec![0.0; size];
+    p[0] = 1.0;
+    for n in 1..=degree {
+        // The sectoral term first, then the rest of the column.
+        let (i, prev) = (index(n, n), index(n - 1, n - 1));
+        let k = if n == 1 {
+            1.0
+        } else {
+            ((2 * n - 1) as f64 / (2 * n) as f64).sqrt()
+        };
+        p[i] = k * sin_theta * p[prev];
+        dp[i] = k * (sin_theta * dp[prev] + cos_theta * p[prev]);
+        for m in 0..n {
+            let i = index(n, m);
+            let a = (2 * n - 1) as f64;
+            let b = (((n - 1) * (n - 1)) as f64 - (m * m) as f64)
+                .max(0.0)
+                .sqrt();
+            let c = ((n * n - m * m) as f64).sqrt();
+            let (p1, dp1) = (p[index(n - 1, m)], dp[index(n - 1, m)]);
+            let (p2, dp2) = if n >= 2 && m < n - 1 {
+                (p[index(n - 2, m)], dp[index(n - 2, m)])
+            } else {
+                (0.0, 0.0)
+            };
+            p[i] = (a * cos_theta * p1 - b * p2) / c;
+            dp[i] = (a * (cos_theta * dp1 - sin_theta * p1) - b * dp2) / c;
+        }
+    }
+    (p, dp)
+}
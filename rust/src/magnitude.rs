@@ -0,0 +1,38 @@
+//! Estimating how bright a satellite looks.
+
+use std::f64::consts::PI;
+
+// Standard magnitudes (at 1000 km range and 90 degrees phase) for objects we
+// know well, by NORAD ID.
+const BY_NORAD_ID: [(u64, f64); 3] = [
+    (25544, -1.3), // ISS (ZARYA)
+    (48274, -0.5), // CSS (TIANHE)
+    (20580, 2.2),  // HST
+];
+
+// Standard magnitudes for families of objects, by name prefix.
+const BY_NAME_PREFIX: [(&str, f64); 1] = [("STARLINK", 3.0)];
+
+/// Looks up the standard magnitude for a satellite, first by NORAD ID and
+/// then by name prefix. Returns None for objects not in the bundled table.
+pub fn standard_magnitude(norad_id: u64, name: &str) -> Option<f64> {
+    BY_NORAD_ID
+        .iter()
+        .find(|(id, _)| *id == norad_id)
+        .map(|(_, mag)| *mag)
+        .or_else(|| {
+            BY_NAME_PREFIX
+                .iter()
+                .find(|(prefix, _)| name.starts_with(prefix))
+                .map(|(_, mag)| *mag)
+        })
+}
+
+/// Apparent visual magnitude of a satellite with the given standard
+/// magnitude, seen from `range_km` away with the Sun-satellite-observer angle
+/// `phase_angle_deg`. Treats the satellite as a diffusely reflecting sphere.
+pub fn visual_magnitude(range_km: f64, phase_angle_deg: f64, standard_magnitude: f64) -> f64 {
+    let phase = phase_angle_deg.to_radians();
+    standard_magnitude + 5.0 * (range_km / 1000.0).log10()
+        - 2.5 * (phase.sin() + (PI - phase) * phase.cos()).log10()
+}
@@ -1,48 +1,1982 @@
 use anyhow::{Context, Result};
-use chrono::{Datelike, TimeZone, Timelike};
-use chrono_tz::UTC;
+use chrono::{DateTime, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::Instant;
+
+mod config;
+mod sink;
+
+use whatsoverhead::coords::{self, Atmosphere, Observer};
+use whatsoverhead::data::{self, Catalog, CatalogStats, Satellite};
+use whatsoverhead::fetch;
+use whatsoverhead::geo::{cardinal_direction, Area, BoundingBox};
+use whatsoverhead::geoid::{self, AltitudeDatum};
+use whatsoverhead::groups;
+use whatsoverhead::locations::{self, City, Place};
+use whatsoverhead::magnetic;
+use whatsoverhead::notable::Aliases;
+use whatsoverhead::orbit::OrbitClass;
+use whatsoverhead::passes::{self, Pass, PassSearchConfig};
+use whatsoverhead::query::{
+    self, ElementsReport, EphemerisPoint, OverheadQuery, Propagator, RankBy, SatelliteReport,
+};
+use whatsoverhead::selftest::{self, StageResult};
+use whatsoverhead::timeutil::{self, display_time, rfc3339};
+use whatsoverhead::units::{NumberStyle, Units};
+
+const DEFAULT_LAT: f64 = 34.56;
+const DEFAULT_LON: f64 = -118.76;
+
+#[derive(Parser)]
+#[command(
+    about = "Find out what's overhead, in space",
+    after_help = "Observer location, altitude, units and minimum elevation can also be \
+set in a TOML config file. Command-line flags override the config file, which \
+overrides the built-in defaults."
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Config file to read defaults from [default: whatsoverhead/config.toml
+    /// in the user's config directory, if it exists]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    /// Path to the OMM JSON catalog [default: space-track-omm.json if it
+    /// exists, otherwise a few bright satellites built into the program]
+    #[arg(long, global = true)]
+    catalog: Option<String>,
+    /// Fail when there's no catalog file, instead of using the built-in
+    /// element sets, which may be weeks old
+    #[arg(long, global = true)]
+    no_bundled: bool,
+    /// Observer location as a city name, e.g. "Los Angeles" or "San Jose,
+    /// CR"; --lat and --lon override it
+    #[arg(long, global = true)]
+    location: Option<String>,
+    /// Observer latitude in degrees; repeat --lat and --lon to report for
+    /// several locations at once [default: 34.56]
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    lat: Vec<f64>,
+    /// Whether --lat is geodetic, the latitude GPS receivers and maps give,
+    /// or geocentric, the angle from the equator seen from Earth's center.
+    /// They differ by up to 0.19 degrees. Everything else, like locations
+    /// and the config file, is geodetic
+    #[arg(long, global = true, value_enum, default_value_t = LatType::Geodetic)]
+    lat_type: LatType,
+    /// Observer longitude in degrees [default: -118.76]
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    lon: Vec<f64>,
+    /// Report for each of the locations in this file, one per line, as
+    /// "lat, lon", "lat, lon, altitude" or a city name, optionally after a
+    /// label and a colon ("Goldstone: 35.4267, -116.89, 1000")
+    #[arg(long, global = true, value_name = "FILE")]
+    locations: Option<PathBuf>,
+    /// Observer altitude in meters, above the datum given by --alt-datum
+    /// [default: 0]
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    alt: Option<f64>,
+    /// What --alt is measured from: msl (mean sea level, as on maps) or
+    /// ellipsoid (the WGS84 ellipsoid, as GPS works internally) [default:
+    /// msl]
+    #[arg(long, global = true)]
+    alt_datum: Option<AltitudeDatum>,
+    /// Report satellite altitudes above mean sea level rather than above
+    /// the WGS84 ellipsoid. The two differ by at most about 100 m
+    #[arg(long, global = true)]
+    geoid: bool,
+    /// Units for distances, altitudes and speeds in text and CSV output:
+    /// metric, or imperial for miles, feet and miles per second [default:
+    /// metric]. JSON is always km, km/s and degrees
+    #[arg(long)]
+    units: Option<Units>,
+    /// Decimal places for angles and distances in text and CSV output
+    /// [default: whatever each number usually gets]
+    #[arg(long)]
+    precision: Option<usize>,
+    /// Print a natural-language sentence about the closest satellite,
+    /// suitable for piping into text-to-speech
+    #[arg(long)]
+    describe: bool,
+    /// Also give where each reported satellite is from this many minutes
+    /// before to this many after, every --trail-step seconds, including
+    /// where it's below the horizon: extra lines in text, extra rows in CSV
+    /// and a "trail" array in JSON
+    #[arg(long, value_name = "MINUTES")]
+    trail: Option<f64>,
+    /// Seconds between --trail samples
+    #[arg(long, default_value_t = 60.0)]
+    trail_step: f64,
+    /// Output format
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    format: Format,
+    /// Write the results to this file instead of stdout, creating any
+    /// directories it's in
+    #[arg(long, short, global = true)]
+    output: Option<PathBuf>,
+    /// Only look at the satellite with this NORAD catalog number; can be
+    /// repeated or given a comma-separated list
+    #[arg(long, global = true, value_name = "ID", value_delimiter = ',')]
+    norad_id: Vec<u64>,
+    /// Only look at the satellites whose NORAD catalog numbers are listed in
+    /// this file, one per line
+    #[arg(long, global = true, value_name = "FILE")]
+    norad_ids: Option<PathBuf>,
+    /// Only look at one satellite, given by NORAD ID, by an alias like "iss"
+    /// or "hubble" (see --notable), or by name (any part of it, ignoring
+    /// case, as long as it matches just one)
+    #[arg(
+        long,
+        global = true,
+        value_name = "ID-OR-NAME",
+        conflicts_with_all = ["norad_id", "norad_ids"]
+    )]
+    sat: Option<String>,
+    /// Only look at satellites whose names contain this, ignoring case, as
+    /// long as there are no more than 10 of them; otherwise list them
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAME",
+        conflicts_with_all = ["sat", "norad_id", "norad_ids"]
+    )]
+    satellite: Option<String>,
+    /// Only look at satellites in this orbit regime: leo (mean altitude
+    /// under 2,000 km), meo (between leo and geo), geo (0.99 to 1.01 rev/day
+    /// with eccentricity under 0.01) or heo (eccentricity of 0.25 or more,
+    /// or under 0.99 rev/day)
+    #[arg(long, global = true)]
+    orbit_class: Option<OrbitClass>,
+    /// Only report satellites at least this many degrees above the horizon
+    /// [default: 0, or -90 when picking satellites with --sat, --satellite,
+    /// --norad-id or --norad-ids, except for passes]
+    #[arg(long, global = true, allow_hyphen_values = true)]
+    min_elevation: Option<f64>,
+    /// Instead of reporting what's overhead now, find the moment in the next
+    /// 24 hours when the most satellites are above --min-elevation
+    #[arg(long)]
+    peak: bool,
+    /// Instead of reporting individual satellites, find pairs above
+    /// --min-elevation that are within --max-separation-deg of each other
+    /// in the sky, for catching conjunctions. This compares every pair, so
+    /// it only looks at satellites already above the horizon
+    #[arg(long, alias = "conjunctions", conflicts_with = "peak")]
+    pairs: bool,
+    /// Largest angle between two satellites, in degrees, for --pairs
+    #[arg(long, alias = "max-sep", default_value_t = 2.0)]
+    max_separation_deg: f64,
+    /// Time step in seconds for --peak
+    #[arg(long, default_value_t = 60.0)]
+    step: f64,
+    /// Instead of reporting what's overhead now, find when the satellite
+    /// picked with --sat is closest in the next --duration minutes, and
+    /// how far away it is then
+    #[arg(long, requires = "sat", conflicts_with_all = ["peak", "pairs"])]
+    closest_approach: bool,
+    /// How many minutes ahead --closest-approach looks
+    #[arg(long, default_value_t = 60.0)]
+    duration: f64,
+    /// Number of satellites to report, in --rank-by order, or of passes to
+    /// report, soonest first [default: 1 satellite for text, otherwise all]
+    #[arg(long, global = true)]
+    top: Option<usize>,
+    /// Keep running, clearing the screen and reprinting what's overhead
+    /// every this many seconds until Ctrl-C
+    #[arg(long, value_name = "SECONDS", conflicts_with_all = ["time", "peak", "output"])]
+    watch: Option<f64>,
+    /// Time to compute positions for, as RFC 3339 (e.g.
+    /// 2025-06-15T18:30:00Z), or as a local time in --timezone without an
+    /// offset (e.g. 2025-06-15T11:30) [default: now]
+    #[arg(long, global = true)]
+    time: Option<String>,
+    /// Keep objects whose decay date is before --time, for historical
+    /// queries
+    #[arg(long, global = true)]
+    include_decayed: bool,
+    /// Instead of listing satellites, count how many of each constellation
+    /// are above --min-elevation (with json, alongside the list)
+    #[arg(long, conflicts_with = "pairs")]
+    summary: bool,
+    /// Instead of reporting on satellites, describe the catalog: how many
+    /// objects are in it after the usual filters, how old their elements
+    /// are at --time, and what kinds of orbit they're in. Nothing is
+    /// propagated, and no location is needed
+    #[arg(long, conflicts_with_all = ["pairs", "peak", "summary", "watch", "closest_approach"])]
+    stats: bool,
+    /// Only report the objects with aliases: the crewed stations, Hubble and
+    /// Envisat by default, plus any in the config file's [aliases] table.
+    /// If none of them are above --min-elevation, report the whole catalog
+    /// instead
+    #[arg(
+        long,
+        conflicts_with_all = ["sat", "satellite", "norad_id", "norad_ids", "stats", "pairs", "peak", "closest_approach"]
+    )]
+    notable: bool,
+    /// Keep every element set for objects that appear in the catalog more
+    /// than once, instead of only the newest
+    #[arg(long, global = true)]
+    keep_duplicates: bool,
+    /// Only report satellites at least this bright (lower is brighter).
+    /// Satellites without a known standard magnitude, or in Earth's
+    /// shadow, are dropped
+    #[arg(long, allow_hyphen_values = true)]
+    max_magnitude: Option<f64>,
+    /// Only report satellites that could be seen with the eye: sunlit,
+    /// against a dark enough sky
+    #[arg(long)]
+    visible_only: bool,
+    /// What order to report satellites in, best first: slant-range, ground
+    /// (distance to the subpoint), elevation or magnitude [default: ground
+    /// with --within-km or --bbox, otherwise slant-range]
+    #[arg(long)]
+    rank_by: Option<RankBy>,
+    /// Report every satellite whose subpoint is within this many km of the
+    /// location over the ground, whether or not it's above the horizon
+    #[arg(long, value_name = "KM", conflicts_with_all = ["bbox", "peak", "pairs"])]
+    within_km: Option<f64>,
+    /// Report every satellite whose subpoint is inside this box, given as
+    /// degrees min_lon,min_lat,max_lon,max_lat. A min_lon greater than
+    /// max_lon wraps across the antimeridian
+    #[arg(
+        long,
+        value_name = "BOX",
+        value_parser = parse_bbox,
+        allow_hyphen_values = true,
+        conflicts_with_all = ["peak", "pairs"]
+    )]
+    bbox: Option<BoundingBox>,
+    /// Report geometric elevations, without correcting for atmospheric
+    /// refraction near the horizon
+    #[arg(long, global = true)]
+    no_refraction: bool,
+    /// Keep a parsed copy of the catalog next to it (as <catalog>.cache) and
+    /// load that instead when the catalog hasn't changed
+    #[arg(long)]
+    cache: bool,
+    /// Show times in this time zone, as an IANA name like America/New_York,
+    /// instead of UTC, and read times given without an offset in it. JSON
+    /// output stays in UTC unless --local-times is given
+    #[arg(long, global = true, default_value = "UTC", value_parser = parse_timezone)]
+    timezone: Tz,
+    /// Write the timestamps in JSON output in --timezone too, with their
+    /// offsets
+    #[arg(long, global = true)]
+    local_times: bool,
+    /// UT1 - UTC in seconds, from IERS Bulletin A, used for the Earth's
+    /// rotation angle. Leaving it at 0 costs at most about 400 m of
+    /// longitude
+    #[arg(long, global = true, default_value_t = 0.0, allow_hyphen_values = true)]
+    delta_ut1: f64,
+    /// World Magnetic Model coefficients, the WMM.COF file NOAA publishes,
+    /// for giving azimuths from magnetic north alongside true ones, to
+    /// point with a compass
+    #[arg(long, global = true, value_name = "FILE")]
+    wmm: Option<PathBuf>,
+    /// How many times to retry a download after a network error, or a 429
+    /// or 5xx response, waiting longer each time
+    #[arg(long, global = true, default_value_t = 3)]
+    retries: u32,
+    /// Print how long loading and propagation took to stderr
+    #[arg(long, global = true)]
+    timing: bool,
+    /// Print why records were skipped and satellites couldn't be
+    /// propagated, grouped by what went wrong
+    #[arg(long, global = true)]
+    show_errors: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the cities --location knows about
+    Locations {
+        /// Only list cities whose name, region or country contains this
+        search: Option<String>,
+    },
+    /// Work out which satellites could be something seen in the sky at
+    /// --time from the observer's location
+    Identify {
+        /// Observed azimuth, in degrees clockwise from north
+        #[arg(long)]
+        az: f64,
+        /// Observed elevation, in degrees above the horizon
+        #[arg(long, allow_hyphen_values = true)]
+        el: f64,
+        /// How far from the observed position a satellite can be, in
+        /// degrees; widened automatically if nothing is that close
+        #[arg(long, default_value_t = 2.0)]
+        tolerance_deg: f64,
+    },
+    /// Predict when satellites will rise above --min-elevation and set
+    /// again, starting at --time
+    Passes {
+        /// How far ahead to look, in hours
+        #[arg(long, default_value_t = 24.0)]
+        hours: f64,
+        /// How often to check whether each satellite is up, in seconds;
+        /// passes shorter than this can be missed
+        #[arg(long, default_value_t = 60.0)]
+        coarse_step: f64,
+        /// How precisely to find rise, culmination and set times, in seconds
+        #[arg(long, default_value_t = 1.0)]
+        refine_step: f64,
+    },
+    /// Write one satellite's position and ECEF velocity at regular steps, as
+    /// CSV unless --format json is given. Pick the satellite with --sat. The
+    /// columns are time, lat, lon, alt_km, x_km, y_km, z_km, vx_kms, vy_kms
+    /// and vz_kms
+    Ephemeris {
+        /// First time to write, like --time [default: --time, or now]
+        #[arg(long)]
+        start: Option<String>,
+        /// Last time to write, like --time
+        #[arg(long)]
+        end: String,
+        /// Time between rows, in seconds
+        #[arg(long, default_value_t = 60.0)]
+        step: f64,
+    },
+    /// Show the orbital elements one satellite was loaded with, and the
+    /// period, size and height of the orbit they describe. Pick the
+    /// satellite with --sat
+    Elements,
+    /// Check propagation and the frame conversions against published
+    /// reference values, Vallado's SGP4 verification set among them, and
+    /// fail if any stage is further off than it should be
+    Selftest,
+    /// Download the current catalog from space-track.org and save it as
+    /// --catalog. Log in with the SPACETRACK_USERNAME and SPACETRACK_PASSWORD
+    /// environment variables, or a credentials file with username and
+    /// password in it
+    FetchSpacetrack {
+        /// The credentials file [default: spacetrack.toml in the config
+        /// directory, e.g. ~/.config/whatsoverhead/spacetrack.toml]
+        #[arg(long)]
+        credentials: Option<PathBuf>,
+        /// The space-track.org query to download, as a URL path
+        #[arg(long, default_value = fetch::DEFAULT_SPACETRACK_QUERY)]
+        query: String,
+    },
+    /// Download CelesTrak groups, e.g. --group stations --group starlink,
+    /// and save them merged into one catalog as --catalog. A group that
+    /// can't be downloaded is reported and left out
+    FetchCelestrak {
+        /// A CelesTrak group to download; give it more than once for more
+        #[arg(long = "group", required = true)]
+        groups: Vec<String>,
+        /// Fail, and save nothing, if any group can't be downloaded
+        #[arg(long)]
+        strict: bool,
+    },
+}
+
+fn parse_bbox(s: &str) -> Result<BoundingBox> {
+    let parts = s
+        .split(',')
+        .map(|p| p.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()?;
+    let [min_lon, min_lat, max_lon, max_lat] = parts[..] else {
+        anyhow::bail!("expected min_lon,min_lat,max_lon,max_lat");
+    };
+    anyhow::ensure!(
+        (-90.0..=90.0).contains(&min_lat) && (-90.0..=90.0).contains(&max_lat),
+        "latitudes must be between -90 and 90"
+    );
+    anyhow::ensure!(min_lat <= max_lat, "min_lat can't be greater than max_lat");
+    Ok(BoundingBox {
+        min_lon,
+        min_lat,
+        max_lon,
+        max_lat,
+    })
+}
+
+fn parse_timezone(s: &str) -> Result<Tz> {
+    s.parse::<Tz>().map_err(|_| {
+        anyhow::anyhow!(
+            "unknown time zone {:?}; use an IANA name like Europe/Paris",
+            s
+        )
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LatType {
+    Geodetic,
+    Geocentric,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Text,
+    /// A header row, then one row per satellite. The columns are always, in
+    /// this order: timestamp (RFC 3339 in --timezone), norad_id, name, lat,
+    /// lon, alt_km, az_deg, el_deg, range_km, range_rate_kms,
+    /// epoch_age_hours, ground_distance_km, ground_bearing_deg (blank when
+    /// the satellite is straight up), decaying, magnitude (blank if unknown
+    /// or eclipsed), el_rate_deg_s, culmination_in_s (blank unless it's
+    /// rising and peaks in the next 15 minutes), visibility (daylight,
+    /// eclipsed or visible), az_rate_deg_s, and, when reporting for several
+    /// locations, location. With --units imperial, alt_ft, range_mi,
+    /// range_rate_mis and ground_distance_mi take the places of the km ones.
+    /// With --trail, each satellite's row is followed by one for each
+    /// sample of its trail, with only the time, position and look angles
+    /// filled in, and trail_offset_s and above_horizon are added at the end.
+    /// az_deg is from true north; with --wmm, az_true_deg (the same as
+    /// az_deg), az_magnetic_deg and magnetic_declination_deg come last
+    Csv,
+    /// An array of reports with every computed field, distances in km and
+    /// angles in degrees whatever --units and --precision say; unknown
+    /// values are null. With several locations,
+    /// an array of {"location", "satellites"} objects instead
+    Json,
+    /// A GeoJSON FeatureCollection for putting on a map, for reports of
+    /// what's overhead: a Point at each satellite's subpoint, in the
+    /// "satellites" layer, and the day/night boundary at --time as a
+    /// LineString in the "terminator" layer. The layer is in each feature's
+    /// properties, with the same units as json
+    Geojson,
+    /// One JSON object per line, each flushed as it's written: the report
+    /// for each satellite, with the same fields as json and a "location"
+    /// field added when reporting for several locations, or each step of
+    /// an ephemeris. With --watch every round's reports follow the last, so
+    /// whatever was written before a run is stopped is whole lines
+    Ndjson,
+}
 
 fn main() -> Result<()> {
-    // Load the OMM data from the JSON file
-    let data = fs::read_to_string("space-track-omm.json").expect("Unable to read file");
-    let satellites: Vec<sgp4::Elements> =
-        serde_json::from_str(&data).expect("JSON was not well-formatted");
-    println!("Loaded {} satellites", satellites.len());
+    let args = Args::parse();
+    anyhow::ensure!(
+        args.delta_ut1.abs() <= 1.0,
+        "--delta-ut1 should be between -1 and 1 seconds, got {}",
+        args.delta_ut1
+    );
+    let Some(path) = &args.output else {
+        return run(&mut io::stdout().lock(), &args);
+    };
+    let mut file = create_output(path)?;
+    run(&mut file, &args)?;
+    file.flush()
+        .with_context(|| format!("Unable to write {}", path.display()))?;
+    eprintln!("Wrote {}", path.display());
+    Ok(())
+}
+
+// Writes pretty JSON, with its times in --timezone if --local-times is
+// given.
+fn write_json<T: Serialize + ?Sized>(out: &mut dyn Write, args: &Args, value: &T) -> Result<()> {
+    // Going through a Value sorts the keys, so only do it when the times
+    // need rewriting.
+    if args.local_times {
+        let mut value = serde_json::to_value(value)?;
+        timeutil::localize_json(&mut value, args.timezone);
+        serde_json::to_writer_pretty(&mut *out, &value)?;
+    } else {
+        serde_json::to_writer_pretty(&mut *out, value)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+// Writes one line of NDJSON, like write_json, and flushes it so readers get
+// whole lines as they come.
+fn write_ndjson<T: Serialize + ?Sized>(out: &mut dyn Write, args: &Args, value: &T) -> Result<()> {
+    if args.local_times {
+        let mut value = serde_json::to_value(value)?;
+        timeutil::localize_json(&mut value, args.timezone);
+        serde_json::to_writer(&mut *out, &value)?;
+    } else {
+        serde_json::to_writer(&mut *out, value)?;
+    }
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
+
+// Creates the --output file, and any directories it's in that don't exist
+// yet.
+fn create_output(path: &Path) -> Result<io::BufWriter<fs::File>> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).with_context(|| format!("Unable to create {}", dir.display()))?;
+    }
+    let file =
+        fs::File::create(path).with_context(|| format!("Unable to write {}", path.display()))?;
+    Ok(io::BufWriter::new(file))
+}
+
+// Does whatever the arguments ask, writing the results to `out`. Messages
+// about how it's going still go to stderr.
+fn run(out: &mut dyn Write, args: &Args) -> Result<()> {
+    if let Some(Command::Locations { search }) = &args.command {
+        return list_locations(out, search.as_deref());
+    }
+    anyhow::ensure!(
+        args.format != Format::Geojson
+            || (args.command.is_none()
+                && !(args.pairs || args.peak || args.summary || args.closest_approach)),
+        "--format geojson only works for reports of what's overhead"
+    );
+    anyhow::ensure!(
+        args.format != Format::Ndjson
+            || matches!(args.command, None | Some(Command::Ephemeris { .. }))
+                && !(args.pairs || args.peak || args.summary || args.closest_approach),
+        "--format ndjson only works for reports of what's overhead and ephemeris"
+    );
+    if let Some(Command::Selftest) = &args.command {
+        return print_selftest(out, args, &selftest::run());
+    }
+    if let Some(Command::FetchSpacetrack { credentials, query }) = &args.command {
+        let path = Path::new(args.catalog.as_deref().unwrap_or(DEFAULT_CATALOG));
+        let credentials = fetch::Credentials::load(credentials.as_deref())?;
+        let retry = fetch::RetryPolicy {
+            retries: args.retries,
+            ..Default::default()
+        };
+        let (records, json) =
+            fetch::fetch_spacetrack(fetch::SPACETRACK_URL, &credentials, query, &retry)?;
+        fs::write(path, json).with_context(|| format!("Unable to write {}", path.display()))?;
+        writeln!(
+            out,
+            "Saved {} element sets to {}",
+            records.len(),
+            path.display()
+        )?;
+        return Ok(());
+    }
+    if let Some(Command::FetchCelestrak { groups, strict }) = &args.command {
+        let path = Path::new(args.catalog.as_deref().unwrap_or(DEFAULT_CATALOG));
+        let retry = fetch::RetryPolicy {
+            retries: args.retries,
+            ..Default::default()
+        };
+        let download = fetch::fetch_celestrak_groups(fetch::CELESTRAK_URL, groups, &retry);
+        for (group, e) in &download.failed {
+            eprintln!("Missing group {}: {:#}", group, e);
+        }
+        if download.failed.len() == groups.len() {
+            anyhow::bail!("None of the groups could be downloaded");
+        }
+        if *strict && !download.failed.is_empty() {
+            anyhow::bail!(
+                "{} of {} groups couldn't be downloaded; not saving {}",
+                download.failed.len(),
+                groups.len(),
+                path.display()
+            );
+        }
+        let json = serde_json::to_vec(&download.records)?;
+        fs::write(path, json).with_context(|| format!("Unable to write {}", path.display()))?;
+        let saved: Vec<&str> = groups
+            .iter()
+            .filter(|g| download.failed.iter().all(|(f, _)| f != *g))
+            .map(String::as_str)
+            .collect();
+        writeln!(
+            out,
+            "Saved {} element sets from {} to {}",
+            download.records.len(),
+            saved.join(", "),
+            path.display()
+        )?;
+        return Ok(());
+    }
+    let config = config::load(args.config.as_deref())?;
+    let mut aliases = Aliases::built_in();
+    aliases.extend(&config.aliases);
+    let wmm = args
+        .wmm
+        .as_deref()
+        .or(config.wmm.as_deref())
+        .map(magnetic::load)
+        .transpose()?;
+    // A location from the command line beats coordinates from the config
+    // file, but explicit coordinates beat either kind of location.
+    let location = args.location.as_deref().map(locations::find).transpose()?;
+    let config_location = config
+        .location
+        .as_deref()
+        .map(locations::find)
+        .transpose()?;
+    let style = NumberStyle {
+        units: args.units.or(config.units).unwrap_or_default(),
+        precision: args.precision,
+    };
+    let output = sink::Output {
+        style,
+        magnetic: wmm.is_some(),
+    };
+    let alt_datum = args.alt_datum.or(config.alt_datum).unwrap_or_default();
+    // When someone asks about particular satellites, tell them where those
+    // are even if they're below the horizon.
+    let selected = selected_ids(args)?;
+    let searching_passes = matches!(args.command, Some(Command::Passes { .. }));
+    let default_min_elevation = if picking(args) && !searching_passes {
+        -90.0
+    } else {
+        0.0
+    };
+    let min_elevation = args
+        .min_elevation
+        .or(config.min_elevation)
+        .unwrap_or(default_min_elevation);
 
-    // Given coordinates
-    let lat = 34.56;
-    let lon = -118.76;
+    let catalog = load(args)?;
+    eprintln!("Loaded {} satellites", catalog.satellites.len());
+    if !catalog.rejected.is_empty() {
+        let ids = catalog
+            .rejected
+            .iter()
+            .map(|r| {
+                r.norad_id
+                    .map_or("unknown".to_string(), |id| id.to_string())
+            })
+            .collect::<Vec<_>>();
+        eprintln!(
+            "Skipped {} records that couldn't be used (NORAD IDs: {})",
+            ids.len(),
+            ids.join(", ")
+        );
+        if args.show_errors {
+            print_error_groups(
+                catalog
+                    .rejected
+                    .iter()
+                    .map(|r| (r.reason.as_str(), r.norad_id)),
+            );
+        }
+    }
+    let mut satellites = catalog.satellites;
+    if !args.keep_duplicates {
+        let dropped = data::drop_duplicates(&mut satellites);
+        if dropped > 0 {
+            eprintln!(
+                "Skipped {} older element sets for objects that appear more than once (use --keep-duplicates to keep them)",
+                dropped
+            );
+        }
+    }
+    if let Some(ids) = &selected {
+        satellites.retain(|sat| ids.contains(&sat.norad_id));
+        for id in ids {
+            if !satellites.iter().any(|sat| sat.norad_id == *id) {
+                eprintln!("NORAD ID {} isn't in the catalog", id);
+            }
+        }
+    }
+    if let Some(class) = args.orbit_class {
+        satellites.retain(|sat| sat.orbit_class() == class);
+    }
+    if let Some(query) = &args.sat {
+        satellites = vec![match aliases.resolve(query) {
+            Some(id) => find_alias(&satellites, query, id)?.clone(),
+            None => data::find_satellite(&satellites, query)?.clone(),
+        }];
+    }
+    if let Some(id) = args.satellite.as_deref().and_then(|q| aliases.resolve(q)) {
+        let query = args.satellite.as_deref().unwrap_or_default();
+        satellites = vec![find_alias(&satellites, query, id)?.clone()];
+    } else if let Some(query) = &args.satellite {
+        let matches = data::find_satellites_by_name(&satellites, query);
+        anyhow::ensure!(
+            !matches.is_empty(),
+            "No satellite's name contains \"{}\"",
+            query.trim()
+        );
+        anyhow::ensure!(
+            matches.len() <= MAX_NAME_MATCHES,
+            "\"{}\" matches {} satellites; use more of the name, or --sat with a NORAD ID:\n{}{}",
+            query.trim(),
+            matches.len(),
+            data::list_satellites(matches.iter().take(MAX_LISTED).map(|&i| &satellites[i])),
+            if matches.len() > MAX_LISTED {
+                format!("\n  and {} more", matches.len() - MAX_LISTED)
+            } else {
+                String::new()
+            }
+        );
+        satellites = matches.iter().map(|&i| satellites[i].clone()).collect();
+    }
 
     // Get the current time
-    let now = chrono::Utc::now();
-    // Iterate over the satellites, propagate their orbits, and find the closest one
-    let predictions = satellites
+    let now = match &args.time {
+        Some(time) => timeutil::parse_time(time, args.timezone).context("Bad --time")?,
+        None => chrono::Utc::now(),
+    };
+    if !args.include_decayed {
+        let skipped = data::drop_decayed(&mut satellites, now);
+        if skipped > 0 {
+            eprintln!(
+                "Skipped {} objects that decayed before {} (use --include-decayed to keep them)",
+                skipped, now
+            );
+        }
+    }
+
+    if args.stats {
+        anyhow::ensure!(
+            args.command.is_none(),
+            "--stats describes the catalog, so it doesn't go with a command"
+        );
+        return print_stats(out, args, &data::catalog_stats(&satellites, now));
+    }
+
+    if let Some(Command::Ephemeris { start, end, step }) = &args.command {
+        let [sat] = &satellites[..] else {
+            anyhow::bail!(
+                "ephemeris needs exactly one satellite, but {} are selected; pick one with --sat",
+                satellites.len()
+            );
+        };
+        let start = match start {
+            Some(start) => timeutil::parse_time(start, args.timezone).context("Bad --start")?,
+            None => now,
+        };
+        let end = timeutil::parse_time(end, args.timezone).context("Bad --end")?;
+        let step = *step;
+        ensure_step("--step", step)?;
+        anyhow::ensure!(end >= start, "--end must not be before --start");
+        let points = query::ephemeris(sat, start, end, step, args.delta_ut1)?;
+        return print_ephemeris(out, args, &points);
+    }
+
+    if let Some(Command::Elements) = &args.command {
+        let [sat] = &satellites[..] else {
+            anyhow::bail!(
+                "elements needs exactly one satellite, but {} are selected; pick one with --sat",
+                satellites.len()
+            );
+        };
+        return print_elements(out, args, &query::elements(sat, now), style);
+    }
+
+    let sites = places(args, &config, location, config_location)?
+        .into_iter()
+        .map(|place| {
+            let alt_m = place.alt_m.or(args.alt).or(config.alt).unwrap_or(0.0);
+            let observer = Observer {
+                lat: place.lat,
+                lon: place.lon,
+                alt_m: alt_datum.to_ellipsoidal(place.lat, place.lon, alt_m),
+                atmosphere: (!args.no_refraction).then_some(Atmosphere::STANDARD),
+            };
+            let declination = wmm.as_ref().and_then(|model| {
+                model.declination(observer.lat, observer.lon, observer.alt_m / 1000.0, now)
+            });
+            (place, observer, declination)
+        })
+        .collect::<Vec<_>>();
+    if let Some(model) = wmm.as_ref().filter(|model| !model.covers(now)) {
+        eprintln!(
+            "{} covers {:.1} to {:.1}, not {}, so azimuths are only given from true north",
+            model.name,
+            model.epoch,
+            model.valid_until(),
+            now
+        );
+    }
+    anyhow::ensure!(
+        sites.len() == 1
+            || !(args.peak
+                || args.pairs
+                || args.closest_approach
+                || matches!(args.command, Some(Command::Identify { .. }))),
+        "--peak, --pairs, --closest-approach and identify work with one location at a time"
+    );
+    anyhow::ensure!(
+        args.command.is_none() || (args.within_km.is_none() && args.bbox.is_none()),
+        "--within-km and --bbox only work for reports on what's overhead"
+    );
+    anyhow::ensure!(
+        args.within_km.is_none_or(|km| km > 0.0),
+        "--within-km must be positive"
+    );
+    let observer = &sites[0].1;
+
+    if args.closest_approach {
+        anyhow::ensure!(args.duration > 0.0, "--duration must be positive");
+        let [sat] = &satellites[..] else {
+            anyhow::bail!(
+                "--closest-approach needs exactly one satellite, but {} are selected; pick one with --sat",
+                satellites.len()
+            );
+        };
+        let end = now + chrono::Duration::milliseconds((args.duration * 60_000.0) as i64);
+        let (time, range_km) = passes::closest_approach(
+            coords::observer_ecef(observer),
+            sat,
+            now,
+            end,
+            passes::approach_step(sat),
+            args.delta_ut1,
+        )?;
+        let approach = ClosestApproach {
+            norad_id: sat.norad_id,
+            name: &sat.name,
+            time,
+            range_km,
+        };
+        return match args.format {
+            Format::Geojson | Format::Ndjson => unreachable!("checked at the start of run"),
+            Format::Json => write_json(out, args, &approach),
+            Format::Csv => {
+                let mut w = csv::Writer::from_writer(&mut *out);
+                w.write_record(["norad_id", "name", "time", "range_km"])?;
+                w.write_record([
+                    sat.norad_id.to_string(),
+                    sat.name.clone(),
+                    rfc3339(time, args.timezone),
+                    style.number(range_km, 3),
+                ])?;
+                w.flush()?;
+                Ok(())
+            }
+            Format::Text => {
+                writeln!(
+                    out,
+                    "{} ({}) is closest at {}, {} away",
+                    sat.name,
+                    sat.norad_id,
+                    display_time(time, args.timezone),
+                    style.distance(range_km)
+                )?;
+                Ok(())
+            }
+        };
+    }
+    if args.peak {
+        ensure_step("--step", args.step)?;
+        let query = overhead_query(args, observer, min_elevation, now);
+        let (t, count, ids) = query.peak(&satellites, args.step)?;
+        if args.format == Format::Text {
+            writeln!(
+                out,
+                "{} satellites are above {} degrees at {}",
+                count,
+                min_elevation,
+                display_time(t, args.timezone)
+            )?;
+            for id in ids {
+                writeln!(out, "{}", id)?;
+            }
+            return Ok(());
+        }
+        // Full reports for everything that's up at the peak.
+        let mut reports = query.at(t).run(&satellites);
+        if args.geoid {
+            to_mean_sea_level(&mut reports);
+        }
+        if let Some(n) = args.top {
+            reports.truncate(n);
+        }
+        let mut sink = sink::for_format(out, args, output, false, t);
+        sink.location(&sites[0].0, None)?;
+        for report in &reports {
+            sink.report(report)?;
+        }
+        return sink.finish();
+    }
+    if let Some(Command::Identify {
+        az,
+        el,
+        tolerance_deg,
+    }) = args.command
+    {
+        let mut id = overhead_query(args, observer, min_elevation, now).identify(
+            &satellites,
+            az,
+            el,
+            tolerance_deg,
+        );
+        if args.geoid {
+            to_mean_sea_level(id.candidates.iter_mut().map(|c| &mut c.report));
+        }
+        return print_identification(out, args, &id, az, el, style);
+    }
+    if let Some(Command::Passes {
+        hours,
+        coarse_step,
+        refine_step,
+    }) = args.command
+    {
+        anyhow::ensure!(hours > 0.0, "--hours must be positive");
+        ensure_step("--coarse-step", coarse_step)?;
+        ensure_step("--refine-step", refine_step)?;
+        let config = PassSearchConfig {
+            coarse_step_seconds: coarse_step,
+            refine_step_seconds: refine_step,
+            window_hours: hours,
+            min_elevation_deg: min_elevation,
+            delta_ut1: args.delta_ut1,
+        };
+        let start = Instant::now();
+        let progress = Progress::new(
+            args,
+            "Searched",
+            Some(satellites.len() * sites.len()),
+            "satellites",
+        );
+        let mut sections = sites
+            .iter()
+            .map(|(place, observer, _)| {
+                let passes = passes::predict_all_passes_with_progress(
+                    &satellites,
+                    observer,
+                    now,
+                    &config,
+                    || progress.tick(),
+                );
+                (place, passes)
+            })
+            .collect::<Vec<_>>();
+        progress.finish();
+        if args.timing {
+            eprintln!(
+                "Searched {} satellites for passes over {} locations in {:.1} ms",
+                satellites.len(),
+                sites.len(),
+                ms_since(start)
+            );
+        }
+        for (_, passes) in &mut sections {
+            if let Some(n) = args.top {
+                passes.truncate(n);
+            }
+        }
+        if let [(_, passes)] = &sections[..] {
+            return print_passes(out, args, &[(None, passes.as_slice())], &config, style);
+        }
+        let labelled = sections
+            .iter()
+            .map(|(place, passes)| (Some(*place), passes.as_slice()))
+            .collect::<Vec<_>>();
+        return print_passes(out, args, &labelled, &config, style);
+    }
+    anyhow::ensure!(
+        !args.notable || args.command.is_none(),
+        "--notable only works for reports on what's overhead"
+    );
+    let propagator = if args.notable {
+        let ids = aliases.ids();
+        let notable = Propagator::new(
+            satellites
+                .iter()
+                .filter(|sat| ids.contains(&sat.norad_id))
+                .cloned()
+                .collect(),
+        );
+        // Only the elevations matter here; the full reports come later.
+        let propagated = notable.propagate(now);
+        let up = sites.iter().any(|(_, observer, _)| {
+            propagated
+                .iter()
+                .any(|p| coords::look_angles(observer, p.position).elevation >= min_elevation)
+        });
+        if up {
+            notable
+        } else {
+            eprintln!(
+                "None of the notable objects are above {} degrees, so reporting on the whole catalog",
+                min_elevation
+            );
+            Propagator::new(satellites)
+        }
+    } else {
+        Propagator::new(satellites)
+    }
+    .with_delta_ut1(args.delta_ut1);
+    match args.watch {
+        Some(seconds) => watch(
+            out,
+            args,
+            &propagator,
+            &sites,
+            min_elevation,
+            output,
+            seconds,
+        ),
+        None => print_overhead(out, args, &propagator, &sites, min_elevation, output, now),
+    }
+}
+
+// The question every report on what's overhead asks, from the flags, for one
+// observer. --top is left to the callers, since summaries and --pairs look
+// at everything.
+fn overhead_query(
+    args: &Args,
+    observer: &Observer,
+    min_elevation: f64,
+    now: DateTime<Utc>,
+) -> OverheadQuery {
+    let mut query = OverheadQuery::new(observer.clone())
+        .at(now)
+        .min_elevation(min_elevation)
+        .visible_only(args.visible_only)
+        .delta_ut1(args.delta_ut1);
+    if let Some(area) = area(args, observer) {
+        query = query.within(area).rank_by(RankBy::Ground);
+    }
+    if let Some(max) = args.max_magnitude {
+        query = query.max_magnitude(max);
+    }
+    if let Some(rank_by) = args.rank_by {
+        query = query.rank_by(rank_by);
+    }
+    query
+}
+
+// A place to report for, the observer there, and the magnetic declination
+// there at --time if --wmm was given and covers it.
+type Site = (Place, Observer, Option<f64>);
+
+// Clears the screen and reprints what's overhead every `seconds` until
+// Ctrl-C.
+fn watch(
+    out: &mut dyn Write,
+    args: &Args,
+    propagator: &Propagator,
+    sites: &[Site],
+    min_elevation: f64,
+    output: sink::Output,
+    seconds: f64,
+) -> Result<()> {
+    anyhow::ensure!(seconds > 0.0, "--watch must be positive");
+    let (stop, stopped) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = stop.send(());
+    })?;
+    let interval = std::time::Duration::from_secs_f64(seconds);
+    loop {
+        let now = Utc::now();
+        if args.format != Format::Ndjson {
+            write!(out, "\x1b[2J\x1b[H")?;
+            writeln!(out, "{}\n", display_time(now, args.timezone))?;
+        }
+        print_overhead(out, args, propagator, sites, min_elevation, output, now)?;
+        out.flush()?;
+        match stopped.recv_timeout(interval) {
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Ok(()) | Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn print_overhead(
+    out: &mut dyn Write,
+    args: &Args,
+    propagator: &Propagator,
+    sites: &[Site],
+    min_elevation: f64,
+    output: sink::Output,
+    now: DateTime<Utc>,
+) -> Result<()> {
+    anyhow::ensure!(
+        !(args.summary && args.format == Format::Csv),
+        "--summary supports text and json output"
+    );
+    // Propagate every satellite once, then find the closest ones that are
+    // above the horizon from each location.
+    let start = Instant::now();
+    let progress = Progress::new(
+        args,
+        "Propagated",
+        Some(propagator.satellites().len()),
+        "satellites",
+    );
+    let (mut propagated, errors) = propagator.propagate_with_progress(now, || progress.tick());
+    progress.finish();
+    if !errors.is_empty() {
+        eprintln!(
+            "Couldn't propagate {} satellites to {}{}",
+            errors.len(),
+            now,
+            if args.show_errors {
+                ":"
+            } else {
+                " (use --show-errors to see why)"
+            }
+        );
+        if args.show_errors {
+            print_error_groups(errors.iter().map(|e| (e.kind.as_str(), Some(e.norad_id))));
+        }
+    }
+    let implausible = query::drop_implausible(&mut propagated);
+    if !implausible.is_empty() {
+        let skipped = implausible
+            .iter()
+            .map(|i| format!("{} ({}) at {:.0} km", i.name, i.norad_id, i.alt_km))
+            .collect::<Vec<_>>();
+        eprintln!(
+            "Skipped {} satellites whose altitude at {} is below {} km or above {} km, which isn't physically plausible: {}",
+            implausible.len(),
+            now,
+            query::MIN_PLAUSIBLE_ALT_KM,
+            query::MAX_PLAUSIBLE_ALT_KM,
+            skipped.join(", ")
+        );
+    }
+    let mut sections = sites
+        .iter()
+        .map(|(place, observer, declination)| {
+            let (satellites, mut reports): (Vec<_>, Vec<_>) =
+                overhead_query(args, observer, min_elevation, now)
+                    .run_propagated_with_satellites(&propagated)
+                    .into_iter()
+                    .unzip();
+            if let Some(declination) = *declination {
+                for r in &mut reports {
+                    r.magnetic_declination = Some(declination);
+                    r.magnetic_azimuth = Some(magnetic::magnetic_azimuth(r.azimuth, declination));
+                }
+            }
+            if args.geoid {
+                to_mean_sea_level(&mut reports);
+            }
+            let summary = args.summary.then(|| summarize(&reports, min_elevation));
+            (place, satellites, reports, summary)
+        })
+        .collect::<Vec<_>>();
+    if args.timing {
+        eprintln!(
+            "Propagated {} satellites for {} locations in {:.1} ms",
+            propagator.satellites().len(),
+            sites.len(),
+            ms_since(start)
+        );
+    }
+    if args.pairs {
+        return print_pairs(out, args, &sections[0].2, output.style);
+    }
+    for (_, satellites, reports, _) in &mut sections {
+        let top = match (args.top, args.format) {
+            (Some(n), _) => n,
+            (None, Format::Csv | Format::Json | Format::Geojson | Format::Ndjson) => reports.len(),
+            (None, Format::Text) if !picking(args) && area(args, &sites[0].1).is_none() => 1,
+            (None, Format::Text) => reports.len(),
+        };
+        satellites.truncate(top);
+        reports.truncate(top);
+    }
+    if let Some(minutes) = args.trail {
+        anyhow::ensure!(minutes >= 0.0, "--trail can't be negative");
+        ensure_step("--trail-step", args.trail_step)?;
+        for ((_, satellites, reports, _), (_, observer, _)) in sections.iter_mut().zip(sites) {
+            for (sat, r) in satellites.iter().zip(reports.iter_mut()) {
+                r.trail = query::trail(
+                    sat,
+                    observer,
+                    r.time,
+                    minutes,
+                    args.trail_step,
+                    propagator.delta_ut1(),
+                );
+                if args.geoid {
+                    for p in &mut r.trail {
+                        p.alt_km =
+                            geoid::ellipsoidal_to_orthometric(p.lat, p.lon, p.alt_km * 1000.0)
+                                / 1000.0;
+                    }
+                }
+            }
+        }
+    }
+    let mut sink = sink::for_format(out, args, output, sections.len() > 1, now);
+    for (place, _, reports, summary) in &sections {
+        sink.location(place, summary.as_ref())?;
+        for report in reports {
+            sink.report(report)?;
+        }
+    }
+    sink.finish()
+}
+
+// Prints how many of something went wrong each way, most common first, with
+// the NORAD IDs involved.
+fn print_error_groups<'a>(errors: impl IntoIterator<Item = (&'a str, Option<u64>)>) {
+    let mut groups = BTreeMap::<&str, Vec<String>>::new();
+    for (kind, norad_id) in errors {
+        groups
+            .entry(kind)
+            .or_default()
+            .push(norad_id.map_or("unknown".to_string(), |id| id.to_string()));
+    }
+    let mut groups = groups.into_iter().collect::<Vec<_>>();
+    groups.sort_by_key(|(_, ids)| std::cmp::Reverse(ids.len()));
+    for (kind, ids) in groups {
+        eprintln!("  {}: {} (NORAD IDs: {})", kind, ids.len(), ids.join(", "));
+    }
+}
+
+// What --closest-approach found, for --format json.
+#[derive(Serialize)]
+struct ClosestApproach<'a> {
+    norad_id: u64,
+    name: &'a str,
+    time: DateTime<Utc>,
+    range_km: f64,
+}
+
+// The passes over one location, for --format json.
+#[derive(Serialize)]
+struct LocationPasses<'a> {
+    location: &'a Place,
+    passes: &'a [Pass],
+}
+
+// What --summary shows: the number of satellites in each constellation,
+// and a line like "Above 10°: 37 STARLINK, 3 ONEWEB, 1 ISS (ZARYA), 12
+// other".
+struct Summary {
+    counts: BTreeMap<String, usize>,
+    line: String,
+}
+
+// How many constellations the summary line names before lumping the rest
+// in with other.
+const SUMMARY_GROUPS: usize = 5;
+
+fn summarize(reports: &[SatelliteReport], min_elevation: f64) -> Summary {
+    let counts = groups::count(reports.iter().map(|r| r.name.as_str()));
+    let mut biggest = counts
         .iter()
-        .map(|sat| {
-            let constants = sgp4::Constants::from_elements(sat).unwrap();
-            let sat_ndt = sat.datetime;
-            let sat_utc_dt = chrono::Utc.from_local_datetime(&sat_ndt).unwrap();
-            let time_diff = now - sat_utc_dt;
-            let epoch_minutes = (time_diff.num_seconds() as f64) / 60.0;
-            println!("Epoch: {:?} {}", sat_utc_dt, epoch_minutes);
-            let prediction = constants.propagate(epoch_minutes).unwrap();
-            // The sgp4 docs say "The position and velocity are given in the
-            // True Equator, Mean Equinox (TEME) of epoch reference frame" but
-            // we need to convert to lat, lon, altitude.
+        .filter(|(group, _)| *group != groups::OTHER)
+        .collect::<Vec<_>>();
+    biggest.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    let mut other = counts.get(groups::OTHER).copied().unwrap_or(0);
+    let mut parts = Vec::new();
+    for (i, (group, &n)) in biggest.into_iter().enumerate() {
+        if i >= SUMMARY_GROUPS {
+            other += n;
+            continue;
+        }
+        // A constellation of one reads better as the satellite's name.
+        let label = match n {
+            1 => reports
+                .iter()
+                .find(|r| groups::constellation(&r.name) == *group)
+                .map_or(group.as_str(), |r| r.name.as_str()),
+            _ => group.as_str(),
+        };
+        parts.push(format!("{} {}", n, label));
+    }
+    if other > 0 {
+        parts.push(format!("{} other", other));
+    }
+    if parts.is_empty() {
+        parts.push("nothing".to_string());
+    }
+    Summary {
+        line: format!("Above {}°: {}", min_elevation, parts.join(", ")),
+        counts,
+    }
+}
 
+// Where to observe from. That's usually one place, worked out from the
+// flags and the config file, but --locations or repeated --lat and --lon
+// give several.
+fn places(
+    args: &Args,
+    config: &config::Config,
+    location: Option<&City>,
+    config_location: Option<&City>,
+) -> Result<Vec<Place>> {
+    if let Some(path) = &args.locations {
+        anyhow::ensure!(
+            args.location.is_none() && args.lat.is_empty() && args.lon.is_empty(),
+            "--locations can't be combined with --location, --lat or --lon"
+        );
+        let places = locations::read_places(path)?;
+        anyhow::ensure!(
+            !places.is_empty(),
+            "{} doesn't list any locations",
+            path.display()
+        );
+        return Ok(places);
+    }
+    if args.lat.len() > 1 || args.lon.len() > 1 {
+        anyhow::ensure!(
+            args.lat.len() == args.lon.len(),
+            "Give a --lon for every --lat when reporting for several locations"
+        );
+        return Ok(args
+            .lat
+            .iter()
+            .zip(&args.lon)
+            .map(|(&lat, &lon)| Place::at(geodetic_lat(args, lat), lon))
+            .collect());
+    }
+    let lat = args
+        .lat
+        .first()
+        .map(|&lat| geodetic_lat(args, lat))
+        .or(location.map(|c| c.lat))
+        .or(config.lat)
+        .or(config_location.map(|c| c.lat))
+        .unwrap_or(DEFAULT_LAT);
+    let lon = args
+        .lon
+        .first()
+        .copied()
+        .or(location.map(|c| c.lon))
+        .or(config.lon)
+        .or(config_location.map(|c| c.lon))
+        .unwrap_or(DEFAULT_LON);
+    Ok(vec![Place::at(lat, lon)])
+}
+
+// A --lat as a geodetic latitude, which is what observers work with.
+fn geodetic_lat(args: &Args, lat: f64) -> f64 {
+    match args.lat_type {
+        LatType::Geodetic => lat,
+        LatType::Geocentric => coords::geocentric_to_geodetic_latitude(lat),
+    }
+}
+
+// For --geoid.
+fn to_mean_sea_level<'a>(reports: impl IntoIterator<Item = &'a mut SatelliteReport>) {
+    for r in reports {
+        r.alt_km = geoid::ellipsoidal_to_orthometric(r.lat, r.lon, r.alt_km * 1000.0) / 1000.0;
+    }
+}
+
+// The region --within-km or --bbox asks about, if either was given. A
+// --within-km circle is centered on the observer.
+fn area(args: &Args, observer: &Observer) -> Option<Area> {
+    match (args.within_km, args.bbox) {
+        (Some(radius_km), _) => Some(Area::Circle {
+            lat: observer.lat,
+            lon: observer.lon,
+            radius_km,
+        }),
+        (None, Some(b)) => Some(Area::Box(b)),
+        (None, None) => None,
+    }
+}
+
+// Whether particular satellites were asked for, in which case they're
+// reported wherever they are.
+fn picking(args: &Args) -> bool {
+    args.sat.is_some()
+        || args.satellite.is_some()
+        || !args.norad_id.is_empty()
+        || args.norad_ids.is_some()
+}
+
+// The satellite an alias stands for.
+fn find_alias<'a>(satellites: &'a [Satellite], alias: &str, id: u64) -> Result<&'a Satellite> {
+    satellites
+        .iter()
+        .find(|sat| sat.norad_id == id)
+        .with_context(|| {
+            format!(
+                "\"{}\" is NORAD ID {}, which isn't in the catalog",
+                alias.trim(),
+                id
+            )
         })
+}
+
+// The NORAD IDs from --norad-id and --norad-ids, or None if neither was
+// given.
+fn selected_ids(args: &Args) -> Result<Option<BTreeSet<u64>>> {
+    if args.norad_id.is_empty() && args.norad_ids.is_none() {
+        return Ok(None);
+    }
+    let mut ids = args.norad_id.iter().copied().collect::<BTreeSet<_>>();
+    if let Some(path) = &args.norad_ids {
+        ids.extend(data::read_norad_ids(path)?);
+    }
+    Ok(Some(ids))
+}
+
+fn list_locations(out: &mut dyn Write, search: Option<&str>) -> Result<()> {
+    let search = search.map(|s| s.to_lowercase());
+    let matches = |city: &City| match &search {
+        Some(s) => city.to_string().to_lowercase().contains(s),
+        None => true,
+    };
+    for city in locations::all().iter().filter(|c| matches(c)) {
+        writeln!(
+            out,
+            "{:<40} {:>9.4} {:>10.4}",
+            city.to_string(),
+            city.lat,
+            city.lon
+        )?;
+    }
+    Ok(())
+}
+
+fn print_identification(
+    out: &mut dyn Write,
+    args: &Args,
+    id: &query::Identification,
+    az: f64,
+    el: f64,
+    style: NumberStyle,
+) -> Result<()> {
+    anyhow::ensure!(
+        args.format != Format::Csv,
+        "identify supports text and json output"
+    );
+    if args.format == Format::Json {
+        write_json(out, args, id)?;
+        return Ok(());
+    }
+    if id.widened {
+        if id.candidates.is_empty() {
+            writeln!(
+                out,
+                "Nothing was within {} degrees of azimuth {:.1}, elevation {:.1}, even after widening the search.",
+                id.tolerance_deg, az, el
+            )?;
+            return Ok(());
+        }
+        writeln!(
+            out,
+            "Nothing was within {} degrees, so widened the search to {} degrees.",
+            id.tolerance_deg / query::WIDENED_TOLERANCE_FACTOR,
+            id.tolerance_deg
+        )?;
+    }
+    for c in &id.candidates {
+        let r = &c.report;
+        let brightness = match (r.sunlit, r.magnitude) {
+            (false, _) => "in Earth's shadow".to_string(),
+            (true, Some(m)) => format!("sunlit, about magnitude {:.1}", m),
+            (true, None) => "sunlit".to_string(),
+        };
+        writeln!(
+            out,
+            "{} ({}) is {} degrees away at azimuth {}, elevation {}, {} away; {}",
+            r.name,
+            r.norad_id,
+            style.number(c.separation_deg, 2),
+            style.number(r.azimuth, 1),
+            style.number(r.elevation, 1),
+            style.distance(r.range_km),
+            brightness
+        )?;
+    }
+    Ok(())
+}
+
+// Prints passes, in sections labelled by location when there's more than
+// one.
+fn print_passes(
+    out: &mut dyn Write,
+    args: &Args,
+    sections: &[(Option<&Place>, &[Pass])],
+    config: &PassSearchConfig,
+    style: NumberStyle,
+) -> Result<()> {
+    let labelled = sections.iter().any(|(place, _)| place.is_some());
+    match args.format {
+        Format::Geojson | Format::Ndjson => unreachable!("checked at the start of run"),
+        Format::Json if labelled => {
+            let nested = sections
+                .iter()
+                .map(|(place, passes)| LocationPasses {
+                    location: place.expect("every section is labelled"),
+                    passes,
+                })
+                .collect::<Vec<_>>();
+            write_json(out, args, &nested)?;
+        }
+        Format::Json => {
+            write_json(out, args, sections[0].1)?;
+        }
+        Format::Csv => {
+            let mut w = csv::Writer::from_writer(&mut *out);
+            let mut header = vec![
+                "norad_id",
+                "name",
+                "rise",
+                "rise_az_deg",
+                "culmination",
+                "culmination_az_deg",
+                "max_el_deg",
+                "set",
+                "set_az_deg",
+            ];
+            if labelled {
+                header.push("location");
+            }
+            w.write_record(header)?;
+            for (place, passes) in sections {
+                for p in passes.iter() {
+                    let mut row = vec![
+                        p.norad_id.to_string(),
+                        p.name.clone(),
+                        rfc3339(p.rise, args.timezone),
+                        style.number(p.rise_azimuth, 3),
+                        rfc3339(p.culmination, args.timezone),
+                        style.number(p.culmination_azimuth, 3),
+                        style.number(p.max_elevation, 3),
+                        rfc3339(p.set, args.timezone),
+                        style.number(p.set_azimuth, 3),
+                    ];
+                    if let Some(place) = place {
+                        row.push(place.name.clone());
+                    }
+                    w.write_record(row)?;
+                }
+            }
+            w.flush()?;
+        }
+        Format::Text => {
+            for (i, (place, passes)) in sections.iter().enumerate() {
+                if let Some(place) = place {
+                    if i > 0 {
+                        writeln!(out)?;
+                    }
+                    writeln!(out, "From {}:", place.name)?;
+                }
+                print_pass_lines(out, passes, config, args.timezone, style)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_pass_lines(
+    out: &mut dyn Write,
+    passes: &[Pass],
+    config: &PassSearchConfig,
+    tz: Tz,
+    style: NumberStyle,
+) -> Result<()> {
+    if passes.is_empty() {
+        writeln!(
+            out,
+            "Nothing rises above {} degrees in the next {} hours.",
+            config.min_elevation_deg, config.window_hours
+        )?;
+        return Ok(());
+    }
+    let time = |t| display_time(t, tz);
+    for p in passes {
+        writeln!(
+            out,
+            "{} ({}) rises at {} in the {}, peaks at {} degrees at {} in the {}, and sets at {} in the {}",
+            p.name,
+            p.norad_id,
+            time(p.rise),
+            cardinal_direction(p.rise_azimuth),
+            style.number(p.max_elevation, 1),
+            time(p.culmination),
+            cardinal_direction(p.culmination_azimuth),
+            time(p.set),
+            cardinal_direction(p.set_azimuth)
+        )?;
+    }
+    Ok(())
+}
+
+fn print_selftest(out: &mut dyn Write, args: &Args, stages: &[StageResult]) -> Result<()> {
+    if args.format == Format::Json {
+        write_json(out, args, stages)?;
+    } else {
+        for s in stages {
+            writeln!(
+                out,
+                "{:4}  {:12}  {:4} checks  max error {:.3e} {} (tolerance {:e}{})",
+                if s.passed() { "ok" } else { "FAIL" },
+                s.stage,
+                s.checks,
+                s.max_error,
+                s.unit,
+                s.tolerance,
+                if s.worst.is_empty() {
+                    String::new()
+                } else {
+                    format!("; worst: {}", s.worst)
+                }
+            )?;
+            for failure in &s.failures {
+                writeln!(out, "      couldn't check {}", failure)?;
+            }
+        }
+    }
+    let failed = stages
+        .iter()
+        .filter(|s| !s.passed())
+        .map(|s| s.stage.as_str())
         .collect::<Vec<_>>();
+    anyhow::ensure!(failed.is_empty(), "Self-test failed: {}", failed.join(", "));
+    Ok(())
+}
+
+fn print_stats(out: &mut dyn Write, args: &Args, stats: &CatalogStats) -> Result<()> {
+    match args.format {
+        Format::Json => return write_json(out, args, stats),
+        Format::Text => {}
+        _ => anyhow::bail!("--stats supports text and json output"),
+    }
+    writeln!(out, "{} satellites", stats.satellites)?;
+    let (Some(oldest), Some(newest), Some(median)) = (
+        stats.oldest_epoch,
+        stats.newest_epoch,
+        stats.median_epoch_age_days,
+    ) else {
+        return Ok(());
+    };
+    writeln!(
+        out,
+        "Epochs from {} to {}",
+        display_time(oldest, args.timezone),
+        display_time(newest, args.timezone)
+    )?;
+    writeln!(out, "Median epoch age {:.1} days, and of them:", median)?;
+    let mut lower = None;
+    for (i, count) in stats.epoch_ages.iter().enumerate() {
+        let range = match (lower, data::EPOCH_AGE_BUCKET_DAYS.get(i)) {
+            (None, Some(1.0)) => "up to a day old".to_string(),
+            (None, Some(upper)) => format!("up to {} days old", upper),
+            (Some(lower), Some(upper)) => format!("{} to {} days old", lower, upper),
+            (Some(lower), None) => format!("over {} days old", lower),
+            (None, None) => unreachable!("there's at least one bucket"),
+        };
+        writeln!(out, "  {:>7}  {}", count, range)?;
+        lower = data::EPOCH_AGE_BUCKET_DAYS.get(i);
+    }
+    let classes = stats
+        .orbit_classes
+        .iter()
+        .map(|(class, count)| format!("{} {}", count, class.to_string().to_uppercase()))
+        .collect::<Vec<_>>();
+    writeln!(out, "Orbits: {}", classes.join(", "))?;
+    writeln!(out, "{} look like they're about to decay", stats.decaying)?;
+    Ok(())
+}
+
+fn print_elements(
+    out: &mut dyn Write,
+    args: &Args,
+    e: &ElementsReport,
+    style: NumberStyle,
+) -> Result<()> {
+    if args.format == Format::Json {
+        return write_json(out, args, e);
+    }
+    if args.format == Format::Csv {
+        let imperial = style.units == Units::Imperial;
+        let mut w = csv::Writer::from_writer(&mut *out);
+        w.write_record([
+            "norad_id",
+            "name",
+            "international_designator",
+            "epoch",
+            "epoch_age_hours",
+            "mean_motion",
+            "mean_motion_dot",
+            "mean_motion_ddot",
+            "eccentricity",
+            "inclination",
+            "right_ascension",
+            "argument_of_perigee",
+            "mean_anomaly",
+            "bstar",
+            "element_set_number",
+            "revolution_number",
+            "period_min",
+            if imperial {
+                "semi_major_axis_mi"
+            } else {
+                "semi_major_axis_km"
+            },
+            if imperial { "apogee_ft" } else { "apogee_km" },
+            if imperial { "perigee_ft" } else { "perigee_km" },
+            "orbit_class",
+        ])?;
+        w.write_record([
+            e.norad_id.to_string(),
+            e.name.clone(),
+            e.international_designator.clone().unwrap_or_default(),
+            rfc3339(e.epoch, args.timezone),
+            format!("{:.2}", e.epoch_age_hours),
+            format!("{:.8}", e.mean_motion),
+            format!("{:e}", e.mean_motion_dot),
+            format!("{:e}", e.mean_motion_ddot),
+            format!("{:.7}", e.eccentricity),
+            format!("{:.4}", e.inclination),
+            format!("{:.4}", e.right_ascension),
+            format!("{:.4}", e.argument_of_perigee),
+            format!("{:.4}", e.mean_anomaly),
+            format!("{:e}", e.bstar),
+            e.element_set_number.to_string(),
+            e.revolution_number.to_string(),
+            format!("{:.2}", e.period_minutes),
+            format!("{:.1}", style.units.distance(e.semi_major_axis_km).0),
+            format!("{:.1}", style.units.altitude(e.apogee_km).0),
+            format!("{:.1}", style.units.altitude(e.perigee_km).0),
+            e.orbit_class.to_string(),
+        ])?;
+        w.flush()?;
+        return Ok(());
+    }
+    let rows = [
+        ("NORAD ID", e.norad_id.to_string()),
+        (
+            "International designator",
+            e.international_designator
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
+        ),
+        (
+            "Epoch",
+            format!(
+                "{} ({:.1} hours old at --time)",
+                display_time(e.epoch, args.timezone),
+                e.epoch_age_hours
+            ),
+        ),
+        ("Mean motion", format!("{:.8} rev/day", e.mean_motion)),
+        (
+            "Mean motion dot",
+            format!("{:e} rev/day²", e.mean_motion_dot),
+        ),
+        (
+            "Mean motion ddot",
+            format!("{:e} rev/day³", e.mean_motion_ddot),
+        ),
+        ("Eccentricity", format!("{:.7}", e.eccentricity)),
+        ("Inclination", format!("{:.4}°", e.inclination)),
+        ("RAAN", format!("{:.4}°", e.right_ascension)),
+        (
+            "Argument of perigee",
+            format!("{:.4}°", e.argument_of_perigee),
+        ),
+        ("Mean anomaly", format!("{:.4}°", e.mean_anomaly)),
+        ("B* drag", format!("{:e} 1/earth radii", e.bstar)),
+        ("Element set number", e.element_set_number.to_string()),
+        ("Revolution number", e.revolution_number.to_string()),
+        ("Period", format!("{:.2} minutes", e.period_minutes)),
+        ("Semi-major axis", style.distance(e.semi_major_axis_km)),
+        ("Apogee", style.altitude(e.apogee_km)),
+        ("Perigee", style.altitude(e.perigee_km)),
+        ("Orbit class", e.orbit_class.to_string().to_uppercase()),
+    ];
+    writeln!(out, "{}", e.name)?;
+    let width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+    for (label, value) in rows {
+        writeln!(out, "  {:width$}  {}", label, value, width = width)?;
+    }
+    Ok(())
+}
+
+fn print_ephemeris(out: &mut dyn Write, args: &Args, points: &[EphemerisPoint]) -> Result<()> {
+    if args.format == Format::Json {
+        write_json(out, args, points)?;
+        return Ok(());
+    }
+    if args.format == Format::Ndjson {
+        for p in points {
+            write_ndjson(out, args, p)?;
+        }
+        return Ok(());
+    }
+    let mut w = csv::Writer::from_writer(&mut *out);
+    w.write_record([
+        "time", "lat", "lon", "alt_km", "x_km", "y_km", "z_km", "vx_kms", "vy_kms", "vz_kms",
+    ])?;
+    for p in points {
+        let mut row = vec![
+            // Steps can be fractions of a second.
+            p.time
+                .with_timezone(&args.timezone)
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+            format!("{:.6}", p.lat),
+            format!("{:.6}", p.lon),
+            format!("{:.4}", p.alt_km),
+        ];
+        row.extend(p.position.iter().map(|x| format!("{:.4}", x)));
+        row.extend(p.velocity.iter().map(|v| format!("{:.6}", v)));
+        w.write_record(row)?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+fn print_pairs(
+    out: &mut dyn Write,
+    args: &Args,
+    reports: &[SatelliteReport],
+    style: NumberStyle,
+) -> Result<()> {
+    anyhow::ensure!(
+        args.format != Format::Csv,
+        "--pairs supports text and json output"
+    );
+    let mut pairs = query::close_pairs(reports, args.max_separation_deg);
+    if let Some(n) = args.top {
+        pairs.truncate(n);
+    }
+    if args.format == Format::Json {
+        write_json(out, args, &pairs)?;
+        return Ok(());
+    }
+    if pairs.is_empty() {
+        writeln!(
+            out,
+            "No two satellites are within {} degrees of each other.",
+            args.max_separation_deg
+        )?;
+    }
+    for p in &pairs {
+        writeln!(
+            out,
+            "{} ({}) and {} ({}) are {} degrees apart, {} degrees up at azimuth {}, {} and {} away",
+            p.a.name,
+            p.a.norad_id,
+            p.b.name,
+            p.b.norad_id,
+            style.number(p.separation_deg, 2),
+            style.number(p.a.elevation, 1),
+            style.number(p.a.azimuth, 1),
+            style.distance(p.a.range_km),
+            style.distance(p.b.range_km)
+        )?;
+    }
+    Ok(())
+}
+
+// A count of how many things have been processed, out of `total` if we know
+// it, redrawn in place on stderr. It's only shown for text output when stderr
+// is a terminal, so it never gets mixed in with output meant for another
+// program. Any thread can tick it.
+struct Progress {
+    label: &'static str,
+    total: Option<usize>,
+    unit: &'static str,
+    done: AtomicUsize,
+    shown: bool,
+}
+
+impl Progress {
+    fn new(args: &Args, label: &'static str, total: Option<usize>, unit: &'static str) -> Progress {
+        Progress {
+            label,
+            total,
+            unit,
+            done: AtomicUsize::new(0),
+            shown: args.format == Format::Text && io::stderr().is_terminal(),
+        }
+    }
+
+    fn tick(&self) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        if !self.shown {
+            return;
+        }
+        // Redrawing for every one would spend more time on the terminal
+        // than on the work.
+        match self.total {
+            Some(total) if done == total || done.is_multiple_of((total / 100).max(1)) => {
+                eprint!("\r{} {}/{} {}", self.label, done, total, self.unit)
+            }
+            None if done.is_multiple_of(1000) => eprint!("\r{} {} {}", self.label, done, self.unit),
+            _ => {}
+        }
+    }
+
+    // Clears the line, so whatever's printed next starts clean.
+    fn finish(&self) {
+        if self.shown {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+// The most satellites --satellite will pick, and the most it lists when
+// there are more.
+const MAX_NAME_MATCHES: usize = 10;
+const MAX_LISTED: usize = 25;
+
+// Where to look for a catalog when --catalog isn't given.
+const DEFAULT_CATALOG: &str = "space-track-omm.json";
+
+// Reads the catalog, going through the cache if --cache was given. A cache
+// that can't be written is only worth a warning; the next run will just
+// parse the JSON again. Without a catalog file this falls back to the
+// bundled element sets, unless --no-bundled says not to.
+fn load(args: &Args) -> Result<Catalog> {
+    let path = match &args.catalog {
+        Some(path) => path.as_str(),
+        None if args.no_bundled || Path::new(DEFAULT_CATALOG).exists() => DEFAULT_CATALOG,
+        None => {
+            let catalog = data::bundled_catalog();
+            let oldest = catalog.satellites.iter().map(|s| s.epoch).min();
+            eprintln!(
+                "Note: no catalog given, so using the built-in element sets for {} bright satellites{}. They may be weeks old, so positions are approximate. Download a current catalog from space-track.org and pass it with --catalog, or use --no-bundled to make this an error.",
+                catalog.satellites.len(),
+                oldest.map_or(String::new(), |t| format!(" from {}", t.format("%Y-%m-%d")))
+            );
+            return Ok(catalog);
+        }
+    };
+    let cache = data::cache_path(path);
+    if args.cache {
+        let start = Instant::now();
+        if let Some(catalog) = data::read_cache(&cache, path) {
+            if args.timing {
+                eprintln!("Read {} in {:.1} ms", cache.display(), ms_since(start));
+            }
+            return Ok(catalog);
+        }
+    }
+    let start = Instant::now();
+    // There's no telling how many records there are until they've all been
+    // read.
+    let progress = Progress::new(args, "Read", None, "records");
+    let catalog = data::load_catalog_with_progress(path, || progress.tick());
+    progress.finish();
+    let catalog = catalog?;
+    if args.timing {
+        eprintln!("Parsed {} in {:.1} ms", path, ms_since(start));
+    }
+    if args.cache {
+        let start = Instant::now();
+        match data::write_cache(&cache, path, &catalog) {
+            Ok(()) if args.timing => {
+                eprintln!("Wrote {} in {:.1} ms", cache.display(), ms_since(start))
+            }
+            Ok(()) => {}
+            Err(e) => eprintln!("Warning: couldn't write {}: {:#}", cache.display(), e),
+        }
+    }
+    Ok(catalog)
+}
+
+// Formats a number of seconds like "3m40s", or "25s" when it's under a
+// minute.
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    match (seconds / 60, seconds % 60) {
+        (0, s) => format!("{}s", s),
+        (m, s) => format!("{}m{:02}s", m, s),
+    }
+}
+
+// Time steps are rounded down to whole milliseconds, so a search given a
+// shorter one would never get anywhere.
+fn ensure_step(flag: &str, seconds: f64) -> Result<()> {
+    anyhow::ensure!(
+        seconds * 1000.0 >= 1.0,
+        "{} must be at least a millisecond, got {} seconds",
+        flag,
+        seconds
+    );
     Ok(())
 }
 
-// Function to calculate the distance between two coordinates
-fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
-    let r = 6371.0; // Radius of the Earth in km
-    let d_lat = (lat2 - lat1).to_radians();
-    let d_lon = (lon2 - lon1).to_radians();
-    let a = (d_lat / 2.0).sin().powi(2)
-        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
-    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
-    r * c
+fn ms_since(start: Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
 }
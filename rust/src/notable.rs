@@ -0,0 +1,62 @@
+//! Friendly names for the handful of objects most people are asking about
+//! when they ask what's overhead.
+//!
+//! Catalog names are a poor way to find these: "ISS" is a substring of
+//! dozens of "ISS DEB" fragments, and the Chinese station is catalogued by
+//! its core module, "CSS (TIANHE)". An alias names one NORAD ID, and
+//! matches the whole query, ignoring case.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The aliases built in, and the NORAD IDs they stand for.
+pub const BUILT_IN: &[(&str, u64)] = &[
+    ("iss", 25544),
+    ("zarya", 25544),
+    ("tiangong", 48274),
+    ("css", 48274),
+    ("tianhe", 48274),
+    ("hubble", 20580),
+    ("hst", 20580),
+    ("envisat", 27386),
+];
+
+/// A table of aliases, keyed in lower case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Aliases {
+    ids: BTreeMap<String, u64>,
+}
+
+impl Default for Aliases {
+    fn default() -> Aliases {
+        Aliases::built_in()
+    }
+}
+
+impl Aliases {
+    /// Just the [`BUILT_IN`] aliases.
+    pub fn built_in() -> Aliases {
+        Aliases {
+            ids: BUILT_IN
+                .iter()
+                .map(|&(alias, id)| (alias.to_string(), id))
+                .collect(),
+        }
+    }
+
+    /// Adds aliases, replacing any built-in ones with the same names.
+    pub fn extend<'a>(&mut self, aliases: impl IntoIterator<Item = (&'a String, &'a u64)>) {
+        for (alias, &id) in aliases {
+            self.ids.insert(alias.trim().to_lowercase(), id);
+        }
+    }
+
+    /// The NORAD ID `query` is an alias for, if it is one.
+    pub fn resolve(&self, query: &str) -> Option<u64> {
+        self.ids.get(&query.trim().to_lowercase()).copied()
+    }
+
+    /// Every NORAD ID with an alias.
+    pub fn ids(&self) -> BTreeSet<u64> {
+        self.ids.values().copied().collect()
+    }
+}
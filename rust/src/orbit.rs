@@ -0,0 +1,83 @@
+//! Sorting orbits into the usual regimes.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::coords::WGS84_A;
+use crate::data::semi_major_axis;
+
+/// Mean motion, in rev/day, that counts as geosynchronous: about one orbit
+/// a day. Exactly geosynchronous is 1.0027, once a sidereal day.
+pub const GEO_MEAN_MOTION: std::ops::RangeInclusive<f64> = 0.99..=1.01;
+/// Largest eccentricity for a geostationary orbit.
+pub const GEO_MAX_ECCENTRICITY: f64 = 0.01;
+/// Smallest eccentricity for a highly elliptical orbit, like Molniya (about
+/// 0.7) or geostationary transfer (also about 0.7).
+pub const HEO_MIN_ECCENTRICITY: f64 = 0.25;
+/// Highest mean altitude, in km above the equator, for low Earth orbit.
+pub const LEO_MAX_ALT_KM: f64 = 2000.0;
+
+/// Which regime an orbit is in, from its mean motion and eccentricity. The
+/// boundaries, checked in this order, are:
+///
+/// - GEO: mean motion within [`GEO_MEAN_MOTION`] and eccentricity under
+///   [`GEO_MAX_ECCENTRICITY`]. Inclination isn't considered, so inclined
+///   geosynchronous orbits count too.
+/// - HEO: eccentricity of at least [`HEO_MIN_ECCENTRICITY`], or a mean
+///   motion below [`GEO_MEAN_MOTION`], which puts it above geosynchronous
+///   altitude.
+/// - LEO: a mean altitude (semi-major axis less the equatorial radius)
+///   under [`LEO_MAX_ALT_KM`].
+/// - MEO: everything else, from LEO up to geosynchronous altitude, like GPS
+///   and Galileo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrbitClass {
+    Leo,
+    Meo,
+    Geo,
+    Heo,
+}
+
+impl OrbitClass {
+    /// Classifies an orbit from its mean motion in rev/day and its
+    /// eccentricity.
+    pub fn classify(mean_motion: f64, eccentricity: f64) -> OrbitClass {
+        if GEO_MEAN_MOTION.contains(&mean_motion) && eccentricity < GEO_MAX_ECCENTRICITY {
+            return OrbitClass::Geo;
+        }
+        if eccentricity >= HEO_MIN_ECCENTRICITY || mean_motion < *GEO_MEAN_MOTION.start() {
+            OrbitClass::Heo
+        } else if semi_major_axis(mean_motion) - WGS84_A < LEO_MAX_ALT_KM {
+            OrbitClass::Leo
+        } else {
+            OrbitClass::Meo
+        }
+    }
+}
+
+impl fmt::Display for OrbitClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            OrbitClass::Leo => "leo",
+            OrbitClass::Meo => "meo",
+            OrbitClass::Geo => "geo",
+            OrbitClass::Heo => "heo",
+        })
+    }
+}
+
+impl FromStr for OrbitClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "leo" => Ok(OrbitClass::Leo),
+            "meo" => Ok(OrbitClass::Meo),
+            "geo" => Ok(OrbitClass::Geo),
+            "heo" => Ok(OrbitClass::Heo),
+            _ => Err(format!("expected leo, meo, geo or heo, got {:?}", s)),
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! Predicting when satellites rise and set.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::coords::{look_angles, sub, Observer};
+use crate::data::Satellite;
+
+/// How to search for passes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PassSearchConfig {
+    /// How often to check whether the satellite is up, in seconds. A pass
+    /// shorter than this can be missed.
+    pub coarse_step_seconds: f64,
+    /// How precisely to find rise, culmination and set times, in seconds.
+    pub refine_step_seconds: f64,
+    /// How far ahead to look, in hours.
+    pub window_hours: f64,
+    /// The satellite counts as up when it's at least this many degrees above
+    /// the horizon.
+    pub min_elevation_deg: f64,
+    /// UT1 - UTC in seconds; see [`crate::coords::gmst`].
+    pub delta_ut1: f64,
+}
+
+impl Default for PassSearchConfig {
+    fn default() -> PassSearchConfig {
+        PassSearchConfig {
+            coarse_step_seconds: 60.0,
+            refine_step_seconds: 1.0,
+            window_hours: 24.0,
+            min_elevation_deg: 0.0,
+            delta_ut1: 0.0,
+        }
+    }
+}
+
+/// One pass of a satellite over the observer. A pass that's already under
+/// way when the search starts rises at the start of the window, and one
+/// that hasn't finished by the end of it sets at the end.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pass {
+    pub name: String,
+    pub norad_id: u64,
+    pub rise: DateTime<Utc>,
+    /// Degrees clockwise from true north.
+    pub rise_azimuth: f64,
+    /// When the satellite is highest.
+    pub culmination: DateTime<Utc>,
+    pub culmination_azimuth: f64,
+    /// Degrees above the horizon at culmination.
+    pub max_elevation: f64,
+    pub set: DateTime<Utc>,
+    pub set_azimuth: f64,
+}
+
+impl Pass {
+    /// Whether the satellite is up for both passes at once, for example to
+    /// hand off between two ground stations.
+    pub fn overlaps(&self, other: &Pass) -> bool {
+        self.rise < other.set && other.rise < self.set
+    }
+}
+
+/// Every pass of one satellite in the window starting at `start`, in order.
+pub fn find_passes(
+    sat: &Satellite,
+    observer: &Observer,
+    start: DateTime<Utc>,
+    config: &PassSearchConfig,
+) -> Vec<Pass> {
+    let look = |t| {
+        sat.try_ecef_at_ut1(t, config.delta_ut1)
+            .ok()
+            .map(|(position, _)| look_angles(observer, position))
+    };
+    let elevation = |t| look(t).map_or(f64::NEG_INFINITY, |l| l.elevation);
+    let up = |t| elevation(t) >= config.min_elevation_deg;
+    let step = seconds(config.coarse_step_seconds);
+    let resolution = seconds(config.refine_step_seconds);
+    let end = start + seconds(config.window_hours * 3600.0);
+
+    let mut passes = Vec::new();
+    let mut pass = |rise, set| {
+        let culmination = culmination(elevation, rise, set, step, resolution);
+        let azimuth = |t| look(t).map_or(f64::NAN, |l| l.azimuth);
+        passes.push(Pass {
+            name: sat.name.clone(),
+            norad_id: sat.norad_id,
+            rise,
+            rise_azimuth: azimuth(rise),
+            culmination,
+            culmination_azimuth: azimuth(culmination),
+            max_elevation: elevation(culmination),
+            set,
+            set_azimuth: azimuth(set),
+        });
+    };
+    let mut rise = up(start).then_some(start);
+    let mut t = start;
+    while t < end {
+        let next = (t + step).min(end);
+        match (rise, up(next)) {
+            (None, true) => rise = Some(crossing(up, t, next, resolution)),
+            (Some(r), false) => {
+                pass(r, crossing(|t| !up(t), t, next, resolution));
+                rise = None;
+            }
+            _ => {}
+        }
+        t = next;
+    }
+    if let Some(r) = rise {
+        pass(r, end);
+    }
+    passes
+}
+
+/// Every pass of every satellite in the window starting at `start`, as one
+/// timeline ordered by rise time. The satellites are searched in parallel.
+pub fn predict_all_passes(
+    satellites: &[Satellite],
+    observer: &Observer,
+    start: DateTime<Utc>,
+    config: &PassSearchConfig,
+) -> Vec<Pass> {
+    predict_all_passes_with_progress(satellites, observer, start, config, || {})
+}
+
+/// Like [`predict_all_passes`], but calls `on_searched` each time a
+/// satellite has been searched, from whichever thread searched it, so a
+/// long search can show its progress.
+pub fn predict_all_passes_with_progress(
+    satellites: &[Satellite],
+    observer: &Observer,
+    start: DateTime<Utc>,
+    config: &PassSearchConfig,
+    on_searched: impl Fn() + Sync,
+) -> Vec<Pass> {
+    let mut passes = satellites
+        .par_iter()
+        .flat_map_iter(|sat| {
+            let passes = find_passes(sat, observer, start, config);
+            on_searched();
+            passes
+        })
+        .collect::<Vec<_>>();
+    passes.sort_by(|a, b| a.rise.cmp(&b.rise).then(a.norad_id.cmp(&b.norad_id)));
+    passes
+}
+
+/// When one satellite is in contact with each of a set of named ground
+/// stations in the window starting at `start`, keyed by station name. Each
+/// station's passes are in order; comparing them with [`Pass::overlaps`]
+/// shows when more than one station can reach the satellite. The stations
+/// are searched in parallel.
+pub fn contact_windows(
+    sat: &Satellite,
+    stations: &[(String, Observer)],
+    start: DateTime<Utc>,
+    config: &PassSearchConfig,
+) -> HashMap<String, Vec<Pass>> {
+    stations
+        .par_iter()
+        .map(|(name, observer)| (name.clone(), find_passes(sat, observer, start, config)))
+        .collect()
+}
+
+/// When `sat` is closest to an observer at `observer_ecef` (see
+/// [`observer_ecef`](crate::coords::observer_ecef)) between `start` and
+/// `end`, and its slant range then, in km. This is a pass's culmination
+/// measured by distance rather than elevation, and it's found the same way:
+/// the closest of the times `coarse_step` apart, then a golden-section
+/// search on either side of that to within 10 ms. Times the satellite can't
+/// be propagated to count as infinitely far away. `coarse_step` should be
+/// short enough that there's only one minimum within a step of the closest
+/// one, which [`approach_step`] is, and it's an error for it not to be
+/// positive. The Earth is turned by `delta_ut1`, UT1 - UTC in seconds; see
+/// [`crate::coords::gmst`].
+pub fn closest_approach(
+    observer_ecef: [f64; 3],
+    sat: &Satellite,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    coarse_step: Duration,
+    delta_ut1: f64,
+) -> anyhow::Result<(DateTime<Utc>, f64)> {
+    anyhow::ensure!(
+        coarse_step > Duration::zero(),
+        "the step must be positive, got {}",
+        coarse_step
+    );
+    let range = |t: DateTime<Utc>| match sat.try_ecef_at_ut1(t, delta_ut1) {
+        Ok((p, _)) => {
+            let d = sub(p, observer_ecef);
+            (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+        }
+        Err(_) => f64::INFINITY,
+    };
+    let mut best = (start, range(start));
+    let mut t = start;
+    while t < end {
+        t = (t + coarse_step).min(end);
+        let r = range(t);
+        if r < best.1 {
+            best = (t, r);
+        }
+    }
+
+    // Golden-section search, in seconds after `start`.
+    let at = |s: f64| start + seconds(s);
+    let offset = |t: DateTime<Utc>| (t - start).num_milliseconds() as f64 / 1000.0;
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let mut a = offset((best.0 - coarse_step).max(start));
+    let mut b = offset((best.0 + coarse_step).min(end));
+    let mut c = b - ratio * (b - a);
+    let mut d = a + ratio * (b - a);
+    let (mut rc, mut rd) = (range(at(c)), range(at(d)));
+    while b - a > 0.01 {
+        if rc < rd {
+            (b, d, rd) = (d, c, rc);
+            c = b - ratio * (b - a);
+            rc = range(at(c));
+        } else {
+            (a, c, rc) = (c, d, rd);
+            d = a + ratio * (b - a);
+            rd = range(at(d));
+        }
+    }
+    let refined = at((a + b) / 2.0);
+    let r = range(refined);
+    // The coarse step can only be beaten, never lost.
+    if r <= best.1 {
+        Ok((refined, r))
+    } else {
+        Ok(best)
+    }
+}
+
+/// A coarse step for [`closest_approach`] that suits `sat`'s orbit: the time
+/// it takes to go 2 degrees around it, which is about half a minute in low
+/// orbits. Even a low pass that only just clears the horizon takes several
+/// times that to go from getting closer to moving away.
+pub fn approach_step(sat: &Satellite) -> Duration {
+    seconds(sat.period_minutes() * 60.0 / 180.0).max(Duration::seconds(1))
+}
+
+pub(crate) fn seconds(s: f64) -> Duration {
+    Duration::milliseconds((s * 1000.0) as i64)
+}
+
+// Narrows down when `crossed` first becomes true between `before`, when it's
+// false, and `after`, when it's true, to within `resolution`.
+pub(crate) fn crossing(
+    crossed: impl Fn(DateTime<Utc>) -> bool,
+    mut before: DateTime<Utc>,
+    mut after: DateTime<Utc>,
+    resolution: Duration,
+) -> DateTime<Utc> {
+    while after - before > resolution {
+        let mid = before + (after - before) / 2;
+        if crossed(mid) {
+            after = mid;
+        } else {
+            before = mid;
+        }
+    }
+    after
+}
+
+// When the satellite is highest between `rise` and `set`. Long passes can
+// have more than one peak, so this finds the highest coarse step first and
+// then narrows it down with a ternary search around it.
+pub(crate) fn culmination(
+    elevation: impl Fn(DateTime<Utc>) -> f64,
+    rise: DateTime<Utc>,
+    set: DateTime<Utc>,
+    step: Duration,
+    resolution: Duration,
+) -> DateTime<Utc> {
+    let mut best = (rise, elevation(rise));
+    let mut t = rise;
+    while t < set {
+        t = (t + step).min(set);
+        let e = elevation(t);
+        if e > best.1 {
+            best = (t, e);
+        }
+    }
+    let mut a = (best.0 - step).max(rise);
+    let mut b = (best.0 + step).min(set);
+    while b - a > resolution {
+        let m1 = a + (b - a) / 3;
+        let m2 = b - (b - a) / 3;
+        if elevation(m1) < elevation(m2) {
+            a = m1;
+        } else {
+            b = m2;
+        }
+    }
+    a + (b - a) / 2
+}
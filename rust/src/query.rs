@@ -0,0 +1,1116 @@
+//! Working out what's overhead.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::coords::{
+    angle_between, angular_separation, ecef_to_geodetic, ground_heading, inertial_speed,
+    look_angle_rates, look_angles, observer_ecef, range_rate, sub, Observer,
+};
+use crate::data::{
+    apogee_altitude, semi_major_axis, Catalog, PropagationError, Rejected, Satellite,
+};
+use crate::geo::{ground_bearing, haversine_distance, Area};
+use crate::magnitude::visual_magnitude;
+use crate::orbit::OrbitClass;
+use crate::passes::{culmination, seconds};
+use crate::sun::{self, Twilight, Visibility};
+
+/// Where a satellite is at some moment, relative to the Earth and to the
+/// observer. This is what every output format is built from. More fields
+/// may be added, so it can only be made in this crate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SatelliteReport {
+    pub name: String,
+    pub norad_id: u64,
+    /// The moment the report is for.
+    pub time: DateTime<Utc>,
+    /// How old the elements were at that moment, in hours. Negative if the
+    /// elements are from later.
+    pub epoch_age_hours: f64,
+    /// Subpoint latitude in degrees.
+    pub lat: f64,
+    /// Subpoint longitude in degrees, in [-180, 180).
+    pub lon: f64,
+    /// Height above the WGS84 ellipsoid in km. The CLI's --geoid turns this
+    /// into height above mean sea level, which differs by at most about
+    /// 0.1 km.
+    pub alt_km: f64,
+    /// Degrees clockwise from true north.
+    pub azimuth: f64,
+    /// Degrees clockwise from magnetic north, for pointing with a compass,
+    /// if a magnetic model covering `time` was given.
+    pub magnetic_azimuth: Option<f64>,
+    /// The magnetic declination at the observer that `magnetic_azimuth`
+    /// was worked out with, in degrees east of true north.
+    pub magnetic_declination: Option<f64>,
+    /// Degrees above the horizon, corrected for refraction unless the
+    /// observer has no atmosphere.
+    pub elevation: f64,
+    /// Slant range in km.
+    pub range_km: f64,
+    /// Rate of change of the slant range in km/s, positive when the
+    /// satellite is moving away.
+    pub range_rate_kms: f64,
+    /// True when the satellite is getting closer to the observer.
+    pub approaching: bool,
+    /// Distance from the observer to the subpoint over the ground in km.
+    pub ground_km: f64,
+    /// Great-circle bearing from the observer to the subpoint, in degrees
+    /// from north, for pointing at the satellite's ground position on a map.
+    /// None when the satellite is essentially straight up.
+    pub ground_bearing: Option<f64>,
+    /// Direction the subpoint is moving, in degrees from north.
+    pub heading: f64,
+    /// Speed relative to the rotating Earth in km/s: the magnitude of the
+    /// ECEF velocity. This is at the satellite's altitude, so the subpoint
+    /// itself moves somewhat slower.
+    pub ground_speed_kms: f64,
+    /// Speed in the inertial frame in km/s.
+    pub inertial_speed_kms: f64,
+    /// Time for one orbit in minutes.
+    pub period_minutes: f64,
+    pub decaying: bool,
+    /// True if the satellite is in sunlight rather than Earth's shadow.
+    pub sunlit: bool,
+    /// Estimated visual magnitude, None if we don't know how bright the
+    /// object is or it's in Earth's shadow.
+    pub magnitude: Option<f64>,
+    /// How fast the azimuth is changing, positive clockwise. This gets
+    /// large for satellites passing close to straight overhead.
+    pub azimuth_rate_deg_per_s: f64,
+    /// How fast the elevation is changing, positive while the satellite is
+    /// rising.
+    pub elevation_rate_deg_per_s: f64,
+    /// For a rising satellite, how long until it's highest, if that's in
+    /// the next 15 minutes.
+    pub culmination_in_seconds: Option<f64>,
+    /// The Sun's geometric elevation from the observer in degrees.
+    pub sun_elevation: f64,
+    pub twilight: Twilight,
+    /// Whether the satellite could be seen with the eye, from `sunlit` and
+    /// `twilight`.
+    pub visibility: Visibility,
+    /// Where it is before and after `time`, if [`trail`] was asked for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trail: Vec<TrailPoint>,
+}
+
+impl SatelliteReport {
+    /// True if the satellite is climbing noticeably, rather than setting or
+    /// hanging in place like a geostationary one.
+    pub fn rising(&self) -> bool {
+        self.elevation_rate_deg_per_s > MIN_RISING_RATE_DEG_PER_S
+    }
+
+    /// True if the satellite is sinking noticeably.
+    pub fn setting(&self) -> bool {
+        self.elevation_rate_deg_per_s < -MIN_RISING_RATE_DEG_PER_S
+    }
+}
+
+/// Elevation rates slower than this, in degrees per second, count as
+/// standing still. That's under 4 degrees an hour: geostationary satellites
+/// wander by much less, and anything in LEO that's up moves much faster.
+pub const MIN_RISING_RATE_DEG_PER_S: f64 = 0.001;
+
+// How far ahead to look for a rising satellite's culmination, and in what
+// steps. Nothing in LEO stays up longer than this, and the search gives up
+// on anything slower.
+const CULMINATION_WINDOW_SECONDS: f64 = 900.0;
+const CULMINATION_STEP_SECONDS: f64 = 20.0;
+
+/// A satellite's position and velocity at some moment. These don't depend
+/// on who's looking, so when there are several observers it's worth
+/// propagating once with [`propagate`] and reusing the result for each of
+/// them.
+#[derive(Debug, Clone, Copy)]
+pub struct Propagated<'a> {
+    pub satellite: &'a Satellite,
+    pub time: DateTime<Utc>,
+    /// ECEF position in km.
+    pub position: [f64; 3],
+    /// ECEF velocity in km/s.
+    pub velocity: [f64; 3],
+    /// The UT1 - UTC, in seconds, the Earth was turned by to get the
+    /// position; see [`crate::coords::gmst`].
+    pub delta_ut1: f64,
+}
+
+/// Propagated altitudes below this, in km, mean the object has re-entered
+/// and the elements are garbage. Nothing stays in orbit this low.
+pub const MIN_PLAUSIBLE_ALT_KM: f64 = 80.0;
+/// Propagated altitudes above this, in km, mean the elements are garbage.
+/// It's well past the Moon, and nothing in a catalog of Earth satellites
+/// gets that far.
+pub const MAX_PLAUSIBLE_ALT_KM: f64 = 1_000_000.0;
+
+impl Propagated<'_> {
+    /// Height above the WGS84 ellipsoid in km.
+    pub fn alt_km(&self) -> f64 {
+        ecef_to_geodetic(self.position).alt_km
+    }
+}
+
+/// A satellite that propagated without errors to somewhere it can't be.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Implausible {
+    pub norad_id: u64,
+    pub name: String,
+    pub alt_km: f64,
+}
+
+fn plausible_altitude(alt_km: f64) -> bool {
+    (MIN_PLAUSIBLE_ALT_KM..=MAX_PLAUSIBLE_ALT_KM).contains(&alt_km)
+}
+
+/// Propagates every satellite to time `t`, leaving out the ones SGP4 can't
+/// handle and the ones that end up at implausible altitudes.
+pub fn propagate(satellites: &[Satellite], t: DateTime<Utc>) -> Vec<Propagated<'_>> {
+    let mut propagated = propagate_with_errors(satellites, t).0;
+    drop_implausible(&mut propagated);
+    propagated
+}
+
+/// Removes satellites whose altitude is below [`MIN_PLAUSIBLE_ALT_KM`] or
+/// above [`MAX_PLAUSIBLE_ALT_KM`], which happens when propagating elements
+/// for an object that has decayed, and returns them. This catches garbage
+/// without needing a real decay model.
+pub fn drop_implausible(propagated: &mut Vec<Propagated>) -> Vec<Implausible> {
+    let mut implausible = Vec::new();
+    propagated.retain(|p| {
+        let alt_km = p.alt_km();
+        let keep = plausible_altitude(alt_km);
+        if !keep {
+            implausible.push(Implausible {
+                norad_id: p.satellite.norad_id,
+                name: p.satellite.name.clone(),
+                alt_km,
+            });
+        }
+        keep
+    });
+    implausible
+}
+
+/// Like [`propagate`], but also returns why each of the satellites SGP4
+/// couldn't handle failed. This doesn't check altitudes; follow it with
+/// [`drop_implausible`] for that.
+pub fn propagate_with_errors(
+    satellites: &[Satellite],
+    t: DateTime<Utc>,
+) -> (Vec<Propagated<'_>>, Vec<PropagationError>) {
+    propagate_with_progress(satellites, t, || {})
+}
+
+/// Like [`propagate_with_errors`], but calls `on_propagated` each time a
+/// satellite has been propagated, or has failed to, so a long run can show
+/// its progress.
+pub fn propagate_with_progress(
+    satellites: &[Satellite],
+    t: DateTime<Utc>,
+    on_propagated: impl FnMut(),
+) -> (Vec<Propagated<'_>>, Vec<PropagationError>) {
+    propagate_ut1(satellites, t, 0.0, on_propagated)
+}
+
+// propagate_with_progress, turning the Earth by `delta_ut1` as well.
+fn propagate_ut1(
+    satellites: &[Satellite],
+    t: DateTime<Utc>,
+    delta_ut1: f64,
+    mut on_propagated: impl FnMut(),
+) -> (Vec<Propagated<'_>>, Vec<PropagationError>) {
+    let mut propagated = Vec::with_capacity(satellites.len());
+    let mut errors = Vec::new();
+    for sat in satellites {
+        match sat.try_ecef_at_ut1(t, delta_ut1) {
+            Ok((position, velocity)) => propagated.push(Propagated {
+                satellite: sat,
+                time: t,
+                position,
+                velocity,
+                delta_ut1,
+            }),
+            Err(e) => errors.push(e),
+        }
+        on_propagated();
+    }
+    (propagated, errors)
+}
+
+/// A set of satellites to ask questions of, for holding on to between
+/// queries. The SGP4 constants are worked out once, when it's made, and the
+/// methods are the same as the free functions in this module, except that
+/// they turn the Earth by the UT1 - UTC given to
+/// [`Propagator::with_delta_ut1`] where the free functions take UT1 to be
+/// UTC.
+///
+/// ```
+/// use whatsoverhead::{Observer, Propagator};
+///
+/// let iss = sgp4::Elements::from_tle(
+///     Some("ISS (ZARYA)".to_string()),
+///     b"1 25544U 98067A   20194.88612269 -.00002218  00000-0 -31515-4 0  9992",
+///     b"2 25544  51.6461 221.2784 0001413  89.1723 280.4612 15.49507896236008",
+/// )?;
+/// let propagator = Propagator::from_elements(vec![iss]);
+/// let observer = Observer {
+///     lat: 34.05,
+///     lon: -118.24,
+///     alt_m: 100.0,
+///     atmosphere: None,
+/// };
+/// let time = "2020-07-13T00:00:00Z".parse()?;
+/// let nearest = propagator.nearest(&observer, time, 1);
+/// assert_eq!(nearest[0].norad_id, 25544);
+/// for report in propagator.overhead(&observer, time, 10.0) {
+///     println!("{} is {:.0} km away", report.name, report.range_km);
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Propagator {
+    satellites: Vec<Satellite>,
+    rejected: Vec<Rejected>,
+    delta_ut1: f64,
+}
+
+impl Propagator {
+    pub fn new(satellites: Vec<Satellite>) -> Propagator {
+        Propagator {
+            satellites,
+            rejected: Vec::new(),
+            delta_ut1: 0.0,
+        }
+    }
+
+    /// Leaves out elements SGP4 can't use, which [`Propagator::rejected`]
+    /// lists.
+    pub fn from_elements(elements: impl IntoIterator<Item = sgp4::Elements>) -> Propagator {
+        Catalog::from_elements(elements).into()
+    }
+
+    pub fn satellites(&self) -> &[Satellite] {
+        &self.satellites
+    }
+
+    /// The elements that couldn't be used when this was made.
+    pub fn rejected(&self) -> &[Rejected] {
+        &self.rejected
+    }
+
+    /// UT1 - UTC in seconds, for positions that need to be good to better
+    /// than about 400 m of longitude; see [`crate::coords::gmst`]. It's 0
+    /// unless this is called.
+    pub fn with_delta_ut1(mut self, seconds: f64) -> Propagator {
+        self.delta_ut1 = seconds;
+        self
+    }
+
+    pub fn delta_ut1(&self) -> f64 {
+        self.delta_ut1
+    }
+
+    /// See [`propagate`].
+    pub fn propagate(&self, t: DateTime<Utc>) -> Vec<Propagated<'_>> {
+        let mut propagated = self.propagate_with_errors(t).0;
+        drop_implausible(&mut propagated);
+        propagated
+    }
+
+    /// See [`propagate_with_errors`].
+    pub fn propagate_with_errors(
+        &self,
+        t: DateTime<Utc>,
+    ) -> (Vec<Propagated<'_>>, Vec<PropagationError>) {
+        self.propagate_with_progress(t, || {})
+    }
+
+    /// See [`propagate_with_progress`].
+    pub fn propagate_with_progress(
+        &self,
+        t: DateTime<Utc>,
+        on_propagated: impl FnMut(),
+    ) -> (Vec<Propagated<'_>>, Vec<PropagationError>) {
+        propagate_ut1(&self.satellites, t, self.delta_ut1, on_propagated)
+    }
+
+    /// See [`overhead`].
+    pub fn overhead(
+        &self,
+        observer: &Observer,
+        t: DateTime<Utc>,
+        min_elevation_deg: f64,
+    ) -> Vec<SatelliteReport> {
+        overhead_propagated(&self.propagate(t), observer, min_elevation_deg)
+    }
+
+    /// The `n` satellites closest to the observer at time `t`, closest
+    /// first, whether or not they're above the horizon.
+    pub fn nearest(&self, observer: &Observer, t: DateTime<Utc>, n: usize) -> Vec<SatelliteReport> {
+        let mut propagated = self
+            .propagate(t)
+            .into_iter()
+            .map(|p| (look_angles(observer, p.position).range_km, p))
+            .collect::<Vec<_>>();
+        propagated.sort_by(|a, b| a.0.total_cmp(&b.0));
+        propagated
+            .iter()
+            .take(n)
+            .map(|(_, p)| report_propagated(p, observer))
+            .collect()
+    }
+}
+
+impl From<Catalog> for Propagator {
+    fn from(catalog: Catalog) -> Propagator {
+        Propagator {
+            satellites: catalog.satellites,
+            rejected: catalog.rejected,
+            delta_ut1: 0.0,
+        }
+    }
+}
+
+impl AsRef<[Satellite]> for Propagator {
+    fn as_ref(&self) -> &[Satellite] {
+        &self.satellites
+    }
+}
+
+/// Where a satellite is at one step of an ephemeris.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EphemerisPoint {
+    pub time: DateTime<Utc>,
+    /// Subpoint latitude in degrees.
+    pub lat: f64,
+    /// Subpoint longitude in degrees, in [-180, 180).
+    pub lon: f64,
+    /// Height above the WGS84 ellipsoid in km.
+    pub alt_km: f64,
+    /// ECEF position in km.
+    pub position: [f64; 3],
+    /// ECEF velocity in km/s.
+    pub velocity: [f64; 3],
+}
+
+/// Where a satellite is every `step_seconds` from `start` up to and
+/// including `end`, for exporting to other tools. Times SGP4 can't propagate
+/// to are left out. As with [`max_simultaneous_overhead`], it's an error for
+/// the step to be under a millisecond. The Earth is turned by `delta_ut1`, UT1
+/// - UTC in seconds; see [`crate::coords::gmst`].
+pub fn ephemeris(
+    sat: &Satellite,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    step_seconds: f64,
+    delta_ut1: f64,
+) -> anyhow::Result<Vec<EphemerisPoint>> {
+    let step = seconds(step_seconds);
+    anyhow::ensure!(
+        step > chrono::Duration::zero(),
+        "the step must be at least a millisecond, got {} seconds",
+        step_seconds
+    );
+    let mut points = Vec::new();
+    let mut t = start;
+    while t <= end {
+        if let Ok((position, velocity)) = sat.try_ecef_at_ut1(t, delta_ut1) {
+            let geo = ecef_to_geodetic(position);
+            points.push(EphemerisPoint {
+                time: t,
+                lat: geo.lat,
+                lon: geo.lon,
+                alt_km: geo.alt_km,
+                position,
+                velocity,
+            });
+        }
+        t += step;
+    }
+    Ok(points)
+}
+
+/// A satellite's mean elements as the catalog gives them, plus the orbit
+/// they describe, for checking what a prediction was made from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementsReport {
+    pub name: String,
+    pub norad_id: u64,
+    pub international_designator: Option<String>,
+    pub epoch: DateTime<Utc>,
+    /// How old the elements are at the query time, in hours. Negative if
+    /// the elements are from later.
+    pub epoch_age_hours: f64,
+    /// Mean motion in rev/day.
+    pub mean_motion: f64,
+    /// First derivative of mean motion in rev/day^2.
+    pub mean_motion_dot: f64,
+    /// Second derivative of mean motion in rev/day^3.
+    pub mean_motion_ddot: f64,
+    pub eccentricity: f64,
+    /// Degrees.
+    pub inclination: f64,
+    /// Right ascension of the ascending node in degrees.
+    pub right_ascension: f64,
+    /// Degrees.
+    pub argument_of_perigee: f64,
+    /// Degrees, at epoch.
+    pub mean_anomaly: f64,
+    /// B* drag term in 1/earth radii.
+    pub bstar: f64,
+    pub element_set_number: u64,
+    pub revolution_number: u64,
+    pub period_minutes: f64,
+    pub semi_major_axis_km: f64,
+    /// Height above the equatorial radius in km.
+    pub apogee_km: f64,
+    /// Height above the equatorial radius in km.
+    pub perigee_km: f64,
+    pub orbit_class: OrbitClass,
+}
+
+/// The elements `sat` was loaded with, and what they work out to, as of
+/// `time`.
+pub fn elements(sat: &Satellite, time: DateTime<Utc>) -> ElementsReport {
+    ElementsReport {
+        name: sat.name.clone(),
+        norad_id: sat.norad_id,
+        international_designator: sat.international_designator.clone(),
+        epoch: sat.epoch,
+        epoch_age_hours: (time - sat.epoch).num_milliseconds() as f64 / 3_600_000.0,
+        mean_motion: sat.mean_motion,
+        mean_motion_dot: sat.mean_motion_dot,
+        mean_motion_ddot: sat.mean_motion_ddot,
+        eccentricity: sat.eccentricity,
+        inclination: sat.inclination,
+        right_ascension: sat.right_ascension,
+        argument_of_perigee: sat.argument_of_perigee,
+        mean_anomaly: sat.mean_anomaly,
+        bstar: sat.bstar,
+        element_set_number: sat.element_set_number,
+        revolution_number: sat.revolution_number,
+        period_minutes: sat.period_minutes(),
+        semi_major_axis_km: semi_major_axis(sat.mean_motion),
+        apogee_km: apogee_altitude(sat.mean_motion, sat.eccentricity),
+        perigee_km: sat.perigee_km,
+        orbit_class: sat.orbit_class(),
+    }
+}
+
+/// One sample of a satellite's path across the sky.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrailPoint {
+    pub time: DateTime<Utc>,
+    /// Seconds from the middle of the trail, negative before it.
+    pub offset_seconds: f64,
+    /// Degrees clockwise from true north.
+    pub azimuth: f64,
+    /// Degrees above the horizon, corrected for refraction like a report's.
+    pub elevation: f64,
+    /// Slant range in km.
+    pub range_km: f64,
+    /// Subpoint latitude in degrees.
+    pub lat: f64,
+    /// Subpoint longitude in degrees, in [-180, 180).
+    pub lon: f64,
+    /// Height above the WGS84 ellipsoid in km.
+    pub alt_km: f64,
+    /// False when the satellite is below the horizon. Those samples are
+    /// kept, so a trail shows where the satellite rises and sets.
+    pub above_horizon: bool,
+}
+
+/// Where a satellite is every `step_seconds` from `minutes` before `center`
+/// to `minutes` after, in order, for showing the path it takes across the
+/// sky. Given the same `delta_ut1` as [`Propagated::delta_ut1`], the sample
+/// at `center` has exactly the look angles and subpoint of a report at that
+/// time. Times SGP4 can't propagate to are left out.
+pub fn trail(
+    sat: &Satellite,
+    observer: &Observer,
+    center: DateTime<Utc>,
+    minutes: f64,
+    step_seconds: f64,
+    delta_ut1: f64,
+) -> Vec<TrailPoint> {
+    let steps = (minutes * 60.0 / step_seconds).floor() as i64;
+    (-steps..=steps)
+        .filter_map(|i| {
+            let offset_seconds = i as f64 * step_seconds;
+            let time = center + seconds(offset_seconds);
+            let (position, _) = sat.try_ecef_at_ut1(time, delta_ut1).ok()?;
+            let geo = ecef_to_geodetic(position);
+            let look = look_angles(observer, position);
+            Some(TrailPoint {
+                time,
+                offset_seconds,
+                azimuth: look.azimuth,
+                elevation: look.elevation,
+                range_km: look.range_km,
+                lat: geo.lat,
+                lon: geo.lon,
+                alt_km: geo.alt_km,
+                above_horizon: look.elevation >= 0.0,
+            })
+        })
+        .collect()
+}
+
+/// Propagates a satellite to the given time and works out where it is
+/// relative to the observer. Returns None if propagation fails.
+pub fn report(sat: &Satellite, observer: &Observer, now: DateTime<Utc>) -> Option<SatelliteReport> {
+    let (position, velocity) = sat.ecef_at(now)?;
+    let propagated = Propagated {
+        satellite: sat,
+        time: now,
+        position,
+        velocity,
+        delta_ut1: 0.0,
+    };
+    Some(report_propagated(&propagated, observer))
+}
+
+/// Works out where an already-propagated satellite is relative to the
+/// observer.
+pub fn report_propagated(p: &Propagated, observer: &Observer) -> SatelliteReport {
+    let (sat, now, position, velocity, delta_ut1) =
+        (p.satellite, p.time, p.position, p.velocity, p.delta_ut1);
+    let geo = ecef_to_geodetic(position);
+    let look = look_angles(observer, position);
+    let range_rate = range_rate(observer, position, velocity);
+    let (azimuth_rate, elevation_rate) = look_angle_rates(observer, position, velocity);
+    let sun = sun::sun_ecef(now, delta_ut1);
+    let sunlit = sun::is_sunlit(position, sun);
+    let sun_elevation = sun::sun_elevation(observer, sun);
+    let twilight = Twilight::from_sun_elevation(sun_elevation);
+    let culmination_in_seconds = (elevation_rate > MIN_RISING_RATE_DEG_PER_S)
+        .then(|| culmination_in(sat, observer, now, look.elevation, delta_ut1))
+        .flatten();
+    SatelliteReport {
+        name: sat.name.clone(),
+        norad_id: sat.norad_id,
+        time: now,
+        epoch_age_hours: (now - sat.epoch).num_milliseconds() as f64 / 3_600_000.0,
+        lat: geo.lat,
+        lon: geo.lon,
+        alt_km: geo.alt_km,
+        azimuth: look.azimuth,
+        magnetic_azimuth: None,
+        magnetic_declination: None,
+        elevation: look.elevation,
+        range_km: look.range_km,
+        range_rate_kms: range_rate,
+        approaching: range_rate < 0.0,
+        ground_km: haversine_distance(observer.lat, observer.lon, geo.lat, geo.lon),
+        ground_bearing: ground_bearing(observer.lat, observer.lon, geo.lat, geo.lon),
+        heading: ground_heading(geo.lat, geo.lon, velocity),
+        ground_speed_kms: (velocity[0].powi(2) + velocity[1].powi(2) + velocity[2].powi(2)).sqrt(),
+        inertial_speed_kms: inertial_speed(position, velocity),
+        period_minutes: sat.period_minutes(),
+        decaying: sat.is_decaying(),
+        sunlit,
+        magnitude: sat.standard_magnitude.filter(|_| sunlit).map(|std_mag| {
+            let obs = observer_ecef(observer);
+            let phase = angle_between(sub(sun, position), sub(obs, position));
+            visual_magnitude(look.range_km, phase, std_mag)
+        }),
+        azimuth_rate_deg_per_s: azimuth_rate,
+        elevation_rate_deg_per_s: elevation_rate,
+        culmination_in_seconds,
+        sun_elevation,
+        twilight,
+        visibility: Visibility::new(twilight, sunlit),
+        trail: Vec::new(),
+    }
+}
+
+// Steps a rising satellite forward until its elevation starts dropping, then
+// narrows down when it peaked. Returns the seconds from `now` to the peak, or
+// None if it's still climbing at the end of the window.
+fn culmination_in(
+    sat: &Satellite,
+    observer: &Observer,
+    now: DateTime<Utc>,
+    elevation_now: f64,
+    delta_ut1: f64,
+) -> Option<f64> {
+    let elevation = |t| {
+        sat.try_ecef_at_ut1(t, delta_ut1)
+            .map_or(f64::NEG_INFINITY, |(p, _)| {
+                look_angles(observer, p).elevation
+            })
+    };
+    let step = seconds(CULMINATION_STEP_SECONDS);
+    let end = now + seconds(CULMINATION_WINDOW_SECONDS);
+    let (mut t, mut highest) = (now, elevation_now);
+    loop {
+        let next = t + step;
+        if next > end {
+            return None;
+        }
+        let e = elevation(next);
+        if e < highest {
+            break;
+        }
+        (t, highest) = (next, e);
+    }
+    let peak = culmination(elevation, (t - step).max(now), t + step, step, seconds(1.0));
+    Some((peak - now).num_milliseconds() as f64 / 1000.0)
+}
+
+/// Everything at least `min_elevation_deg` above the horizon at time `t`,
+/// closest first.
+pub fn overhead(
+    satellites: &[Satellite],
+    observer: &Observer,
+    t: DateTime<Utc>,
+    min_elevation_deg: f64,
+) -> Vec<SatelliteReport> {
+    overhead_propagated(&propagate(satellites, t), observer, min_elevation_deg)
+}
+
+/// Like [`overhead`], for satellites that have already been propagated.
+pub fn overhead_propagated(
+    propagated: &[Propagated],
+    observer: &Observer,
+    min_elevation_deg: f64,
+) -> Vec<SatelliteReport> {
+    let mut reports = propagated
+        .iter()
+        .filter(|p| look_angles(observer, p.position).elevation >= min_elevation_deg)
+        .map(|p| report_propagated(p, observer))
+        .collect::<Vec<_>>();
+    reports.sort_by(|a, b| a.range_km.total_cmp(&b.range_km));
+    reports
+}
+
+/// Reports on every satellite whose subpoint is in `area`, whether or not
+/// the observer can see it, nearest subpoint first.
+pub fn in_area(
+    propagated: &[Propagated],
+    observer: &Observer,
+    area: &Area,
+) -> Vec<SatelliteReport> {
+    let mut reports = propagated
+        .iter()
+        .filter(|p| {
+            let geo = ecef_to_geodetic(p.position);
+            area.contains(geo.lat, geo.lon)
+        })
+        .map(|p| report_propagated(p, observer))
+        .collect::<Vec<_>>();
+    reports.sort_by(|a, b| a.ground_km.total_cmp(&b.ground_km));
+    reports
+}
+
+/// What order [`OverheadQuery`] reports satellites in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RankBy {
+    /// Closest first, by the straight-line distance from the observer.
+    #[default]
+    SlantRange,
+    /// Nearest subpoint first.
+    Ground,
+    /// Highest in the sky first.
+    Elevation,
+    /// Brightest first, with the ones of unknown brightness last.
+    Magnitude,
+}
+
+impl fmt::Display for RankBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            RankBy::SlantRange => "slant-range",
+            RankBy::Ground => "ground",
+            RankBy::Elevation => "elevation",
+            RankBy::Magnitude => "magnitude",
+        })
+    }
+}
+
+impl FromStr for RankBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            // Plain "range" was the only kind of range there was at first.
+            "slant-range" | "range" => Ok(RankBy::SlantRange),
+            "ground" => Ok(RankBy::Ground),
+            "elevation" => Ok(RankBy::Elevation),
+            "magnitude" => Ok(RankBy::Magnitude),
+            _ => Err(format!(
+                "expected slant-range, ground, elevation or magnitude, got {:?}",
+                s
+            )),
+        }
+    }
+}
+
+/// A question about what's overhead, built up a setting at a time. Unless
+/// they're changed, it's for now, everything at least 0 degrees up, closest
+/// first, with no limit on how many.
+///
+/// ```
+/// use whatsoverhead::query::{OverheadQuery, RankBy};
+/// use whatsoverhead::{data, Observer};
+///
+/// let catalog = data::bundled_catalog();
+/// let observer = Observer {
+///     lat: 34.05,
+///     lon: -118.24,
+///     alt_m: 100.0,
+///     atmosphere: None,
+/// };
+/// let time = "2026-03-01T04:00:00Z".parse()?;
+/// let sightings = OverheadQuery::new(observer)
+///     .at(time)
+///     .min_elevation(10.0)
+///     .rank_by(RankBy::SlantRange)
+///     .top(5)
+///     .visible_only(true)
+///     .run(&catalog);
+/// assert!(sightings.len() <= 5);
+/// assert!(sightings.iter().all(|s| s.elevation >= 10.0));
+/// assert!(sightings.windows(2).all(|w| w[0].range_km <= w[1].range_km));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct OverheadQuery {
+    observer: Observer,
+    time: Option<DateTime<Utc>>,
+    min_elevation_deg: f64,
+    area: Option<Area>,
+    max_magnitude: Option<f64>,
+    visible_only: bool,
+    rank_by: RankBy,
+    top: Option<usize>,
+    delta_ut1: f64,
+}
+
+impl OverheadQuery {
+    pub fn new(observer: Observer) -> OverheadQuery {
+        OverheadQuery {
+            observer,
+            time: None,
+            min_elevation_deg: 0.0,
+            area: None,
+            max_magnitude: None,
+            visible_only: false,
+            rank_by: RankBy::default(),
+            top: None,
+            delta_ut1: 0.0,
+        }
+    }
+
+    /// The moment to report on, rather than when the query is run. This
+    /// doesn't matter to [`OverheadQuery::run_propagated`].
+    pub fn at(mut self, time: DateTime<Utc>) -> OverheadQuery {
+        self.time = Some(time);
+        self
+    }
+
+    /// Only satellites at least this many degrees above the horizon.
+    pub fn min_elevation(mut self, degrees: f64) -> OverheadQuery {
+        self.min_elevation_deg = degrees;
+        self
+    }
+
+    /// Instead of satellites above the horizon, every satellite whose
+    /// subpoint is in `area`, as [`in_area`] finds them.
+    pub fn within(mut self, area: Area) -> OverheadQuery {
+        self.area = Some(area);
+        self
+    }
+
+    /// Only satellites at least this bright, dropping the ones whose
+    /// brightness isn't known.
+    pub fn max_magnitude(mut self, magnitude: f64) -> OverheadQuery {
+        self.max_magnitude = Some(magnitude);
+        self
+    }
+
+    /// Only satellites that could be seen with the eye: sunlit, against a
+    /// dark enough sky.
+    pub fn visible_only(mut self, visible_only: bool) -> OverheadQuery {
+        self.visible_only = visible_only;
+        self
+    }
+
+    pub fn rank_by(mut self, rank_by: RankBy) -> OverheadQuery {
+        self.rank_by = rank_by;
+        self
+    }
+
+    /// At most this many satellites, the best ranked ones.
+    pub fn top(mut self, n: usize) -> OverheadQuery {
+        self.top = Some(n);
+        self
+    }
+
+    /// UT1 - UTC in seconds, as for [`Propagator::with_delta_ut1`], for
+    /// [`OverheadQuery::run`] to propagate with. Satellites given to
+    /// [`OverheadQuery::run_propagated`] were already propagated with theirs.
+    pub fn delta_ut1(mut self, seconds: f64) -> OverheadQuery {
+        self.delta_ut1 = seconds;
+        self
+    }
+
+    /// Propagates the satellites, from a [`Catalog`], a [`Propagator`] or
+    /// just a slice of them, and answers the query.
+    pub fn run(&self, satellites: impl AsRef<[Satellite]>) -> Vec<SatelliteReport> {
+        let t = self.time.unwrap_or_else(Utc::now);
+        self.run_propagated(&self.propagate(satellites.as_ref(), t))
+    }
+
+    /// Steps through the 24 hours after the query's time and finds the
+    /// moment when the most satellites answer it, leaving out
+    /// [`OverheadQuery::top`]. Returns that time, the count, and the NORAD
+    /// IDs of the satellites, best ranked first. Ties go to the earliest
+    /// time. Steps are rounded down to whole milliseconds, so it's an error
+    /// for `step_seconds` to be less than one.
+    pub fn peak(
+        &self,
+        satellites: impl AsRef<[Satellite]>,
+        step_seconds: f64,
+    ) -> anyhow::Result<(DateTime<Utc>, usize, Vec<u64>)> {
+        let step = seconds(step_seconds);
+        anyhow::ensure!(
+            step > chrono::Duration::zero(),
+            "the step must be at least a millisecond, got {} seconds",
+            step_seconds
+        );
+        let start = self.time.unwrap_or_else(Utc::now);
+        let end = start + chrono::Duration::hours(24);
+        let mut best = (start, 0, Vec::new());
+        let mut t = start;
+        while t <= end {
+            let found = self.select(&self.propagate(satellites.as_ref(), t));
+            if found.len() > best.1 {
+                let ids = found.iter().map(|(sat, _)| sat.norad_id).collect();
+                best = (t, found.len(), ids);
+            }
+            t += step;
+        }
+        Ok(best)
+    }
+
+    /// Works out which satellites could be the thing seen from the query's
+    /// observer, at its time, at azimuth `azimuth_deg` and elevation
+    /// `elevation_deg`: everything within `tolerance_deg` of it on the sky.
+    /// If there's nothing that close, it tries once more with the tolerance
+    /// widened by [`WIDENED_TOLERANCE_FACTOR`], since observed positions are
+    /// often rough. Only the observer, time and UT1 - UTC of the query
+    /// matter here; where it was seen is the only filter.
+    pub fn identify(
+        &self,
+        satellites: impl AsRef<[Satellite]>,
+        azimuth_deg: f64,
+        elevation_deg: f64,
+        tolerance_deg: f64,
+    ) -> Identification {
+        let observer = &self.observer;
+        let t = self.time.unwrap_or_else(Utc::now);
+        // Separations only need look angles, so only the satellites that
+        // might be candidates, even after widening, get a full report.
+        let widest = tolerance_deg * WIDENED_TOLERANCE_FACTOR;
+        let mut all = self
+            .propagate(satellites.as_ref(), t)
+            .into_iter()
+            .filter_map(|p| {
+                let look = look_angles(observer, p.position);
+                let separation_deg =
+                    angular_separation(azimuth_deg, elevation_deg, look.azimuth, look.elevation);
+                (separation_deg <= widest).then(|| Candidate {
+                    separation_deg,
+                    report: report_propagated(&p, observer),
+                })
+            })
+            .collect::<Vec<_>>();
+        all.sort_by(|a, b| a.separation_deg.total_cmp(&b.separation_deg));
+        let within = |tolerance: f64| {
+            all.iter()
+                .take_while(|c| c.separation_deg <= tolerance)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let candidates = within(tolerance_deg);
+        if !candidates.is_empty() {
+            return Identification {
+                candidates,
+                tolerance_deg,
+                widened: false,
+            };
+        }
+        Identification {
+            candidates: within(widest),
+            tolerance_deg: widest,
+            widened: true,
+        }
+    }
+
+    // Everything but the implausible, at `t`.
+    fn propagate<'a>(&self, satellites: &'a [Satellite], t: DateTime<Utc>) -> Vec<Propagated<'a>> {
+        let mut propagated = propagate_ut1(satellites, t, self.delta_ut1, || {}).0;
+        drop_implausible(&mut propagated);
+        propagated
+    }
+
+    /// Answers the query for satellites that have already been propagated,
+    /// at whatever time they were propagated to.
+    pub fn run_propagated(&self, propagated: &[Propagated]) -> Vec<SatelliteReport> {
+        self.run_propagated_with_satellites(propagated)
+            .into_iter()
+            .map(|(_, sighting)| sighting)
+            .collect()
+    }
+
+    /// [`OverheadQuery::run_propagated`], with the satellite each sighting
+    /// is of, for working out more about it later. With duplicate element
+    /// sets in the catalog the NORAD ID alone doesn't say which that was.
+    pub fn run_propagated_with_satellites<'a>(
+        &self,
+        propagated: &[Propagated<'a>],
+    ) -> Vec<(&'a Satellite, SatelliteReport)> {
+        let mut found = self.select(propagated);
+        if let Some(n) = self.top {
+            found.truncate(n);
+        }
+        found
+    }
+
+    // The answer, ranked, before it's cut down to the top few.
+    fn select<'a>(&self, propagated: &[Propagated<'a>]) -> Vec<(&'a Satellite, SatelliteReport)> {
+        let observer = &self.observer;
+        let mut found = propagated
+            .iter()
+            .filter(|p| match &self.area {
+                Some(area) => {
+                    let geo = ecef_to_geodetic(p.position);
+                    area.contains(geo.lat, geo.lon)
+                }
+                None => look_angles(observer, p.position).elevation >= self.min_elevation_deg,
+            })
+            .map(|p| (p.satellite, report_propagated(p, observer)))
+            .collect::<Vec<_>>();
+        // As in_area and overhead_propagated order them.
+        match self.area {
+            Some(_) => found.sort_by(|(_, a), (_, b)| a.ground_km.total_cmp(&b.ground_km)),
+            None => found.sort_by(|(_, a), (_, b)| a.range_km.total_cmp(&b.range_km)),
+        }
+        if let Some(max) = self.max_magnitude {
+            found.retain(|(_, r)| r.magnitude.is_some_and(|m| m <= max));
+        }
+        if self.visible_only {
+            found.retain(|(_, r)| r.visibility == Visibility::Visible);
+        }
+        // The sorts are stable, so ties stay closest first.
+        match self.rank_by {
+            RankBy::SlantRange => found.sort_by(|(_, a), (_, b)| a.range_km.total_cmp(&b.range_km)),
+            RankBy::Ground => found.sort_by(|(_, a), (_, b)| a.ground_km.total_cmp(&b.ground_km)),
+            RankBy::Elevation => {
+                found.sort_by(|(_, a), (_, b)| b.elevation.total_cmp(&a.elevation))
+            }
+            RankBy::Magnitude => found.sort_by(|(_, a), (_, b)| {
+                let m = |r: &SatelliteReport| r.magnitude.unwrap_or(f64::INFINITY);
+                m(a).total_cmp(&m(b))
+            }),
+        }
+        found
+    }
+}
+
+/// Two satellites close together in the observer's sky.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pair {
+    pub a: SatelliteReport,
+    pub b: SatelliteReport,
+    /// Angle between them on the sky in degrees.
+    pub separation_deg: f64,
+}
+
+/// Every pair of reports less than `max_separation_deg` apart on the sky,
+/// closest first. This checks all pairs, so pass it reports that have
+/// already been filtered down to what's worth looking at.
+pub fn close_pairs(reports: &[SatelliteReport], max_separation_deg: f64) -> Vec<Pair> {
+    let mut pairs = Vec::new();
+    for (i, a) in reports.iter().enumerate() {
+        for b in &reports[i + 1..] {
+            let separation = angular_separation(a.azimuth, a.elevation, b.azimuth, b.elevation);
+            if separation < max_separation_deg {
+                pairs.push(Pair {
+                    a: a.clone(),
+                    b: b.clone(),
+                    separation_deg: separation,
+                });
+            }
+        }
+    }
+    pairs.sort_by(|x, y| x.separation_deg.total_cmp(&y.separation_deg));
+    pairs
+}
+
+/// A satellite that might be what someone saw.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Candidate {
+    pub report: SatelliteReport,
+    /// Angle on the sky between the observation and the satellite in
+    /// degrees.
+    pub separation_deg: f64,
+}
+
+/// The result of trying to identify an observation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Identification {
+    /// Closest match first.
+    pub candidates: Vec<Candidate>,
+    /// The tolerance the candidates were found with.
+    pub tolerance_deg: f64,
+    /// True if nothing was within the requested tolerance and the search
+    /// was widened.
+    pub widened: bool,
+}
+
+/// How much `identify` widens its search when nothing is close enough.
+pub const WIDENED_TOLERANCE_FACTOR: f64 = 3.0;
+
+/// Works out which satellites could be the thing seen at azimuth
+/// `azimuth_deg` and elevation `elevation_deg` at time `t`; see
+/// [`OverheadQuery::identify`].
+pub fn identify(
+    satellites: &[Satellite],
+    observer: &Observer,
+    t: DateTime<Utc>,
+    azimuth_deg: f64,
+    elevation_deg: f64,
+    tolerance_deg: f64,
+) -> Identification {
+    OverheadQuery::new(observer.clone()).at(t).identify(
+        satellites,
+        azimuth_deg,
+        elevation_deg,
+        tolerance_deg,
+    )
+}
+
+/// Steps through the 24 hours after `start` and finds the moment when the
+/// most satellites are at least `min_elevation_deg` up; see
+/// [`OverheadQuery::peak`].
+pub fn max_simultaneous_overhead(
+    satellites: &[Satellite],
+    observer: &Observer,
+    min_elevation_deg: f64,
+    start: DateTime<Utc>,
+    step_seconds: f64,
+) -> anyhow::Result<(DateTime<Utc>, usize, Vec<u64>)> {
+    OverheadQuery::new(observer.clone())
+        .at(start)
+        .min_elevation(min_elevation_deg)
+        .peak(satellites, step_seconds)
+}
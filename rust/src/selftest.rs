@@ -0,0 +1,328 @@
+//! Checking the propagation and frame conversions against published
+//! answers, so a wiring mistake or a platform quirk shows up as a number
+//! rather than as satellites in the wrong place.
+//!
+//! Each stage is checked on its own, so it's clear which one is off:
+//!
+//! - propagation: Vallado's SGP4 verification set (Vallado, Crawford,
+//!   Hujsak and Kelso, "Revisiting Spacetrack Report #3", AIAA 2006-6753),
+//!   propagated by the sgp4 library and compared with the TEME positions
+//!   published with it
+//! - pipeline: the same set loaded into [`Satellite`]s and propagated to
+//!   ECEF the way every prediction is
+//! - gmst: sidereal time against Vallado's Example 3-5
+//! - teme_to_ecef: the TEME to pseudo-Earth-fixed rotation against
+//!   Vallado's Example 3-15, without polar motion, which we ignore
+//! - geodetic: ECEF to latitude, longitude and height against Vallado's
+//!   Example 3-3
+
+use anyhow::{Context, Result};
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::coords::{self, WGS84_A};
+use crate::data::{OmmRecord, Satellite};
+
+/// The verification TLEs and the TEME states they should propagate to,
+/// leaving out the few times the set expects SGP4 to fail at.
+pub const BUNDLED_VECTORS: &str = include_str!("../data/sgp4-verification.json");
+
+/// How far off each stage may be, in its own units, before the self-test
+/// fails.
+pub const PROPAGATION_TOLERANCE_KM: f64 = 1e-5;
+pub const PIPELINE_TOLERANCE_KM: f64 = 1.0;
+pub const GMST_TOLERANCE_DEG: f64 = 1e-6;
+pub const TEME_TO_ECEF_TOLERANCE_KM: f64 = 0.001;
+pub const GEODETIC_TOLERANCE_KM: f64 = 0.02;
+
+/// One TLE from the verification set and where it should be.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCase {
+    pub line1: String,
+    pub line2: String,
+    pub states: Vec<State>,
+}
+
+impl TestCase {
+    /// The satellite number from the first line, which is how the set
+    /// refers to its cases.
+    pub fn label(&self) -> String {
+        self.line1.get(2..7).unwrap_or("").trim().to_string()
+    }
+
+    pub fn elements(&self) -> Result<sgp4::Elements> {
+        Ok(sgp4::Elements::from_tle(
+            None,
+            self.line1.as_bytes(),
+            self.line2.as_bytes(),
+        )?)
+    }
+}
+
+/// Cases in the verification set where the improved mode of SGP4, which
+/// predictions use, comes out more than [`PIPELINE_TOLERANCE_KM`] from the
+/// AFSPC-compatible reference: two decaying orbits with very low perigees,
+/// and two with eccentricities near 1. The propagation stage still checks
+/// them.
+pub const IMPROVED_MODE_OUTLIERS: &[&str] = &["22312", "23333", "28350", "33333"];
+
+/// A TEME state some minutes after the TLE's epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct State {
+    pub minutes: f64,
+    /// km.
+    pub position: [f64; 3],
+    /// km/s.
+    pub velocity: [f64; 3],
+}
+
+/// How one stage did.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageResult {
+    pub stage: String,
+    /// How many comparisons were made.
+    pub checks: usize,
+    /// The largest difference from the reference, in `unit`.
+    pub max_error: f64,
+    pub tolerance: f64,
+    pub unit: String,
+    /// Which comparison had the largest error.
+    pub worst: String,
+    /// Comparisons that couldn't be made at all, like a TLE that didn't
+    /// parse. Any of these fail the stage.
+    pub failures: Vec<String>,
+}
+
+impl StageResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty() && self.max_error <= self.tolerance
+    }
+}
+
+/// Reads a verification set in the format of [`BUNDLED_VECTORS`].
+pub fn parse_vectors(json: &str) -> Result<Vec<TestCase>> {
+    serde_json::from_str(json).context("Verification vectors were not well-formatted")
+}
+
+/// Runs every stage, with the bundled verification set for propagation.
+pub fn run() -> Vec<StageResult> {
+    let cases = parse_vectors(BUNDLED_VECTORS).expect("bundled vectors are valid");
+    vec![
+        check_propagation(&cases),
+        check_pipeline(&cases),
+        check_gmst(),
+        check_teme_to_ecef(),
+        check_geodetic(),
+    ]
+}
+
+// Keeps track of the worst comparison in a stage.
+struct Worst {
+    error: f64,
+    label: String,
+}
+
+impl Worst {
+    fn new() -> Worst {
+        Worst {
+            error: 0.0,
+            label: String::new(),
+        }
+    }
+
+    fn add(&mut self, error: f64, label: impl FnOnce() -> String) {
+        // NaN counts as worse than anything.
+        if self.label.is_empty() || error > self.error || (error.is_nan() && !self.error.is_nan()) {
+            self.error = error;
+            self.label = label();
+        }
+    }
+}
+
+fn stage(name: &str, checks: usize, worst: Worst, tolerance: f64, unit: &str) -> StageResult {
+    StageResult {
+        stage: name.to_string(),
+        checks,
+        max_error: worst.error,
+        tolerance,
+        unit: unit.to_string(),
+        worst: worst.label,
+        failures: Vec::new(),
+    }
+}
+
+/// Propagates each case with the sgp4 library in the mode the reference
+/// was computed in, AFSPC compatibility mode, which should reproduce it to
+/// well under a millimeter.
+pub fn check_propagation(cases: &[TestCase]) -> StageResult {
+    let mut worst = Worst::new();
+    let mut failures = Vec::new();
+    let mut checks = 0;
+    for case in cases {
+        let constants = match case
+            .elements()
+            .and_then(|e| Ok(sgp4::Constants::from_elements_afspc_compatibility_mode(&e)?))
+        {
+            Ok(constants) => constants,
+            Err(e) => {
+                failures.push(format!("{}: {}", case.label(), e));
+                continue;
+            }
+        };
+        for state in &case.states {
+            checks += 1;
+            match constants.propagate_afspc_compatibility_mode(state.minutes) {
+                Ok(prediction) => worst.add(distance(prediction.position, state.position), || {
+                    format!("{} after {} minutes", case.label(), state.minutes)
+                }),
+                Err(e) => failures.push(format!(
+                    "{} at {} minutes: {:?}",
+                    case.label(),
+                    state.minutes,
+                    e
+                )),
+            }
+        }
+    }
+    StageResult {
+        failures,
+        ..stage("propagation", checks, worst, PROPAGATION_TOLERANCE_KM, "km")
+    }
+}
+
+/// Loads each case into a [`Satellite`] the way catalogs are loaded and
+/// asks it where it is in ECEF at each reference time, which goes through
+/// the epoch arithmetic, SGP4 in the improved mode we predict with, sidereal
+/// time and the rotation. The reference positions are rotated the same way
+/// to compare. The cases in [`IMPROVED_MODE_OUTLIERS`] are left out.
+pub fn check_pipeline(cases: &[TestCase]) -> StageResult {
+    let mut worst = Worst::new();
+    let mut failures = Vec::new();
+    let mut checks = 0;
+    for case in cases {
+        if IMPROVED_MODE_OUTLIERS.contains(&case.label().as_str()) {
+            continue;
+        }
+        let sat = match case.elements().and_then(|elements| {
+            Ok(Satellite::from_record(&OmmRecord {
+                elements,
+                decay_date: None,
+            })?)
+        }) {
+            Ok(sat) => sat,
+            Err(e) => {
+                failures.push(format!("{}: {}", case.label(), e));
+                continue;
+            }
+        };
+        for state in &case.states {
+            checks += 1;
+            let t = sat.epoch + Duration::nanoseconds((state.minutes * 60e9).round() as i64);
+            let expected = coords::teme_to_ecef(state.position, coords::gmst(t, 0.0));
+            match sat.try_ecef_at(t) {
+                Ok((position, _)) => worst.add(distance(position, expected), || {
+                    format!("{} after {} minutes", case.label(), state.minutes)
+                }),
+                Err(e) => failures.push(format!(
+                    "{} at {} minutes: {}",
+                    case.label(),
+                    state.minutes,
+                    e.kind
+                )),
+            }
+        }
+    }
+    StageResult {
+        failures,
+        ..stage("pipeline", checks, worst, PIPELINE_TOLERANCE_KM, "km")
+    }
+}
+
+/// Sidereal time at 1992-08-20 12:14 UT1, which Vallado gives as
+/// 152.578787810 degrees.
+pub fn check_gmst() -> StageResult {
+    let ut1 = Utc.from_utc_datetime(
+        &NaiveDate::from_ymd_opt(1992, 8, 20)
+            .unwrap()
+            .and_hms_opt(12, 14, 0)
+            .unwrap(),
+    );
+    // The time is already UT1, so there's nothing to correct by.
+    let theta = coords::gmst(ut1, 0.0).to_degrees().rem_euclid(360.0);
+    let mut worst = Worst::new();
+    worst.add(angle_difference(theta, 152.578787810), || {
+        "1992-08-20T12:14:00 UT1".to_string()
+    });
+    stage("gmst", 1, worst, GMST_TOLERANCE_DEG, "deg")
+}
+
+/// Vallado's TEME position at 2004-04-06 07:51:28.386009 UTC, with UT1 -
+/// UTC = -0.4399619 s, rotated into the Earth-fixed frame.
+pub fn check_teme_to_ecef() -> StageResult {
+    let utc = Utc.from_utc_datetime(
+        &NaiveDate::from_ymd_opt(2004, 4, 6)
+            .unwrap()
+            .and_hms_micro_opt(7, 51, 28, 386_009)
+            .unwrap(),
+    );
+    let teme = [5094.18016210, 6127.64465950, 6380.34453270];
+    let pef = [-1033.47503130, 7901.30558560, 6380.34453270];
+    let ecef = coords::teme_to_ecef(teme, coords::gmst(utc, -0.4399619));
+    let mut worst = Worst::new();
+    worst.add(distance(ecef, pef), || {
+        "2004-04-06T07:51:28.386009Z".to_string()
+    });
+    stage("teme_to_ecef", 1, worst, TEME_TO_ECEF_TOLERANCE_KM, "km")
+}
+
+/// Vallado's ECEF position (6524.834, 6862.875, 6448.296) km, which is at
+/// 34.352496 N, 46.4464 E and 5085.22 km up. The error is how far apart
+/// the two points are.
+pub fn check_geodetic() -> StageResult {
+    let geo = coords::ecef_to_geodetic([6524.834, 6862.875, 6448.296]);
+    let expected = coords::Geodetic {
+        lat: 34.352496,
+        lon: 46.4464,
+        alt_km: 5085.22,
+    };
+    // Going back through the observer formula, which is exact, gives the
+    // distance between the two points.
+    let back = |g: coords::Geodetic| {
+        coords::observer_ecef(&coords::Observer {
+            lat: g.lat,
+            lon: g.lon,
+            alt_m: g.alt_km * 1000.0,
+            atmosphere: None,
+        })
+    };
+    let mut worst = Worst::new();
+    worst.add(distance(back(geo), back(expected)), || {
+        "(6524.834, 6862.875, 6448.296) km".to_string()
+    });
+    // The conversion should also undo the observer formula exactly, from
+    // the ground out past GEO.
+    let mut checks = 1;
+    for lat in [-89.9, -45.0, 0.0, 30.0, 60.0, 89.9] {
+        for alt_km in [0.0, 400.0, 20_200.0, 42_164.0 - WGS84_A] {
+            checks += 1;
+            let point = coords::Geodetic {
+                lat,
+                lon: -118.76,
+                alt_km,
+            };
+            let error = distance(back(coords::ecef_to_geodetic(back(point))), back(point));
+            worst.add(error, || format!("round trip at {}, {} km", lat, alt_km));
+        }
+    }
+    stage("geodetic", checks, worst, GEODETIC_TOLERANCE_KM, "km")
+}
+
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let d = coords::sub(a, b);
+    (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+}
+
+fn angle_difference(a: f64, b: f64) -> f64 {
+    let d = (a - b).rem_euclid(360.0);
+    d.min(360.0 - d)
+}
@@ -0,0 +1,600 @@
+// Where reports of what's overhead go. Each --format has a ReportSink that
+// is handed the reports for one location after another. NDJSON, CSV and
+// text are written as the reports come; JSON and GeoJSON are whole
+// documents, so those sinks hold on to what they need until finish.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use whatsoverhead::describe::describe;
+use whatsoverhead::geo::cardinal_direction;
+use whatsoverhead::locations::Place;
+use whatsoverhead::query::SatelliteReport;
+use whatsoverhead::sun::{self, Visibility};
+use whatsoverhead::timeutil::rfc3339;
+use whatsoverhead::units::{NumberStyle, Units};
+
+use crate::{format_duration, write_json, write_ndjson, Args, Format, Summary};
+
+/// How far apart in longitude the points of the terminator in GeoJSON
+/// output are, in degrees.
+const TERMINATOR_STEP_DEG: f64 = 1.0;
+
+pub trait ReportSink {
+    /// Starts the reports for `place`, with its --summary if there is one.
+    fn location(&mut self, place: &Place, summary: Option<&Summary>) -> Result<()>;
+
+    /// Takes the next report for the current location.
+    fn report(&mut self, report: &SatelliteReport) -> Result<()>;
+
+    /// Ends the last location and writes anything still held back.
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// How reports are written beyond what the flags alone say, worked out
+/// from the flags and the config file together.
+#[derive(Clone, Copy)]
+pub struct Output {
+    pub style: NumberStyle,
+    /// Whether a --wmm model was given, on the command line or in the
+    /// config file, so there are magnetic azimuths to make room for.
+    pub magnetic: bool,
+}
+
+/// The sink for `args.format`. `labelled` says whether there are several
+/// locations, so each report needs to say which it's for. GeoJSON output
+/// draws the terminator at `now`.
+pub fn for_format<'a>(
+    out: &'a mut dyn Write,
+    args: &'a Args,
+    output: Output,
+    labelled: bool,
+    now: DateTime<Utc>,
+) -> Box<dyn ReportSink + 'a> {
+    let Output { style, magnetic } = output;
+    match args.format {
+        Format::Text => Box::new(TextSink {
+            out,
+            args,
+            style,
+            labelled,
+            locations: 0,
+            reports: 0,
+            summarized: false,
+        }),
+        Format::Csv => Box::new(CsvSink {
+            w: csv::Writer::from_writer(out),
+            timezone: args.timezone,
+            style,
+            labelled,
+            trails: args.trail.is_some(),
+            magnetic,
+            location: None,
+            wrote_header: false,
+        }),
+        Format::Json => Box::new(JsonSink {
+            out,
+            args,
+            labelled,
+            sections: Vec::new(),
+        }),
+        Format::Geojson => Box::new(GeojsonSink {
+            out,
+            args,
+            labelled,
+            now,
+            location: None,
+            features: Vec::new(),
+        }),
+        Format::Ndjson => Box::new(NdjsonSink {
+            out,
+            args,
+            labelled,
+            location: None,
+        }),
+    }
+}
+
+// Sentences, as many as there is to say about each satellite, with each
+// location under a heading when there are several.
+struct TextSink<'a> {
+    out: &'a mut dyn Write,
+    args: &'a Args,
+    style: NumberStyle,
+    labelled: bool,
+    locations: usize,
+    // How many reports the current location has had.
+    reports: usize,
+    // With --summary the summary line stands in for the reports.
+    summarized: bool,
+}
+
+impl TextSink<'_> {
+    // Says so if the location that's ending had nothing to report.
+    fn end_location(&mut self) -> Result<()> {
+        if self.locations == 0 || self.summarized || self.reports > 0 {
+            return Ok(());
+        }
+        if self.args.within_km.is_some() || self.args.bbox.is_some() {
+            writeln!(self.out, "Nothing is over that area right now.")?;
+        } else {
+            writeln!(self.out, "Nothing is above the horizon right now.")?;
+        }
+        Ok(())
+    }
+}
+
+impl ReportSink for TextSink<'_> {
+    fn location(&mut self, place: &Place, summary: Option<&Summary>) -> Result<()> {
+        self.end_location()?;
+        if self.labelled {
+            if self.locations > 0 {
+                writeln!(self.out)?;
+            }
+            writeln!(self.out, "From {}:", place.name)?;
+        }
+        if let Some(summary) = summary {
+            writeln!(self.out, "{}", summary.line)?;
+        }
+        self.locations += 1;
+        self.reports = 0;
+        self.summarized = summary.is_some();
+        Ok(())
+    }
+
+    fn report(&mut self, s: &SatelliteReport) -> Result<()> {
+        self.reports += 1;
+        if self.summarized {
+            return Ok(());
+        }
+        write_text(self.out, self.args, s, self.style)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.end_location()
+    }
+}
+
+fn write_text(
+    out: &mut dyn Write,
+    args: &Args,
+    s: &SatelliteReport,
+    style: NumberStyle,
+) -> Result<()> {
+    if args.describe {
+        writeln!(out, "{}", describe(s, style))?;
+    } else {
+        let track = match s.ground_bearing {
+            Some(b) => format!(
+                "{} to your {}",
+                style.distance(s.ground_km),
+                cardinal_direction(b)
+            ),
+            None => "right under you".to_string(),
+        };
+        writeln!(
+            out,
+            "{} ({}) is {} away, {} degrees up at azimuth {}{}, {} above {}, {}; its ground track is {}",
+            s.name,
+            s.norad_id,
+            style.distance(s.range_km),
+            style.number(s.elevation, 1),
+            style.number(s.azimuth, 1),
+            s.magnetic_azimuth
+                .map(|m| format!(" ({} magnetic)", style.number(m, 1)))
+                .unwrap_or_default(),
+            style.altitude(s.alt_km),
+            style.number(s.lat, 3),
+            style.number(s.lon, 3),
+            track
+        )?;
+        writeln!(
+            out,
+            "  It's {} at {}",
+            if s.approaching {
+                "getting closer"
+            } else {
+                "moving away"
+            },
+            style.speed(s.range_rate_kms.abs())
+        )?;
+        match (s.rising(), s.culmination_in_seconds) {
+            (true, Some(t)) => writeln!(out, "  It's rising, peaks in {}", format_duration(t))?,
+            (true, None) => writeln!(out, "  It's rising")?,
+            (false, _) if s.setting() => writeln!(out, "  It's setting")?,
+            (false, _) => {}
+        }
+        writeln!(
+            out,
+            "  It's moving at {} relative to the ground ({} inertial) and orbits every {:.1} minutes",
+            style.speed(s.ground_speed_kms),
+            style.speed(s.inertial_speed_kms),
+            s.period_minutes
+        )?;
+        if let Some(m) = s.magnitude {
+            writeln!(out, "  It's about magnitude {:.1}", m)?;
+        }
+        if s.elevation > 0.0 {
+            let seeing = match s.visibility {
+                Visibility::Visible => {
+                    "It's sunlit against a dark sky, so you may be able to see it"
+                }
+                Visibility::Eclipsed => "It's in Earth's shadow, so you won't see it",
+                Visibility::Daylight => "The sky is too bright to see it",
+            };
+            writeln!(out, "  {}", seeing)?;
+        }
+        if s.decaying {
+            writeln!(out, "  {} is decaying and may re-enter soon", s.name)?;
+        }
+        for p in &s.trail {
+            writeln!(
+                out,
+                "  {:+.1} min: {} degrees up at azimuth {}, above {}, {}{}",
+                p.offset_seconds / 60.0,
+                style.number(p.elevation, 1),
+                style.number(p.azimuth, 1),
+                style.number(p.lat, 3),
+                style.number(p.lon, 3),
+                if p.above_horizon {
+                    ""
+                } else {
+                    " (below the horizon)"
+                }
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// An array of reports or, with several locations, of {"location",
+// "satellites"} objects.
+struct JsonSink<'a> {
+    out: &'a mut dyn Write,
+    args: &'a Args,
+    labelled: bool,
+    sections: Vec<JsonSection>,
+}
+
+// What JsonSink has for one location.
+struct JsonSection {
+    place: Place,
+    summary: Option<BTreeMap<String, usize>>,
+    reports: Vec<SatelliteReport>,
+}
+
+// The reports for one location, for --format json.
+#[derive(Serialize)]
+struct LocationReports<'a> {
+    location: &'a Place,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a BTreeMap<String, usize>>,
+    satellites: &'a [SatelliteReport],
+}
+
+// The reports for one location with --summary, for --format json.
+#[derive(Serialize)]
+struct SummarizedReports<'a> {
+    summary: &'a BTreeMap<String, usize>,
+    satellites: &'a [SatelliteReport],
+}
+
+impl ReportSink for JsonSink<'_> {
+    fn location(&mut self, place: &Place, summary: Option<&Summary>) -> Result<()> {
+        self.sections.push(JsonSection {
+            place: place.clone(),
+            summary: summary.map(|s| s.counts.clone()),
+            reports: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn report(&mut self, report: &SatelliteReport) -> Result<()> {
+        if let Some(section) = self.sections.last_mut() {
+            section.reports.push(report.clone());
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.labelled {
+            let nested = self
+                .sections
+                .iter()
+                .map(|section| LocationReports {
+                    location: &section.place,
+                    summary: section.summary.as_ref(),
+                    satellites: &section.reports,
+                })
+                .collect::<Vec<_>>();
+            return write_json(self.out, self.args, &nested);
+        }
+        let Some(section) = self.sections.first() else {
+            return write_json(self.out, self.args, &[] as &[SatelliteReport]);
+        };
+        match &section.summary {
+            Some(summary) => {
+                let both = SummarizedReports {
+                    summary,
+                    satellites: &section.reports,
+                };
+                write_json(self.out, self.args, &both)
+            }
+            None => write_json(self.out, self.args, &section.reports),
+        }
+    }
+}
+
+// A FeatureCollection with a Point for each report and the terminator,
+// which is written at finish.
+struct GeojsonSink<'a> {
+    out: &'a mut dyn Write,
+    args: &'a Args,
+    labelled: bool,
+    now: DateTime<Utc>,
+    location: Option<String>,
+    features: Vec<serde_json::Value>,
+}
+
+impl ReportSink for GeojsonSink<'_> {
+    fn location(&mut self, place: &Place, _: Option<&Summary>) -> Result<()> {
+        self.location = self.labelled.then(|| place.name.clone());
+        Ok(())
+    }
+
+    fn report(&mut self, r: &SatelliteReport) -> Result<()> {
+        let mut properties = serde_json::json!({
+            "layer": "satellites",
+            "name": r.name,
+            "norad_id": r.norad_id,
+            "time": r.time,
+            "alt_km": r.alt_km,
+            "azimuth": r.azimuth,
+            "elevation": r.elevation,
+            "range_km": r.range_km,
+            "visibility": r.visibility,
+        });
+        if let Some(location) = &self.location {
+            properties["location"] = serde_json::json!(location);
+        }
+        self.features.push(serde_json::json!({
+            "type": "Feature",
+            "geometry": {"type": "Point", "coordinates": [r.lon, r.lat]},
+            "properties": properties,
+        }));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        // GeoJSON puts longitude first.
+        let terminator = sun::solar_terminator(self.now, TERMINATOR_STEP_DEG)
+            .into_iter()
+            .map(|(lat, lon)| [lon, lat])
+            .collect::<Vec<_>>();
+        let mut features = std::mem::take(&mut self.features);
+        features.push(serde_json::json!({
+            "type": "Feature",
+            "geometry": {"type": "LineString", "coordinates": terminator},
+            "properties": {"layer": "terminator", "time": self.now},
+        }));
+        write_json(
+            self.out,
+            self.args,
+            &serde_json::json!({"type": "FeatureCollection", "features": features}),
+        )
+    }
+}
+
+// One line per report, written and flushed as soon as it comes.
+struct NdjsonSink<'a> {
+    out: &'a mut dyn Write,
+    args: &'a Args,
+    labelled: bool,
+    location: Option<String>,
+}
+
+// One report, and which location it's for when there are several, for
+// --format ndjson.
+#[derive(Serialize)]
+struct LocatedReport<'a> {
+    #[serde(flatten)]
+    report: &'a SatelliteReport,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<&'a str>,
+}
+
+impl ReportSink for NdjsonSink<'_> {
+    fn location(&mut self, place: &Place, _: Option<&Summary>) -> Result<()> {
+        self.location = self.labelled.then(|| place.name.clone());
+        Ok(())
+    }
+
+    fn report(&mut self, report: &SatelliteReport) -> Result<()> {
+        let line = LocatedReport {
+            report,
+            location: self.location.as_deref(),
+        };
+        write_ndjson(self.out, self.args, &line)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// The CSV columns every report has. The columns stay in the same places in
+// imperial units, but their names say what's in them. Downstream scripts
+// depend on the column order, so only ever add columns at the end, and keep
+// the list in the Format::Csv doc comment in sync.
+fn csv_base_header(units: Units) -> Vec<&'static str> {
+    let imperial = units == Units::Imperial;
+    vec![
+        "timestamp",
+        "norad_id",
+        "name",
+        "lat",
+        "lon",
+        if imperial { "alt_ft" } else { "alt_km" },
+        "az_deg",
+        "el_deg",
+        if imperial { "range_mi" } else { "range_km" },
+        if imperial {
+            "range_rate_mis"
+        } else {
+            "range_rate_kms"
+        },
+        "epoch_age_hours",
+        if imperial {
+            "ground_distance_mi"
+        } else {
+            "ground_distance_km"
+        },
+        "ground_bearing_deg",
+        "decaying",
+        "magnitude",
+        "el_rate_deg_s",
+        "culmination_in_s",
+        "visibility",
+        "az_rate_deg_s",
+    ]
+}
+
+// One row per report, written as it comes. Which columns there are beyond
+// the base ones depends only on the flags, so every run with the same
+// flags has the same columns in the same order: location when there are
+// several locations, the trail ones with --trail, and the magnetic ones
+// with a --wmm model.
+struct CsvSink<'a> {
+    w: csv::Writer<&'a mut dyn Write>,
+    timezone: Tz,
+    style: NumberStyle,
+    labelled: bool,
+    trails: bool,
+    magnetic: bool,
+    location: Option<String>,
+    wrote_header: bool,
+}
+
+impl CsvSink<'_> {
+    fn write_header(&mut self) -> Result<()> {
+        if self.wrote_header {
+            return Ok(());
+        }
+        let mut header = csv_base_header(self.style.units);
+        if self.labelled {
+            header.push("location");
+        }
+        if self.trails {
+            header.extend(["trail_offset_s", "above_horizon"]);
+        }
+        if self.magnetic {
+            header.extend(["az_true_deg", "az_magnetic_deg", "magnetic_declination_deg"]);
+        }
+        self.w.write_record(header)?;
+        self.wrote_header = true;
+        Ok(())
+    }
+}
+
+impl ReportSink for CsvSink<'_> {
+    fn location(&mut self, place: &Place, _: Option<&Summary>) -> Result<()> {
+        self.write_header()?;
+        self.location = self.labelled.then(|| place.name.clone());
+        Ok(())
+    }
+
+    fn report(&mut self, s: &SatelliteReport) -> Result<()> {
+        self.write_header()?;
+        let location = self.location.clone().unwrap_or_default();
+        let mut row = vec![
+            rfc3339(s.time, self.timezone),
+            s.norad_id.to_string(),
+            s.name.clone(),
+            self.style.number(s.lat, 4),
+            self.style.number(s.lon, 4),
+            self.style.number(self.style.units.altitude(s.alt_km).0, 3),
+            self.style.number(s.azimuth, 3),
+            self.style.number(s.elevation, 3),
+            self.style
+                .number(self.style.units.distance(s.range_km).0, 3),
+            format!("{:.4}", self.style.units.speed(s.range_rate_kms).0),
+            format!("{:.2}", s.epoch_age_hours),
+            self.style
+                .number(self.style.units.distance(s.ground_km).0, 3),
+            s.ground_bearing
+                .map(|b| self.style.number(b, 3))
+                .unwrap_or_default(),
+            s.decaying.to_string(),
+            s.magnitude.map(|m| format!("{:.1}", m)).unwrap_or_default(),
+            format!("{:.4}", s.elevation_rate_deg_per_s),
+            s.culmination_in_seconds
+                .map(|t| format!("{:.0}", t))
+                .unwrap_or_default(),
+            s.visibility.to_string(),
+            format!("{:.4}", s.azimuth_rate_deg_per_s),
+        ];
+        if self.labelled {
+            row.push(location.clone());
+        }
+        if self.trails {
+            row.extend([String::new(), String::new()]);
+        }
+        if self.magnetic {
+            row.extend([
+                self.style.number(s.azimuth, 3),
+                s.magnetic_azimuth
+                    .map(|a| self.style.number(a, 3))
+                    .unwrap_or_default(),
+                s.magnetic_declination
+                    .map(|d| self.style.number(d, 3))
+                    .unwrap_or_default(),
+            ]);
+        }
+        self.w.write_record(row)?;
+        // Each trail sample gets a row of its own with what it has: the
+        // time, where it is and the look angles.
+        let base = csv_base_header(self.style.units).len();
+        for p in &s.trail {
+            let mut row = vec![
+                rfc3339(p.time, self.timezone),
+                s.norad_id.to_string(),
+                s.name.clone(),
+                self.style.number(p.lat, 4),
+                self.style.number(p.lon, 4),
+                self.style.number(self.style.units.altitude(p.alt_km).0, 3),
+                self.style.number(p.azimuth, 3),
+                self.style.number(p.elevation, 3),
+                self.style
+                    .number(self.style.units.distance(p.range_km).0, 3),
+            ];
+            // Blanks for everything from range_rate_kms to az_rate_deg_s.
+            row.resize(base, String::new());
+            if self.labelled {
+                row.push(location.clone());
+            }
+            row.extend([
+                format!("{:.0}", p.offset_seconds),
+                p.above_horizon.to_string(),
+            ]);
+            if self.magnetic {
+                row.extend([
+                    self.style.number(p.azimuth, 3),
+                    String::new(),
+                    String::new(),
+                ]);
+            }
+            self.w.write_record(row)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.write_header()?;
+        self.w.flush()?;
+        Ok(())
+    }
+}
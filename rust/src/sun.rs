@@ -0,0 +1,199 @@
+//! Where the Sun is, to the accuracy needed for lighting satellites.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::coords::{gmst, observer_ecef, sub, teme_to_ecef, Observer};
+
+const AU_KM: f64 = 149_597_870.7;
+const EARTH_RADIUS_KM: f64 = 6378.137;
+const SUN_RADIUS_KM: f64 = 696_000.0;
+
+/// The Sun's geocentric position in km, in the equatorial frame of date
+/// (close enough to TEME to rotate into ECEF the same way).
+///
+/// Uses the low-precision formula from the Astronomical Almanac, good to
+/// about 0.01 degrees between 1950 and 2050.
+pub fn sun_position(t: DateTime<Utc>) -> [f64; 3] {
+    let jd = t.timestamp_millis() as f64 / 86_400_000.0 + 2_440_587.5;
+    let n = jd - 2_451_545.0;
+    let l = (280.460 + 0.985_647_4 * n).to_radians();
+    let g = (357.528 + 0.985_600_3 * n).to_radians();
+    let lambda = l + (1.915 * g.sin() + 0.020 * (2.0 * g).sin()).to_radians();
+    let epsilon = (23.439 - 0.000_000_4 * n).to_radians();
+    let r = (1.000_14 - 0.016_71 * g.cos() - 0.000_14 * (2.0 * g).cos()) * AU_KM;
+    [
+        r * lambda.cos(),
+        r * epsilon.cos() * lambda.sin(),
+        r * epsilon.sin() * lambda.sin(),
+    ]
+}
+
+/// The Sun's position in ECEF, in km, with UT1 - UTC as for `gmst`.
+pub fn sun_ecef(t: DateTime<Utc>, delta_ut1: f64) -> [f64; 3] {
+    teme_to_ecef(sun_position(t), gmst(t, delta_ut1))
+}
+
+/// Points along the day/night boundary at `t`, as (latitude, longitude) in
+/// degrees, every `step_lon_deg` of longitude from -180 to 180 inclusive.
+/// This is where the Sun's center is 90 degrees from the zenith on a
+/// spherical Earth, without refraction, so it's good to a few tenths of a
+/// degree, which is plenty for drawing on a map, and ignores UT1 - UTC.
+///
+/// Panics unless `step_lon_deg` is positive.
+pub fn solar_terminator(t: DateTime<Utc>, step_lon_deg: f64) -> Vec<(f64, f64)> {
+    assert!(step_lon_deg > 0.0, "step_lon_deg must be positive");
+    let sun = sun_ecef(t, 0.0);
+    let subsolar_lon = sun[1].atan2(sun[0]);
+    // At an equinox the terminator runs through the poles, and the
+    // formula divides by zero; nudging the declination keeps it finite.
+    let tan_declination = match sun[2].atan2(sun[0].hypot(sun[1])).tan() {
+        d if d.abs() < 1e-9 => 1e-9_f64.copysign(d),
+        d => d,
+    };
+    let steps = (360.0 / step_lon_deg).ceil() as usize;
+    (0..=steps)
+        .map(|i| {
+            let lon = (-180.0 + i as f64 * step_lon_deg).min(180.0);
+            let lat = (-(lon.to_radians() - subsolar_lon).cos() / tan_declination)
+                .atan()
+                .to_degrees();
+            (lat, lon)
+        })
+        .collect()
+}
+
+/// How much of the Sun a satellite can see.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shadow {
+    Sunlit,
+    /// Earth hides part of the Sun.
+    Penumbra,
+    /// Earth hides all of the Sun.
+    Umbra,
+}
+
+/// Which part of Earth's shadow, if any, a satellite at `sat` is in, with
+/// the Sun at `sun`, both in the same Earth-centered frame in km.
+///
+/// The umbra is treated as a cylinder the width of the Earth, as in
+/// [`is_sunlit`], and the penumbra as the cone of lines touching both the
+/// Sun and the Earth on opposite sides, which widens behind the Earth.
+pub fn shadow(sat: [f64; 3], sun: [f64; 3]) -> Shadow {
+    if is_sunlit(sat, sun) {
+        let sun_dist = dot(sun, sun).sqrt();
+        let along = dot(sat, sun) / sun_dist;
+        if along > 0.0 {
+            return Shadow::Sunlit;
+        }
+        // The penumbra cone's apex is between the Earth and the Sun.
+        let half_angle = ((SUN_RADIUS_KM + EARTH_RADIUS_KM) / sun_dist).asin();
+        let apex = EARTH_RADIUS_KM / half_angle.sin();
+        let radius = (apex - along) * half_angle.tan();
+        let perp2 = dot(sat, sat) - along * along;
+        if perp2 < radius * radius {
+            Shadow::Penumbra
+        } else {
+            Shadow::Sunlit
+        }
+    } else {
+        Shadow::Umbra
+    }
+}
+
+/// True if a satellite at `sat` is lit by the Sun at `sun`, both in the same
+/// Earth-centered frame in km.
+///
+/// This treats Earth's shadow as a cylinder, which is off by at most a few
+/// seconds at shadow entry and exit for LEO.
+pub fn is_sunlit(sat: [f64; 3], sun: [f64; 3]) -> bool {
+    let sun_dist = dot(sun, sun).sqrt();
+    let along = dot(sat, sun) / sun_dist;
+    if along > 0.0 {
+        return true;
+    }
+    let perp2 = dot(sat, sat) - along * along;
+    perp2 > EARTH_RADIUS_KM * EARTH_RADIUS_KM
+}
+
+/// The Sun's geometric elevation from the observer in degrees, given its
+/// ECEF position from [`sun_ecef`]. The direction to the Sun is measured from
+/// the observer's ellipsoid normal, so this is what sunrise tables use
+/// before refraction.
+pub fn sun_elevation(observer: &Observer, sun: [f64; 3]) -> f64 {
+    let d = sub(sun, observer_ecef(observer));
+    let (lat, lon) = (observer.lat.to_radians(), observer.lon.to_radians());
+    let up = [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()];
+    (dot(d, up) / dot(d, d).sqrt()).asin().to_degrees()
+}
+
+/// How dark the observer's sky is, by how far the Sun is below the horizon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Twilight {
+    /// Any of the Sun is up: its center is above -0.833°, which allows for
+    /// its radius and refraction.
+    Day,
+    /// The Sun is between 0.833° and 6° down. Bright stars and planets
+    /// show.
+    Civil,
+    /// Between 6° and 12° down. The horizon fades out.
+    Nautical,
+    /// Between 12° and 18° down. Only faint objects are still washed out.
+    Astronomical,
+    /// More than 18° down.
+    Night,
+}
+
+impl Twilight {
+    pub fn from_sun_elevation(elevation_deg: f64) -> Twilight {
+        match elevation_deg {
+            e if e > -0.833 => Twilight::Day,
+            e if e > -6.0 => Twilight::Civil,
+            e if e > -12.0 => Twilight::Nautical,
+            e if e > -18.0 => Twilight::Astronomical,
+            _ => Twilight::Night,
+        }
+    }
+}
+
+/// Whether someone could see a satellite with their own eyes, given it's
+/// above the horizon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    /// The observer's sky is too bright: the Sun is up, or in civil
+    /// twilight. This wins over `Eclipsed`.
+    Daylight,
+    /// The sky is dark enough but the satellite is in Earth's shadow.
+    Eclipsed,
+    /// The satellite is sunlit against a sky at least as dark as nautical
+    /// twilight, which is when satellites show up well.
+    Visible,
+}
+
+impl Visibility {
+    pub fn new(twilight: Twilight, sunlit: bool) -> Visibility {
+        match (twilight, sunlit) {
+            (Twilight::Day | Twilight::Civil, _) => Visibility::Daylight,
+            (_, false) => Visibility::Eclipsed,
+            (_, true) => Visibility::Visible,
+        }
+    }
+}
+
+impl fmt::Display for Visibility {
+    /// The same names as in JSON.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Visibility::Daylight => "daylight",
+            Visibility::Eclipsed => "eclipsed",
+            Visibility::Visible => "visible",
+        })
+    }
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
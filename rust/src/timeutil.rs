@@ -0,0 +1,128 @@
+//! Reading the times people type and writing times for people to read, in
+//! any IANA time zone.
+//!
+//! Everything is computed in UTC. A time zone only matters at the edges: a
+//! time given without an offset is taken to be local time there, and times
+//! are shown there. Local times that happen twice when the clocks go back,
+//! or never when they go forward, are errors that say what the choices are,
+//! rather than a guess.
+
+use anyhow::{bail, Context, Result};
+use chrono::{
+    DateTime, Duration, LocalResult, NaiveDateTime, Offset, SecondsFormat, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+
+// How local times can be written, from most to least precise.
+const LOCAL_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Reads a time as RFC 3339 with an offset (2025-06-15T18:30:00Z or
+/// 2025-06-15T11:30:00-07:00), or as a local time in `tz` without one
+/// (2025-06-15T11:30, or with seconds, or with a space for the T).
+pub fn parse_time(s: &str, tz: Tz) -> Result<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    let local = LOCAL_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .with_context(|| {
+            format!(
+                "{:?} isn't a time; use RFC 3339, like 2025-06-15T18:30:00Z, or a local \
+                 time like 2025-06-15T11:30:00 with --timezone",
+                s
+            )
+        })?;
+    from_local(local, tz)
+}
+
+/// The moment a local time in `tz` refers to, or an error listing the
+/// candidates if there are two, or would be two, around a change of the
+/// clocks.
+pub fn from_local(local: NaiveDateTime, tz: Tz) -> Result<DateTime<Utc>> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(t) => Ok(t.with_timezone(&Utc)),
+        LocalResult::Ambiguous(earlier, later) => bail!(
+            "{} happens twice in {}, when the clocks go back: it's {} ({}) or {} ({}); \
+             give an offset to pick one",
+            local,
+            tz,
+            rfc3339(earlier.with_timezone(&Utc), tz),
+            utc(earlier.with_timezone(&Utc)),
+            rfc3339(later.with_timezone(&Utc), tz),
+            utc(later.with_timezone(&Utc)),
+        ),
+        LocalResult::None => {
+            // The offsets on either side of the gap. Clocks don't change
+            // twice in a day anywhere.
+            let before = tz
+                .offset_from_utc_datetime(&(local - Duration::days(1)))
+                .fix();
+            let after = tz
+                .offset_from_utc_datetime(&(local + Duration::days(1)))
+                .fix();
+            let at = |offset: chrono::FixedOffset| {
+                Utc.from_utc_datetime(&(local - Duration::seconds(offset.local_minus_utc() as i64)))
+            };
+            bail!(
+                "{} doesn't exist in {}, which skips it when the clocks go forward; at the \
+                 offset before the change ({}) it would be {}, and at the one after ({}) {}",
+                local,
+                tz,
+                before,
+                utc(at(before)),
+                after,
+                utc(at(after)),
+            )
+        }
+    }
+}
+
+/// Formats a time for people to read in `tz`, like
+/// "2025-06-15 11:30:00 PDT".
+pub fn display_time(t: DateTime<Utc>, tz: Tz) -> String {
+    t.with_timezone(&tz)
+        .format("%Y-%m-%d %H:%M:%S %Z")
+        .to_string()
+}
+
+/// Formats a time as RFC 3339 in `tz`, with its offset, to the second.
+pub fn rfc3339(t: DateTime<Utc>, tz: Tz) -> String {
+    t.with_timezone(&tz)
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Rewrites every RFC 3339 UTC timestamp in a JSON value into `tz`, keeping
+/// any fractional seconds. Other strings are left alone.
+pub fn localize_json(value: &mut serde_json::Value, tz: Tz) {
+    match value {
+        serde_json::Value::String(s) if s.ends_with('Z') => {
+            if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+                *s = t
+                    .with_timezone(&tz)
+                    .to_rfc3339_opts(SecondsFormat::AutoSi, true);
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for v in values {
+                localize_json(v, tz);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for v in map.values_mut() {
+                localize_json(v, tz);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn utc(t: DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
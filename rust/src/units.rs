@@ -0,0 +1,119 @@
+//! Display units and precision for the numbers people read. JSON output
+//! doesn't go through any of this: it's always km, km/s and degrees.
+
+use serde::Deserialize;
+use std::str::FromStr;
+
+const KM_PER_MILE: f64 = 1.609344;
+const KM_PER_FOOT: f64 = 0.0003048;
+
+/// Which units to show distances in. Everything is computed in km; this only
+/// affects what people read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl Units {
+    /// Converts a distance in km to these units, with the unit's label.
+    pub fn distance(self, km: f64) -> (f64, &'static str) {
+        match self {
+            Units::Metric => (km, "km"),
+            Units::Imperial => (km / KM_PER_MILE, "miles"),
+        }
+    }
+
+    /// Converts a distance in these units back to km.
+    pub fn distance_to_km(self, value: f64) -> f64 {
+        match self {
+            Units::Metric => value,
+            Units::Imperial => value * KM_PER_MILE,
+        }
+    }
+
+    /// Converts an altitude in km to these units, with the unit's label.
+    /// Imperial altitudes are in feet, the way pilots give them.
+    pub fn altitude(self, km: f64) -> (f64, &'static str) {
+        match self {
+            Units::Metric => (km, "km"),
+            Units::Imperial => (km / KM_PER_FOOT, "ft"),
+        }
+    }
+
+    /// Converts an altitude in these units back to km.
+    pub fn altitude_to_km(self, value: f64) -> f64 {
+        match self {
+            Units::Metric => value,
+            Units::Imperial => value * KM_PER_FOOT,
+        }
+    }
+
+    /// Converts a speed in km/s to these units, with the unit's label.
+    pub fn speed(self, kms: f64) -> (f64, &'static str) {
+        match self {
+            Units::Metric => (kms, "km/s"),
+            Units::Imperial => (kms / KM_PER_MILE, "mi/s"),
+        }
+    }
+
+    /// Formats a distance in km as a rounded number with its unit, like
+    /// "680 km".
+    pub fn format_distance(self, km: f64) -> String {
+        let (value, label) = self.distance(km);
+        format!("{:.0} {}", value, label)
+    }
+}
+
+impl FromStr for Units {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "metric" => Ok(Units::Metric),
+            "imperial" => Ok(Units::Imperial),
+            _ => Err(format!("expected metric or imperial, got {:?}", s)),
+        }
+    }
+}
+
+/// How to write numbers for people: the units for distances, altitudes and
+/// speeds, and optionally how many decimal places every angle and distance
+/// gets. Without a precision, each number keeps the places it usually has,
+/// like 1 for elevations in text or 3 for ranges in CSV. Numbers are
+/// rounded to the nearest value at that precision, not truncated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NumberStyle {
+    pub units: Units,
+    pub precision: Option<usize>,
+}
+
+impl NumberStyle {
+    /// Formats `value` with the precision, or `places` decimal places if
+    /// there isn't one.
+    pub fn number(self, value: f64, places: usize) -> String {
+        format!("{:.*}", self.precision.unwrap_or(places), value)
+    }
+
+    /// Formats a distance in km with its unit, like "680 km".
+    pub fn distance(self, km: f64) -> String {
+        let (value, label) = self.units.distance(km);
+        format!("{} {}", self.number(value, 0), label)
+    }
+
+    /// Formats an altitude in km with its unit, like "420 km".
+    pub fn altitude(self, km: f64) -> String {
+        let (value, label) = self.units.altitude(km);
+        format!("{} {}", self.number(value, 0), label)
+    }
+
+    /// Formats a speed in km/s with its unit, like "7.66 km/s". Speeds keep
+    /// their 2 places regardless of the precision, which is for angles and
+    /// distances.
+    pub fn speed(self, kms: f64) -> String {
+        let (value, label) = self.units.speed(kms);
+        format!("{:.2} {}", value, label)
+    }
+}
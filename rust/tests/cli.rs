@@ -0,0 +1,1670 @@
+// End-to-end tests that run the binary against the fixtures in
+// tests/fixtures.
+
+use chrono::DateTime;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{self, Command, Output};
+
+use whatsoverhead::query::SatelliteReport;
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+// Runs the binary with the given arguments. The config directory points
+// somewhere empty so a developer's own config file can't change the results.
+fn run(args: &[String]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_whatsoverhead-in-space"))
+        .args(args)
+        .env("XDG_CONFIG_HOME", scratch_dir("no-config"))
+        .output()
+        .expect("failed to run binary")
+}
+
+// A fresh directory for this test to write files into.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("whatsoverhead-test-{}-{}", process::id(), name));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Runs the binary with the given arguments and parses its CSV output into
+// rows keyed by column name.
+fn run_csv(args: &[String]) -> Vec<HashMap<String, String>> {
+    let mut args = args.to_vec();
+    args.extend(["--format".to_string(), "csv".to_string()]);
+    let output = run(&args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let mut reader = csv::Reader::from_reader(output.stdout.as_slice());
+    let headers = reader.headers().unwrap().clone();
+    reader
+        .records()
+        .map(|r| {
+            let r = r.unwrap();
+            headers
+                .iter()
+                .zip(r.iter())
+                .map(|(h, v)| (h.to_string(), v.to_string()))
+                .collect()
+        })
+        .collect()
+}
+
+fn norad_ids(rows: &[HashMap<String, String>]) -> Vec<u64> {
+    let mut ids = rows
+        .iter()
+        .map(|r| r["norad_id"].parse().unwrap())
+        .collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+// Reports everything in the decay fixture at the given time, whether or not
+// it's above the horizon.
+fn decay_args(time: &str) -> Vec<String> {
+    [
+        "--catalog",
+        &fixture("decay.json"),
+        "--time",
+        time,
+        "--min-elevation",
+        "-90",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+#[test]
+fn skips_decayed_objects_by_default() {
+    let rows = run_csv(&decay_args("2026-03-01T00:00:00Z"));
+    assert_eq!(norad_ids(&rows), vec![25544, 66123]);
+}
+
+#[test]
+fn include_decayed_keeps_decayed_objects() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.push("--include-decayed".to_string());
+    let rows = run_csv(&args);
+    assert_eq!(norad_ids(&rows), vec![25544, 44714, 66123]);
+}
+
+#[test]
+fn keeps_objects_before_their_decay_date() {
+    let rows = run_csv(&decay_args("2026-02-21T00:00:00Z"));
+    assert!(norad_ids(&rows).contains(&44714));
+}
+
+#[test]
+fn flags_decaying_objects() {
+    let rows = run_csv(&decay_args("2026-03-01T00:00:00Z"));
+    for row in rows {
+        let expected = row["norad_id"] == "66123";
+        assert_eq!(row["decaying"], expected.to_string(), "{}", row["name"]);
+    }
+}
+
+#[test]
+fn json_output_matches_csv() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    let rows = run_csv(&args);
+    args.extend(["--format", "json"].map(String::from));
+    let output = run(&args);
+    assert!(output.status.success());
+    let reports: Vec<SatelliteReport> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(reports.len(), rows.len());
+    for (report, row) in reports.iter().zip(&rows) {
+        assert_eq!(report.norad_id.to_string(), row["norad_id"]);
+        assert_eq!(format!("{:.3}", report.range_km), row["range_km"]);
+        assert_eq!(report.decaying.to_string(), row["decaying"]);
+    }
+}
+
+#[test]
+fn malformed_records_dont_stop_the_run() {
+    let args = [
+        "--catalog",
+        &fixture("malformed.json"),
+        "--time",
+        "2026-02-28T12:00:00Z",
+        "--min-elevation",
+        "-90",
+        "--format",
+        "csv",
+    ]
+    .map(String::from);
+    let output = run(&args);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(
+            "Skipped 4 records that couldn't be used (NORAD IDs: 99001, 99002, 99003, unknown)"
+        ),
+        "{}",
+        stderr
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 2, "{}", stdout);
+}
+
+// Writes a config file and returns the arguments to use it.
+fn config_args(name: &str, contents: &str) -> Vec<String> {
+    let path = scratch_dir(name).join("config.toml");
+    fs::write(&path, contents).unwrap();
+    vec!["--config".to_string(), path.display().to_string()]
+}
+
+#[test]
+fn conjunctions_is_another_name_for_pairs() {
+    let pairs = |extra: [&str; 3]| {
+        let mut args = decay_args("2026-03-01T00:00:00Z");
+        args.extend(extra.map(String::from));
+        args.extend(["--format", "json"].map(String::from));
+        let output = run(&args);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice::<Vec<serde_json::Value>>(&output.stdout).unwrap()
+    };
+    let wide = pairs(["--conjunctions", "--max-sep", "180"]);
+    assert_eq!(wide.len(), 1);
+    assert_eq!(wide, pairs(["--pairs", "--max-separation-deg", "180"]));
+    assert!(pairs(["--conjunctions", "--max-sep", "0.001"]).is_empty());
+}
+
+// Like decay_args, but leaves the minimum elevation to the config file.
+fn decay_args_without_elevation() -> Vec<String> {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.truncate(4);
+    args
+}
+
+#[test]
+fn config_file_sets_defaults() {
+    let mut args = decay_args_without_elevation();
+    // Neither object is above the horizon at the default location.
+    assert_eq!(norad_ids(&run_csv(&args)), Vec::<u64>::new());
+    args.extend(config_args("defaults", "min_elevation = -90.0\n"));
+    assert_eq!(norad_ids(&run_csv(&args)), vec![25544, 66123]);
+}
+
+#[test]
+fn flags_override_config_file() {
+    let mut args = decay_args_without_elevation();
+    args.extend(config_args("override", "min_elevation = -90.0\n"));
+    args.extend(["--min-elevation".to_string(), "0".to_string()]);
+    assert_eq!(norad_ids(&run_csv(&args)), Vec::<u64>::new());
+}
+
+#[test]
+fn malformed_config_file_is_an_error() {
+    let mut args = decay_args_without_elevation();
+    args.extend(config_args("malformed", "min_elevation = \"high\"\n"));
+    let output = run(&args);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Malformed config file"));
+
+    let mut args = decay_args_without_elevation();
+    args.extend(config_args("typo", "min_elevaton = 10.0\n"));
+    assert!(!run(&args).status.success());
+}
+
+// The decay fixture with an "ISS DEB" fragment added, so "iss" is a
+// substring of two names.
+fn catalog_with_iss_debris() -> String {
+    let mut records: Vec<serde_json::Value> =
+        serde_json::from_str(&fs::read_to_string(fixture("decay.json")).unwrap()).unwrap();
+    let mut debris = records[0].clone();
+    debris["OBJECT_NAME"] = "ISS DEB".into();
+    debris["NORAD_CAT_ID"] = 99544.into();
+    records.push(debris);
+    let path = scratch_dir("iss-debris").join("catalog.json");
+    fs::write(&path, serde_json::to_string(&records).unwrap()).unwrap();
+    path.display().to_string()
+}
+
+#[test]
+fn aliases_come_before_name_matching() {
+    let args = |sat: &str| {
+        [
+            "--catalog",
+            &catalog_with_iss_debris(),
+            "--time",
+            "2026-03-01T00:00:00Z",
+            "--sat",
+            sat,
+        ]
+        .map(String::from)
+        .to_vec()
+    };
+    assert_eq!(norad_ids(&run_csv(&args("ISS"))), vec![25544]);
+    assert_eq!(norad_ids(&run_csv(&args("Zarya"))), vec![25544]);
+    // Without an alias, "deb" is just part of a name.
+    assert_eq!(norad_ids(&run_csv(&args("deb"))), vec![99544]);
+    // An alias for something that isn't loaded says so rather than falling
+    // back to a name match.
+    let output = run(&args("hubble"));
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("NORAD ID 20580, which isn't in"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn config_file_adds_aliases() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(config_args(
+        "aliases",
+        "[aliases]\n\"Long March\" = 66123\nISS = 66123\n",
+    ));
+    args.extend(["--sat", "long march"].map(String::from));
+    assert_eq!(norad_ids(&run_csv(&args)), vec![66123]);
+    // Config aliases replace built-in ones.
+    let last = args.len() - 1;
+    args[last] = "iss".to_string();
+    assert_eq!(norad_ids(&run_csv(&args)), vec![66123]);
+}
+
+// With --notable, the ISS is reported from under it, and from under the
+// rocket body, where the ISS is below the horizon, the whole catalog is
+// reported instead.
+#[test]
+fn notable_falls_back_to_the_whole_catalog() {
+    let notable = |lat: &str, lon: &str| {
+        let args = [
+            "--catalog",
+            &fixture("decay.json"),
+            "--time",
+            "2026-03-01T00:00:00Z",
+            "--lat",
+            lat,
+            "--lon",
+            lon,
+            "--notable",
+            "--format",
+            "csv",
+        ]
+        .map(String::from);
+        let output = run(&args);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let ids = csv::Reader::from_reader(output.stdout.as_slice())
+            .records()
+            .map(|r| r.unwrap()[1].parse().unwrap())
+            .collect::<Vec<u64>>();
+        (ids, String::from_utf8(output.stderr).unwrap())
+    };
+    let (ids, stderr) = notable("-50.6", "-13.3");
+    assert_eq!(ids, vec![25544]);
+    assert!(!stderr.contains("notable"), "{}", stderr);
+    let (ids, stderr) = notable("37.5", "-14.2");
+    assert_eq!(ids, vec![66123]);
+    assert!(
+        stderr.contains("None of the notable objects are above 0 degrees"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn wmm_adds_magnetic_azimuths() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--wmm".to_string(), fixture("dipole.cof")]);
+    args.extend(["--format", "json"].map(String::from));
+    let output = run(&args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let reports = serde_json::from_slice::<Vec<SatelliteReport>>(&output.stdout).unwrap();
+    assert_eq!(reports.len(), 2);
+    for r in &reports {
+        let declination = r.magnetic_declination.unwrap();
+        assert!(declination.abs() > 0.1 && declination.abs() < 90.0);
+        let magnetic = r.magnetic_azimuth.unwrap();
+        assert!(
+            ((r.azimuth - declination - magnetic).rem_euclid(360.0)) < 1e-9,
+            "{:?}",
+            r
+        );
+    }
+
+    let last = args.len() - 1;
+    args[last] = "csv".to_string();
+    let header = String::from_utf8(run(&args).stdout)
+        .unwrap()
+        .lines()
+        .next()
+        .unwrap()
+        .to_string();
+    assert!(
+        header.ends_with(",az_rate_deg_s,az_true_deg,az_magnetic_deg,magnetic_declination_deg"),
+        "{}",
+        header
+    );
+    for row in run_csv(&args[..last - 1]) {
+        assert_eq!(row["az_true_deg"], row["az_deg"]);
+    }
+}
+
+#[test]
+fn wmm_can_come_from_the_config_file() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(config_args(
+        "wmm",
+        &format!("wmm = {:?}\n", fixture("dipole.cof")),
+    ));
+    let rows = run_csv(&args);
+    assert!(rows
+        .iter()
+        .all(|r| !r["magnetic_declination_deg"].is_empty()));
+}
+
+#[test]
+fn times_outside_the_magnetic_model_get_true_azimuths_only() {
+    let path = scratch_dir("old-wmm").join("WMM.COF");
+    fs::write(
+        &path,
+        fs::read_to_string(fixture("dipole.cof"))
+            .unwrap()
+            .replace("2025.0", "2015.0"),
+    )
+    .unwrap();
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--wmm".to_string(), path.display().to_string()]);
+    args.extend(["--format", "csv"].map(String::from));
+    let output = run(&args);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("DIPOLE-2025 covers 2015.0 to 2020.0"),
+        "{}",
+        stderr
+    );
+    // The columns are there, since --wmm was given, but empty.
+    let mut reader = csv::Reader::from_reader(output.stdout.as_slice());
+    let headers = reader.headers().unwrap().clone();
+    let magnetic = headers.iter().position(|h| h == "az_magnetic_deg").unwrap();
+    let rows = reader.records().map(Result::unwrap).collect::<Vec<_>>();
+    assert!(!rows.is_empty());
+    for row in rows {
+        assert_eq!(&row[magnetic], "");
+        assert_eq!(&row[magnetic + 1], "");
+    }
+}
+
+// The columns depend on the flags, not on what there is to report, so a
+// run that finds nothing still has the columns a run that finds something
+// would.
+#[test]
+fn csv_columns_come_from_the_flags() {
+    // Nothing is above the horizon at the default location.
+    let mut args = decay_args_without_elevation();
+    args.extend(["--trail", "2", "--wmm"].map(String::from));
+    args.push(fixture("dipole.cof"));
+    args.extend(["--format", "csv"].map(String::from));
+    let output = run(&args);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1, "{}", stdout);
+    assert!(
+        stdout.trim_end().ends_with(
+            ",az_rate_deg_s,trail_offset_s,above_horizon,az_true_deg,az_magnetic_deg,magnetic_declination_deg"
+        ),
+        "{}",
+        stdout
+    );
+}
+
+// The CLI's reports are the builder's, for the same observer, time and
+// settings.
+#[test]
+fn cli_reports_match_the_query_builder() {
+    use whatsoverhead::coords::Observer;
+    use whatsoverhead::query::{OverheadQuery, RankBy};
+
+    let time = "2026-03-01T00:00:00Z";
+    let mut satellites = whatsoverhead::load_catalog(fixture("decay.json"))
+        .unwrap()
+        .satellites;
+    whatsoverhead::data::drop_decayed(&mut satellites, time.parse().unwrap());
+    let observer = Observer {
+        lat: -45.0,
+        lon: -13.3,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let base = OverheadQuery::new(observer)
+        .at(time.parse().unwrap())
+        .min_elevation(-90.0);
+    let cases: [(&[&str], OverheadQuery); 4] = [
+        (&[], base.clone()),
+        (
+            &["--rank-by", "elevation", "--top", "1"],
+            base.clone().rank_by(RankBy::Elevation).top(1),
+        ),
+        (&["--visible-only"], base.clone().visible_only(true)),
+        (
+            &["--within-km", "2000"],
+            base.clone()
+                .within(whatsoverhead::geo::Area::Circle {
+                    lat: -45.0,
+                    lon: -13.3,
+                    radius_km: 2000.0,
+                })
+                .rank_by(RankBy::Ground),
+        ),
+    ];
+    for (extra, query) in cases {
+        let mut args = decay_args(time);
+        args.extend(
+            [
+                "--lat",
+                "-45",
+                "--lon",
+                "-13.3",
+                "--alt-datum",
+                "ellipsoid",
+                "--no-refraction",
+                "--format",
+                "json",
+            ]
+            .map(String::from),
+        );
+        args.extend(extra.iter().map(|s| s.to_string()));
+        let output = run(&args);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let cli = serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap();
+        let builder = serde_json::from_str::<serde_json::Value>(
+            &serde_json::to_string(&query.run(&satellites)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(cli, builder, "{:?}", extra);
+    }
+
+    // --peak asks the same question at the busiest moment.
+    let mut args = decay_args(time);
+    args.extend(
+        [
+            "--lat",
+            "-45",
+            "--lon",
+            "-13.3",
+            "--alt-datum",
+            "ellipsoid",
+            "--no-refraction",
+            "--format",
+            "json",
+            "--peak",
+            "--step",
+            "3600",
+        ]
+        .map(String::from),
+    );
+    let cli = serde_json::from_slice::<serde_json::Value>(&run(&args).stdout).unwrap();
+    let (peak, _, _) = base.peak(&satellites, 3600.0).unwrap();
+    let builder = serde_json::from_str::<serde_json::Value>(
+        &serde_json::to_string(&base.at(peak).run(&satellites)).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(cli, builder);
+}
+
+#[test]
+fn location_sets_coordinates() {
+    let mut by_name = decay_args("2026-03-01T00:00:00Z");
+    by_name.extend(["--location", "Los Angeles"].map(String::from));
+    let mut by_coords = decay_args("2026-03-01T00:00:00Z");
+    let la = whatsoverhead::locations::find("Los Angeles").unwrap();
+    by_coords.extend(["--lat".to_string(), la.lat.to_string()]);
+    by_coords.extend(["--lon".to_string(), la.lon.to_string()]);
+    assert_eq!(run_csv(&by_name), run_csv(&by_coords));
+}
+
+#[test]
+fn lat_and_lon_override_location() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--lat", "40", "--lon", "-74"].map(String::from));
+    let expected = run_csv(&args);
+    args.extend(["--location", "Los Angeles"].map(String::from));
+    assert_eq!(run_csv(&args), expected);
+}
+
+#[test]
+fn location_flag_overrides_config_coordinates() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--location", "Tokyo"].map(String::from));
+    let expected = run_csv(&args);
+    args.extend(config_args("location", "lat = 40.0\nlon = -74.0\n"));
+    assert_eq!(run_csv(&args), expected);
+}
+
+#[test]
+fn watch_needs_the_real_time() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--watch", "5"].map(String::from));
+    let output = run(&args);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("--time"), "{}", stderr);
+}
+
+#[test]
+fn csv_columns_are_stable() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--format", "csv"].map(String::from));
+    let stdout = String::from_utf8(run(&args).stdout).unwrap();
+    assert_eq!(
+        stdout.lines().next().unwrap(),
+        "timestamp,norad_id,name,lat,lon,alt_km,az_deg,el_deg,range_km,range_rate_kms,\
+         epoch_age_hours,ground_distance_km,ground_bearing_deg,decaying,magnitude,\
+         el_rate_deg_s,culmination_in_s,visibility,az_rate_deg_s"
+    );
+    let rows = run_csv(&decay_args("2026-03-01T00:00:00Z"));
+    assert_eq!(rows[0]["timestamp"], "2026-03-01T00:00:00Z");
+    let iss = rows.iter().find(|r| r["norad_id"] == "25544").unwrap();
+    // The ISS elements are from 2026-02-28T12:00.
+    assert_eq!(iss["epoch_age_hours"], "12.00");
+}
+
+#[test]
+fn units_and_precision_change_text_and_csv_but_not_json() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--units", "imperial", "--precision", "1"].map(String::from));
+    let metric = run_csv(&decay_args("2026-03-01T00:00:00Z"));
+    let imperial = run_csv(&args);
+    let iss = |rows: &[HashMap<String, String>]| {
+        rows.iter()
+            .find(|r| r["norad_id"] == "25544")
+            .unwrap()
+            .clone()
+    };
+    let (metric, imperial) = (iss(&metric), iss(&imperial));
+    let km: f64 = metric["range_km"].parse().unwrap();
+    let miles: f64 = imperial["range_mi"].parse().unwrap();
+    assert!((miles - km / 1.609344).abs() <= 0.05, "{} {}", km, miles);
+    assert_eq!(imperial["range_mi"], format!("{:.1}", km / 1.609344));
+    let alt_km: f64 = metric["alt_km"].parse().unwrap();
+    let alt_ft: f64 = imperial["alt_ft"].parse().unwrap();
+    assert!(
+        (alt_ft - alt_km / 0.0003048).abs() <= 1.0,
+        "{} {}",
+        alt_km,
+        alt_ft
+    );
+    assert_eq!(imperial["el_deg"].split('.').nth(1).unwrap().len(), 1);
+    assert_eq!(imperial["lat"].split('.').nth(1).unwrap().len(), 1);
+
+    let text = String::from_utf8(run(&args).stdout).unwrap();
+    assert!(text.contains(" miles away"), "{}", text);
+    assert!(text.contains(" ft above "), "{}", text);
+    assert!(text.contains(" mi/s"), "{}", text);
+
+    let mut json_args = args.clone();
+    json_args.extend(["--format", "json"].map(String::from));
+    let reports: Vec<serde_json::Value> = serde_json::from_slice(&run(&json_args).stdout).unwrap();
+    let iss = reports.iter().find(|r| r["norad_id"] == 25544).unwrap();
+    // JSON keeps km, at full precision.
+    assert!((iss["range_km"].as_f64().unwrap() - km).abs() < 0.001);
+}
+
+#[test]
+fn closest_approach_needs_one_satellite() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--closest-approach", "--duration", "360"].map(String::from));
+    assert!(!run(&args).status.success());
+
+    args.extend(["--sat", "ZARYA", "--format", "json"].map(String::from));
+    let output = run(&args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let approach: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(approach["norad_id"], 25544);
+    let time = DateTime::parse_from_rfc3339(approach["time"].as_str().unwrap()).unwrap();
+    assert!(time > DateTime::parse_from_rfc3339("2026-03-01T00:00:00Z").unwrap());
+    assert!(time < DateTime::parse_from_rfc3339("2026-03-01T06:00:00Z").unwrap());
+    // Closer than it is at the start, and no closer than its altitude.
+    let range_km = approach["range_km"].as_f64().unwrap();
+    let rows = run_csv(&decay_args("2026-03-01T00:00:00Z"));
+    let iss = rows.iter().find(|r| r["norad_id"] == "25544").unwrap();
+    assert!(range_km < iss["range_km"].parse::<f64>().unwrap());
+    assert!(range_km > iss["alt_km"].parse::<f64>().unwrap());
+}
+
+// The Starlink in the decay fixture came down before the time asked about,
+// so nothing is left to find the closest approach of.
+#[test]
+fn closest_approach_of_a_decayed_object_is_an_error() {
+    let args = [
+        "--catalog",
+        &fixture("decay.json"),
+        "--sat",
+        "44714",
+        "--closest-approach",
+        "--time",
+        "2026-03-01T00:00:00Z",
+        "--lat",
+        "34",
+        "--lon",
+        "-118",
+    ]
+    .map(String::from);
+    let output = run(&args);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--closest-approach needs exactly one satellite, but 0 are selected"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+}
+
+#[test]
+fn orbit_class_filters_before_propagating() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--orbit-class", "leo"].map(String::from));
+    assert_eq!(norad_ids(&run_csv(&args)), vec![25544, 66123]);
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--orbit-class", "geo"].map(String::from));
+    assert_eq!(norad_ids(&run_csv(&args)), Vec::<u64>::new());
+}
+
+#[test]
+fn geocentric_latitudes_are_converted() {
+    let at = |lat: &str, lat_type: &str| {
+        let mut args = decay_args("2026-03-01T00:00:00Z");
+        args.extend(["--lat", lat, "--lon", "10", "--lat-type", lat_type].map(String::from));
+        run_csv(&args)
+    };
+    let geodetic = at("45", "geodetic");
+    let geocentric = at("44.807577", "geocentric");
+    for (a, b) in geodetic.iter().zip(&geocentric) {
+        let range = |r: &HashMap<String, String>| r["range_km"].parse::<f64>().unwrap();
+        assert!((range(a) - range(b)).abs() < 0.01, "{:?} {:?}", a, b);
+    }
+    let uncorrected = at("44.807577", "geodetic");
+    let range = |rows: &[HashMap<String, String>]| rows[0]["range_km"].parse::<f64>().unwrap();
+    assert!((range(&uncorrected) - range(&geodetic)).abs() > 1.0);
+}
+
+#[test]
+fn trail_steps_under_a_millisecond_are_refused() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--trail", "2", "--trail-step", "0.0004"].map(String::from));
+    let output = run(&args);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--trail-step must be at least a millisecond"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn trails_are_extra_rows_in_csv_and_an_array_in_json() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--trail", "2", "--trail-step", "30"].map(String::from));
+    let rows = run_csv(&args);
+    // Each satellite is followed by the 9 samples of its trail.
+    assert_eq!(rows.len(), 2 * 10);
+    assert_eq!(rows[0]["trail_offset_s"], "");
+    let offsets: Vec<&str> = rows[1..10]
+        .iter()
+        .map(|r| r["trail_offset_s"].as_str())
+        .collect();
+    assert_eq!(
+        offsets,
+        ["-120", "-90", "-60", "-30", "0", "30", "60", "90", "120"]
+    );
+    assert_eq!(rows[5]["el_deg"], rows[0]["el_deg"]);
+    assert_eq!(rows[5]["lat"], rows[0]["lat"]);
+    assert_eq!(rows[5]["range_rate_kms"], "");
+    assert!(rows[1..]
+        .iter()
+        .all(|r| r["above_horizon"] == "false" || r["trail_offset_s"].is_empty()));
+    // The trail turns the Earth by the same UT1 - UTC as the report.
+    let mut shifted = args.clone();
+    shifted.extend(["--delta-ut1", "0.9"].map(String::from));
+    let shifted = run_csv(&shifted);
+    assert_ne!(shifted[0]["lon"], rows[0]["lon"]);
+    assert_eq!(shifted[5]["lon"], shifted[0]["lon"]);
+
+    args.extend(["--format", "json"].map(String::from));
+    let reports: Vec<serde_json::Value> = serde_json::from_slice(&run(&args).stdout).unwrap();
+    for r in &reports {
+        let trail = r["trail"].as_array().unwrap();
+        assert_eq!(trail.len(), 9);
+        assert_eq!(trail[4]["elevation"], r["elevation"]);
+        assert_eq!(trail[0]["above_horizon"], false);
+    }
+    // Without --trail there's no trail at all.
+    let plain = run(&[
+        decay_args("2026-03-01T00:00:00Z"),
+        vec!["--format".into(), "json".into()],
+    ]
+    .concat());
+    assert!(!String::from_utf8(plain.stdout).unwrap().contains("trail"));
+}
+
+#[test]
+fn satellite_picks_every_match_unless_there_are_too_many() {
+    let dir = scratch_dir("satellite-names");
+    let catalog = dir.join("catalog.json");
+    let mut records: Vec<serde_json::Value> =
+        serde_json::from_str(&fs::read_to_string(fixture("decay.json")).unwrap()).unwrap();
+    // Nothing's decayed in this catalog.
+    for r in &mut records {
+        r["DECAY_DATE"] = serde_json::Value::Null;
+    }
+    let starlink = records
+        .iter()
+        .find(|r| r["OBJECT_NAME"] == "STARLINK-1001")
+        .unwrap();
+    let write = |count: u64| {
+        let mut catalog_records = records.clone();
+        for n in 1..count {
+            let mut copy = starlink.clone();
+            copy["OBJECT_NAME"] = format!("STARLINK-{}", 1001 + n).into();
+            copy["NORAD_CAT_ID"] = (44714 + n).into();
+            catalog_records.push(copy);
+        }
+        fs::write(&catalog, serde_json::to_string(&catalog_records).unwrap()).unwrap();
+    };
+    let args = |name: &str| {
+        [
+            "--catalog",
+            catalog.to_str().unwrap(),
+            "--time",
+            "2026-03-01T00:00:00Z",
+            "--satellite",
+            name,
+        ]
+        .map(String::from)
+        .to_vec()
+    };
+
+    write(10);
+    let rows = run_csv(&args("starlink"));
+    assert_eq!(rows.len(), 10);
+    assert!(rows.iter().all(|r| r["name"].starts_with("STARLINK-")));
+    assert_eq!(norad_ids(&run_csv(&args("Iss"))), vec![25544]);
+
+    write(11);
+    let output = run(&args("starlink"));
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("\"starlink\" matches 11 satellites"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("  STARLINK-1011 (44724)"), "{}", stderr);
+    assert!(!run(&args("hubble")).status.success());
+}
+
+#[test]
+fn csv_quotes_awkward_names() {
+    let dir = scratch_dir("csv-quoting");
+    let catalog = dir.join("catalog.json");
+    let mut records: Vec<serde_json::Value> =
+        serde_json::from_str(&fs::read_to_string(fixture("decay.json")).unwrap()).unwrap();
+    let name = r#"OBJECT A, "THE BIG ONE""#;
+    records[0]["OBJECT_NAME"] = name.into();
+    fs::write(&catalog, serde_json::to_string(&records).unwrap()).unwrap();
+
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args[1] = catalog.display().to_string();
+    let rows = run_csv(&args);
+    assert!(rows.iter().any(|r| r["name"] == name));
+    args.extend(["--format", "csv"].map(String::from));
+    let stdout = String::from_utf8(run(&args).stdout).unwrap();
+    assert!(
+        stdout.contains(r#","OBJECT A, ""THE BIG ONE""","#),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn csv_top_and_peak_give_several_rows() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--include-decayed", "--top", "2"].map(String::from));
+    assert_eq!(run_csv(&args).len(), 2);
+
+    // With everything down to -90 degrees counted, the peak is all of them.
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--peak", "--step", "3600"].map(String::from));
+    let rows = run_csv(&args);
+    assert_eq!(norad_ids(&rows), [25544, 66123]);
+    assert!(rows.iter().all(|r| r["timestamp"] == rows[0]["timestamp"]));
+}
+
+#[test]
+fn norad_id_picks_satellites() {
+    let args = [
+        "--catalog",
+        &fixture("decay.json"),
+        "--time",
+        "2026-03-01T00:00:00Z",
+        "--norad-id",
+        "25544,66123",
+    ]
+    .map(String::from);
+    // Picked satellites are reported even when they're below the horizon.
+    assert_eq!(norad_ids(&run_csv(&args)), vec![25544, 66123]);
+}
+
+#[test]
+fn norad_ids_reads_a_file() {
+    let path = scratch_dir("norad-ids").join("ids.txt");
+    fs::write(
+        &path,
+        "# The space station\n25544\n\n12345  # not in the catalog\n",
+    )
+    .unwrap();
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--norad-ids".to_string(), path.display().to_string()]);
+    let output = run(&args);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("NORAD ID 12345 isn't in the catalog"));
+    assert_eq!(norad_ids(&run_csv(&args)), vec![25544]);
+
+    fs::write(&path, "25544\nISS\n").unwrap();
+    let output = run(&args);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 2"));
+}
+
+#[test]
+fn reports_for_several_locations() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--lat", "40", "--lon", "-74", "--location", "Tokyo"].map(String::from));
+    let new_york = run_csv(&args);
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--location", "Tokyo"].map(String::from));
+    let tokyo = run_csv(&args);
+
+    let path = scratch_dir("locations").join("locations.txt");
+    fs::write(&path, "# Stations\nNew York: 40, -74\n\nTokyo\n").unwrap();
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--locations".to_string(), path.display().to_string()]);
+    let rows = run_csv(&args);
+    assert_eq!(rows.len(), new_york.len() + tokyo.len());
+    for (expected, location) in [(&new_york, "New York"), (&tokyo, "Tokyo, JP")] {
+        let got = rows
+            .iter()
+            .filter(|r| r["location"] == location)
+            .map(|r| r["range_km"].clone())
+            .collect::<Vec<_>>();
+        let want = expected
+            .iter()
+            .map(|r| r["range_km"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(got, want, "{}", location);
+    }
+
+    args.extend(["--format", "json"].map(String::from));
+    let output = run(&args);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json[1]["location"]["name"], "Tokyo, JP");
+    assert_eq!(json[1]["satellites"].as_array().unwrap().len(), tokyo.len());
+}
+
+#[test]
+fn repeated_lat_and_lon_must_pair_up() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--lat", "40", "--lon", "-74", "--lat", "35"].map(String::from));
+    assert!(!run(&args).status.success());
+    args.extend(["--lon", "139"].map(String::from));
+    let rows = run_csv(&args);
+    assert_eq!(rows[0]["location"], "40, -74");
+}
+
+#[test]
+fn passes_lists_rises_and_sets() {
+    let args = [
+        "passes",
+        "--catalog",
+        &fixture("decay.json"),
+        "--time",
+        "2026-03-01T00:00:00Z",
+        "--min-elevation",
+        "10",
+        "--format",
+        "json",
+    ]
+    .map(String::from);
+    let output = run(&args);
+    assert!(output.status.success());
+    let passes: Vec<whatsoverhead::passes::Pass> = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(!passes.is_empty());
+    assert!(passes.windows(2).all(|w| w[0].rise <= w[1].rise));
+    assert!(passes.iter().all(|p| p.max_elevation >= 10.0));
+}
+
+#[test]
+fn passes_refuses_steps_under_a_millisecond() {
+    for flag in ["--coarse-step", "--refine-step"] {
+        let args = [
+            "passes",
+            "--catalog",
+            &fixture("decay.json"),
+            "--time",
+            "2026-03-01T00:00:00Z",
+            flag,
+            "0.0004",
+        ]
+        .map(String::from);
+        let output = run(&args);
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains(&format!("{} must be at least a millisecond", flag)),
+            "{}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn passes_over_several_ground_stations() {
+    let args = [
+        "passes",
+        "--catalog",
+        &fixture("decay.json"),
+        "--time",
+        "2026-03-01T00:00:00Z",
+        "--sat",
+        "25544",
+        "--lat",
+        "34.05",
+        "--lon",
+        "-118.24",
+        "--lat",
+        "-33.87",
+        "--lon",
+        "151.21",
+        "--format",
+        "csv",
+    ]
+    .map(String::from);
+    let output = run(&args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let mut reader = csv::Reader::from_reader(output.stdout.as_slice());
+    assert_eq!(
+        reader.headers().unwrap().iter().next_back(),
+        Some("location")
+    );
+    let locations = reader
+        .records()
+        .map(|r| r.unwrap()[9].to_string())
+        .collect::<std::collections::BTreeSet<_>>();
+    assert_eq!(
+        locations.into_iter().collect::<Vec<_>>(),
+        vec!["-33.87, 151.21", "34.05, -118.24"]
+    );
+}
+
+#[test]
+fn altitude_is_above_sea_level_unless_told_otherwise() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--lat", "34.56", "--lon", "-118.76", "--alt", "1000"].map(String::from));
+    let msl = run_csv(&args);
+    let h = whatsoverhead::geoid::orthometric_to_ellipsoidal(34.56, -118.76, 1000.0);
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(
+        [
+            "--lat",
+            "34.56",
+            "--lon",
+            "-118.76",
+            "--alt-datum",
+            "ellipsoid",
+        ]
+        .map(String::from),
+    );
+    args.extend(["--alt".to_string(), h.to_string()]);
+    assert_eq!(run_csv(&args), msl);
+    args.pop();
+    args.push("1000".to_string());
+    assert_ne!(run_csv(&args), msl);
+}
+
+#[test]
+fn geoid_reports_altitude_above_sea_level() {
+    let args = decay_args("2026-03-01T00:00:00Z");
+    let ellipsoid = run_csv(&args);
+    let mut args = args;
+    args.push("--geoid".to_string());
+    let msl = run_csv(&args);
+    for (e, m) in ellipsoid.iter().zip(&msl) {
+        let (lat, lon) = (e["lat"].parse().unwrap(), e["lon"].parse().unwrap());
+        let expected = e["alt_km"].parse::<f64>().unwrap()
+            - whatsoverhead::geoid::undulation(lat, lon) / 1000.0;
+        let got = m["alt_km"].parse::<f64>().unwrap();
+        assert!((got - expected).abs() < 0.002, "{} vs {}", got, expected);
+        assert_eq!(e["range_km"], m["range_km"]);
+    }
+}
+
+#[test]
+fn summary_counts_constellations() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--include-decayed", "--summary"].map(String::from));
+    let output = run(&args);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Above -90°: 1 CZ-2C R/B, 1 ISS (ZARYA), 1 STARLINK-1001\n"
+    );
+
+    args.extend(["--format", "json"].map(String::from));
+    let output = run(&args);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        json["summary"],
+        serde_json::json!({"CZ": 1, "ISS": 1, "STARLINK": 1})
+    );
+    assert_eq!(json["satellites"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn sat_reports_one_satellite_wherever_it_is() {
+    let args = [
+        "--catalog",
+        &fixture("decay.json"),
+        "--time",
+        "2026-03-01T00:00:00Z",
+        "--sat",
+        "iss",
+    ]
+    .map(String::from);
+    assert_eq!(norad_ids(&run_csv(&args)), vec![25544]);
+    let output = run(&args);
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("ISS (ZARYA) (25544) is "));
+}
+
+#[test]
+fn duplicate_element_sets_are_dropped() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args[1] = fixture("duplicates.json");
+    let output = run(&args);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Skipped 1 older element sets"));
+    let rows = run_csv(&args);
+    assert_eq!(norad_ids(&rows), [25544, 66123]);
+    let iss = rows.iter().find(|r| r["norad_id"] == "25544").unwrap();
+    assert_eq!(iss["epoch_age_hours"], "12.00");
+
+    args.push("--keep-duplicates".to_string());
+    assert_eq!(norad_ids(&run_csv(&args)), [25544, 25544, 66123]);
+}
+
+// Each element set's trail is its own, even when another has the same
+// NORAD ID.
+#[test]
+fn trails_follow_their_own_duplicate_element_set() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args[1] = fixture("duplicates.json");
+    args.extend(["--keep-duplicates", "--trail", "1", "--format", "json"].map(String::from));
+    let output = run(&args);
+    assert!(output.status.success());
+    let reports = serde_json::from_slice::<Vec<SatelliteReport>>(&output.stdout).unwrap();
+    let iss = reports
+        .iter()
+        .filter(|r| r.norad_id == 25544)
+        .collect::<Vec<_>>();
+    assert_eq!(iss.len(), 2);
+    assert!((iss[0].lat - iss[1].lat).abs() > 0.1);
+    for r in iss {
+        let center = r.trail.iter().find(|p| p.offset_seconds == 0.0).unwrap();
+        assert_eq!((center.lat, center.lon), (r.lat, r.lon));
+    }
+}
+
+#[test]
+fn show_errors_explains_propagation_failures() {
+    let mut args = decay_args("2026-02-21T00:00:00Z");
+    args.push("--include-decayed".to_string());
+    let rows = run_csv(&args);
+    assert_eq!(norad_ids(&rows), vec![25544, 44714]);
+    let output = run(&args);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Couldn't propagate 1 satellites"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("--show-errors"), "{}", stderr);
+
+    args.push("--show-errors".to_string());
+    let output = run(&args);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("eccentricity out of range: 1 (NORAD IDs: 66123)"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn implausible_altitudes_are_skipped_with_a_warning() {
+    let mut args = decay_args("2026-06-01T00:00:00Z");
+    args.push("--include-decayed".to_string());
+    assert_eq!(norad_ids(&run_csv(&args)), vec![25544, 44714]);
+    let stderr = String::from_utf8(run(&args).stderr).unwrap();
+    assert!(stderr.contains("CZ-2C R/B (66123)"), "{}", stderr);
+    assert!(!stderr.contains("Couldn't propagate"), "{}", stderr);
+}
+
+#[test]
+fn bundled_elements_are_used_without_a_catalog() {
+    // Somewhere with no space-track-omm.json.
+    let dir = scratch_dir("bundled");
+    let run_in = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_whatsoverhead-in-space"))
+            .args(["--time", "2026-10-14T00:00:00Z", "--min-elevation", "-90"])
+            .args(extra)
+            .current_dir(&dir)
+            .env("XDG_CONFIG_HOME", scratch_dir("no-config"))
+            .output()
+            .expect("failed to run binary")
+    };
+    let output = run_in(&["--format", "csv"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("built-in element sets"));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("ISS (ZARYA)"), "{}", stdout);
+
+    let output = run_in(&["--no-bundled"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unable to read space-track-omm.json"));
+}
+
+#[test]
+fn timezone_changes_displayed_times_but_not_json() {
+    let args = |format: &str, timezone: &str| {
+        [
+            "passes",
+            "--catalog",
+            &fixture("decay.json"),
+            "--time",
+            "2026-03-01T00:00:00Z",
+            "--sat",
+            "25544",
+            "--top",
+            "1",
+            "--timezone",
+            timezone,
+            "--format",
+            format,
+        ]
+        .map(String::from)
+    };
+    let stdout = |args: &[String]| String::from_utf8(run(args).stdout).unwrap();
+    let utc = stdout(&args("text", "UTC"));
+    assert!(utc.contains("12:48:09 UTC"), "{}", utc);
+    let tokyo = stdout(&args("text", "Asia/Tokyo"));
+    assert!(tokyo.contains("21:48:09 JST"), "{}", tokyo);
+    let csv = stdout(&args("csv", "Asia/Tokyo"));
+    assert!(csv.contains(",2026-03-01T21:48:09+09:00,"), "{}", csv);
+    assert_eq!(
+        stdout(&args("json", "Asia/Tokyo")),
+        stdout(&args("json", "UTC"))
+    );
+
+    let output = run(&args("text", "Mars/Olympus_Mons"));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown time zone"));
+}
+
+#[test]
+fn times_without_offsets_are_read_in_the_timezone() {
+    let args = |time: &str, extra: &[&str]| {
+        let mut args = [
+            "passes",
+            "--catalog",
+            &fixture("decay.json"),
+            "--time",
+            time,
+            "--sat",
+            "25544",
+            "--top",
+            "1",
+            "--format",
+            "json",
+        ]
+        .map(String::from)
+        .to_vec();
+        args.extend(extra.iter().map(|s| s.to_string()));
+        args
+    };
+    let stdout = |args: &[String]| String::from_utf8(run(args).stdout).unwrap();
+    let utc = stdout(&args("2026-03-01T00:00:00Z", &[]));
+    assert_eq!(
+        stdout(&args("2026-03-01T09:00", &["--timezone", "Asia/Tokyo"])),
+        utc
+    );
+
+    let local = stdout(&args(
+        "2026-03-01T00:00:00Z",
+        &["--timezone", "Asia/Tokyo", "--local-times"],
+    ));
+    assert_ne!(local, utc);
+    assert!(local.contains("2026-03-01T21:48:09"), "{}", local);
+    assert!(local.contains("+09:00\""), "{}", local);
+    assert!(!local.contains("Z\""), "{}", local);
+
+    let output = run(&args(
+        "2026-11-01T01:30",
+        &["--timezone", "America/Los_Angeles"],
+    ));
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("happens twice"), "{}", stderr);
+    assert!(stderr.contains("2026-11-01T08:30:00Z"), "{}", stderr);
+    assert!(stderr.contains("2026-11-01T09:30:00Z"), "{}", stderr);
+}
+
+#[test]
+fn ephemeris_writes_regular_steps() {
+    let mut args = [
+        "ephemeris",
+        "--catalog",
+        &fixture("decay.json"),
+        "--start",
+        "2026-03-01T00:00:00Z",
+        "--end",
+        "2026-03-01T01:00:00Z",
+        "--step",
+        "300",
+    ]
+    .map(String::from)
+    .to_vec();
+    // It needs one satellite.
+    let output = run(&args);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--sat"));
+
+    args.extend(["--sat", "ISS"].map(String::from));
+    let output = run(&args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines[0],
+        "time,lat,lon,alt_km,x_km,y_km,z_km,vx_kms,vy_kms,vz_kms"
+    );
+    assert_eq!(lines.len(), 14);
+    assert!(lines[1].starts_with("2026-03-01T00:00:00Z,"));
+    assert!(lines[13].starts_with("2026-03-01T01:00:00Z,"));
+
+    // A step that rounds down to nothing would never get to --end.
+    let step = args.iter().position(|a| a == "--step").unwrap() + 1;
+    args[step] = "0.0001".to_string();
+    let output = run(&args);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("--step must be at least a millisecond")
+    );
+}
+
+#[test]
+fn elements_shows_one_satellites_elements() {
+    let mut args = [
+        "elements",
+        "--catalog",
+        &fixture("decay.json"),
+        "--time",
+        "2026-03-01T00:00:00Z",
+    ]
+    .map(String::from)
+    .to_vec();
+    let output = run(&args);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--sat"));
+
+    args.extend(["--sat", "25544"].map(String::from));
+    let stdout = |extra: &[&str]| {
+        let mut args = args.clone();
+        args.extend(extra.iter().map(|s| s.to_string()));
+        let output = run(&args);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let text = stdout(&[]);
+    assert!(text.starts_with("ISS (ZARYA)\n"), "{}", text);
+    for line in [
+        "2026-02-28 12:00:00 UTC (12.0 hours old at --time)",
+        "15.49507896 rev/day",
+        "51.6461°",
+        "23600",
+        "Apogee                    419 km",
+        "Orbit class               LEO",
+    ] {
+        assert!(text.contains(line), "{:?} in {}", line, text);
+    }
+
+    let json: serde_json::Value = serde_json::from_str(&stdout(&["--format", "json"])).unwrap();
+    assert_eq!(json["norad_id"], 25544);
+    assert_eq!(json["element_set_number"], 999);
+    assert_eq!(json["orbit_class"], "leo");
+
+    let csv = stdout(&["--format", "csv"]);
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert!(
+        lines[0].ends_with(",semi_major_axis_km,apogee_km,perigee_km,orbit_class"),
+        "{}",
+        lines[0]
+    );
+    assert!(lines[1].starts_with("25544,ISS (ZARYA),1998-067A,2026-02-28T12:00:00Z,12.00,"));
+}
+
+#[test]
+fn selftest_passes_and_breaks_errors_down_by_stage() {
+    let output = run(&["selftest".to_string()]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stages = stdout
+        .lines()
+        .map(|line| line.split_whitespace().take(2).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        stages,
+        [
+            ["ok", "propagation"],
+            ["ok", "pipeline"],
+            ["ok", "gmst"],
+            ["ok", "teme_to_ecef"],
+            ["ok", "geodetic"],
+        ]
+    );
+
+    let output = run(&["selftest", "--format", "json"].map(String::from));
+    let json: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json.len(), 5);
+    assert_eq!(json[0]["stage"], "propagation");
+    assert_eq!(json[0]["unit"], "km");
+    assert!(json[0]["max_error"].as_f64().unwrap() < 1e-5);
+}
+
+#[test]
+fn geojson_has_satellites_and_the_terminator() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--format", "geojson"].map(String::from));
+    let output = run(&args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["type"], "FeatureCollection");
+    let features = json["features"].as_array().unwrap();
+    let layer = |name: &str| {
+        features
+            .iter()
+            .filter(|f| f["properties"]["layer"] == name)
+            .collect::<Vec<_>>()
+    };
+    let satellites = layer("satellites");
+    assert_eq!(satellites.len(), 2);
+    for f in &satellites {
+        assert_eq!(f["type"], "Feature");
+        assert_eq!(f["geometry"]["type"], "Point");
+        let [lon, lat] = f["geometry"]["coordinates"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c.as_f64().unwrap())
+            .collect::<Vec<_>>()[..]
+        else {
+            panic!("{}", f);
+        };
+        assert!((-180.0..180.0).contains(&lon) && (-90.0..=90.0).contains(&lat));
+    }
+    let terminator = layer("terminator");
+    assert_eq!(terminator.len(), 1);
+    assert_eq!(terminator[0]["geometry"]["type"], "LineString");
+    assert_eq!(terminator[0]["properties"]["time"], "2026-03-01T00:00:00Z");
+    let line = terminator[0]["geometry"]["coordinates"].as_array().unwrap();
+    assert_eq!(line.len(), 361);
+    assert_eq!(line[0][0], -180.0);
+
+    let output = run(&[
+        "passes",
+        "--catalog",
+        &fixture("decay.json"),
+        "--format",
+        "geojson",
+    ]
+    .map(String::from));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("geojson"));
+}
+
+#[test]
+fn ndjson_has_one_report_per_line() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--format", "ndjson"].map(String::from));
+    let stdout = String::from_utf8(run(&args).stdout).unwrap();
+    let lines = stdout
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+
+    // The same reports as json, one to a line.
+    let format = args.len() - 1;
+    args[format] = "json".to_string();
+    let json: Vec<serde_json::Value> = serde_json::from_slice(&run(&args).stdout).unwrap();
+    assert_eq!(lines, json);
+    assert_eq!(lines.len(), 2);
+
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(
+        [
+            "--format", "ndjson", "--lat", "34", "--lon", "-118", "--lat", "-40", "--lon", "20",
+        ]
+        .map(String::from),
+    );
+    let stdout = String::from_utf8(run(&args).stdout).unwrap();
+    let locations = stdout
+        .lines()
+        .map(|line| {
+            let report: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert!(report["norad_id"].is_u64(), "{}", line);
+            report["location"].as_str().unwrap().to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(locations, ["34, -118", "34, -118", "-40, 20", "-40, 20"]);
+
+    let output = run(&[
+        "passes",
+        "--catalog",
+        &fixture("decay.json"),
+        "--format",
+        "ndjson",
+    ]
+    .map(String::from));
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("ndjson"));
+}
+
+#[test]
+fn ndjson_ephemeris_has_one_step_per_line() {
+    let output = run(&[
+        "ephemeris",
+        "--catalog",
+        &fixture("decay.json"),
+        "--sat",
+        "ISS",
+        "--start",
+        "2026-03-01T00:00:00Z",
+        "--end",
+        "2026-03-01T00:10:00Z",
+        "--format",
+        "ndjson",
+    ]
+    .map(String::from));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let times = stdout
+        .lines()
+        .map(|line| {
+            let point: serde_json::Value = serde_json::from_str(line).unwrap();
+            point["time"].as_str().unwrap().to_string()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(times.len(), 11);
+    assert_eq!(times[0], "2026-03-01T00:00:00Z");
+    assert_eq!(times[10], "2026-03-01T00:10:00Z");
+}
+
+#[test]
+fn json_keeps_fields_in_report_order() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--format", "json"].map(String::from));
+    let stdout = String::from_utf8(run(&args).stdout).unwrap();
+    let first_keys = stdout
+        .lines()
+        .skip(2)
+        .take(3)
+        .map(|line| line.trim().split(':').next().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(first_keys, ["\"name\"", "\"norad_id\"", "\"time\""]);
+}
+
+#[test]
+fn stats_describe_the_catalog_after_filtering() {
+    let stats = |extra: &[&str]| {
+        let mut args = [
+            "--stats",
+            "--catalog",
+            &fixture("pipeline.json"),
+            "--time",
+            "2026-03-01T00:00:00Z",
+        ]
+        .map(String::from)
+        .to_vec();
+        args.extend(extra.iter().map(|s| s.to_string()));
+        let output = run(&args);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let text = stats(&[]);
+    // The Starlink in the fixture has decayed.
+    assert!(text.starts_with("3 satellites\n"), "{}", text);
+    assert!(
+        text.contains("Epochs from 2026-02-27 18:00:00 UTC to 2026-02-28 12:00:00 UTC"),
+        "{}",
+        text
+    );
+    assert!(text.contains("        2  up to a day old\n"), "{}", text);
+    assert!(text.contains("Orbits: 1 LEO, 1 MEO, 1 GEO\n"), "{}", text);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&stats(&["--include-decayed", "--format", "json"])).unwrap();
+    assert_eq!(json["satellites"], 4);
+    assert_eq!(json["orbit_classes"]["leo"], 2);
+    assert_eq!(json["epoch_ages"], serde_json::json!([2, 1, 0, 1, 0]));
+
+    let json: serde_json::Value =
+        serde_json::from_str(&stats(&["--orbit-class", "geo", "--format", "json"])).unwrap();
+    assert_eq!(json["satellites"], 1);
+}
+
+#[test]
+fn output_writes_results_to_a_file() {
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--format", "json"].map(String::from));
+    let stdout = run(&args);
+    assert!(stdout.status.success());
+
+    let path = scratch_dir("output").join("nested/dir/results.json");
+    let _ = fs::remove_file(&path);
+    args.extend(["--output".to_string(), path.display().to_string()]);
+    let output = run(&args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!("Wrote {}", path.display())),
+        "{}",
+        stderr
+    );
+    assert_eq!(fs::read(&path).unwrap(), stdout.stdout);
+}
+
+#[test]
+fn within_km_lists_subpoints_near_the_location() {
+    // The ISS is over -50.59, -13.26 then, and the other satellite in the
+    // fixture is thousands of km north.
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--lat", "-48", "--lon", "-13", "--within-km", "500"].map(String::from));
+    let rows = run_csv(&args);
+    assert_eq!(norad_ids(&rows), vec![25544]);
+    let km: f64 = rows[0]["ground_distance_km"].parse().unwrap();
+    assert!((250.0..350.0).contains(&km), "{}", km);
+
+    // The text report lists everything in the area, not just the closest.
+    let mut args = decay_args("2026-03-01T00:00:00Z");
+    args.extend(["--bbox=-20,-60,-10,40"].map(String::from));
+    let stdout = String::from_utf8(run(&args).stdout).unwrap();
+    assert!(
+        stdout.contains("ISS (ZARYA)") && stdout.contains("CZ-2C"),
+        "{}",
+        stdout
+    );
+}
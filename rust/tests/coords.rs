@@ -0,0 +1,433 @@
+use chrono::{TimeZone, Utc};
+use whatsoverhead::coords::{
+    angular_separation, azimuth_rate, ecef_to_geodetic, elevation_rate,
+    geocentric_to_geodetic_latitude, geodetic_to_geocentric_latitude, gmst,
+    line_of_sight_separation, look_angle_rates, look_angles, observer_ecef, range_rate,
+    refraction_correction_deg, teme_to_ecef, teme_to_ecef_velocity, teme_to_geodetic, Atmosphere,
+    Observer, WGS84_A,
+};
+use whatsoverhead::Propagator;
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {} ± {}, got {}",
+        expected,
+        tolerance,
+        actual
+    );
+}
+
+#[test]
+fn geodetic_round_trip() {
+    for (lat, lon, alt_m) in [
+        (0.0, 0.0, 0.0),
+        (34.56, -118.76, 1200.0),
+        (-45.0, 170.0, 400_000.0),
+        (89.9, 10.0, 35_786_000.0),
+    ] {
+        let observer = Observer {
+            lat,
+            lon,
+            alt_m,
+            atmosphere: None,
+        };
+        let geo = ecef_to_geodetic(observer_ecef(&observer));
+        assert_close(geo.lat, lat, 1e-9);
+        assert_close(geo.lon, lon, 1e-9);
+        assert_close(geo.alt_km, alt_m / 1000.0, 1e-6);
+    }
+}
+
+#[test]
+fn gmst_at_j2000() {
+    // GMST at 2000-01-01 12:00 UT1 is 280.46 degrees.
+    let t = Utc.with_ymd_and_hms(2000, 1, 1, 12, 0, 0).unwrap();
+    assert_close(gmst(t, 0.0).to_degrees(), 280.46, 0.01);
+}
+
+#[test]
+fn delta_ut1_turns_the_earth_under_the_satellite() {
+    let iss = whatsoverhead::data::bundled_catalog()
+        .satellites
+        .into_iter()
+        .filter(|sat| sat.norad_id == 25544)
+        .collect::<Vec<_>>();
+    let t = Utc.with_ymd_and_hms(2026, 10, 2, 12, 0, 0).unwrap();
+    let subpoint = |propagator: &Propagator| ecef_to_geodetic(propagator.propagate(t)[0].position);
+    let before = subpoint(&Propagator::new(iss.clone()));
+    let after = subpoint(&Propagator::new(iss).with_delta_ut1(0.5));
+    // Half a second of Earth rotation: 0.5 s * 360° / 86164 s. The ground
+    // moves east under the satellite, so its longitude goes down.
+    let shift = before.lon - after.lon;
+    assert!((shift - 0.002089).abs() < 1e-5, "{shift}");
+    assert!((before.lat - after.lat).abs() < 1e-9);
+    assert!((before.alt_km - after.alt_km).abs() < 1e-6);
+}
+
+#[test]
+fn geostationary_satellite_stays_put() {
+    // A satellite on the equator moving with the Earth has no velocity in
+    // the Earth-fixed frame, and sits at the same longitude all day.
+    let r = 42_164.0;
+    let v = r * whatsoverhead::coords::EARTH_ROTATION_RATE;
+    let t = Utc.with_ymd_and_hms(2024, 3, 20, 0, 0, 0).unwrap();
+    let theta = gmst(t, 0.0);
+    let (s, c) = theta.sin_cos();
+    let position = [r * c, r * s, 0.0];
+    let velocity = [-v * s, v * c, 0.0];
+
+    let ecef = teme_to_ecef(position, theta);
+    assert_close(ecef[0], r, 1e-6);
+    assert_close(ecef[1], 0.0, 1e-6);
+    for component in teme_to_ecef_velocity(position, velocity, theta) {
+        assert_close(component, 0.0, 1e-9);
+    }
+    let geo = teme_to_geodetic(position, t, 0.0);
+    assert_close(geo.lat, 0.0, 1e-9);
+    assert_close(geo.lon, 0.0, 1e-9);
+    assert_close(geo.alt_km, r - WGS84_A, 1e-6);
+}
+
+#[test]
+fn look_angles_to_geostationary_satellite() {
+    let observer = Observer {
+        lat: 0.0,
+        lon: 0.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    // Straight up.
+    let look = look_angles(&observer, [42_164.0, 0.0, 0.0]);
+    assert_close(look.elevation, 90.0, 1e-9);
+    assert_close(look.range_km, 42_164.0 - WGS84_A, 1e-6);
+
+    // Due east and low: tan(el) = (cos γ - R/r) / sin γ.
+    let gamma = 60.0_f64.to_radians();
+    let look = look_angles(
+        &observer,
+        [42_164.0 * gamma.cos(), 42_164.0 * gamma.sin(), 0.0],
+    );
+    let expected = ((gamma.cos() - WGS84_A / 42_164.0) / gamma.sin())
+        .atan()
+        .to_degrees();
+    assert_close(look.azimuth, 90.0, 1e-9);
+    assert_close(look.elevation, expected, 1e-9);
+}
+
+#[test]
+fn observer_altitude_raises_the_observer() {
+    let sea_level = Observer {
+        lat: 34.56,
+        lon: -118.76,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let mountain = Observer {
+        alt_m: 4000.0,
+        ..sea_level.clone()
+    };
+    let straight_up = observer_ecef(&Observer {
+        alt_m: 400_000.0,
+        ..sea_level.clone()
+    });
+    let low = look_angles(&sea_level, straight_up);
+    let high = look_angles(&mountain, straight_up);
+    assert_close(low.range_km - high.range_km, 4.0, 1e-6);
+}
+
+#[test]
+fn refraction_near_the_horizon() {
+    // About half a degree at the horizon, a tenth of a degree at 5 degrees,
+    // and next to nothing high up.
+    assert_close(refraction_correction_deg(0.0, 10.0, 1010.0), 0.483, 0.005);
+    assert_close(refraction_correction_deg(5.0, 10.0, 1010.0), 0.158, 0.005);
+    assert!(refraction_correction_deg(45.0, 10.0, 1010.0) < 0.02);
+    assert!(refraction_correction_deg(90.0, 10.0, 1010.0).abs() < 1e-4);
+    // Thinner, warmer air bends light less.
+    assert!(
+        refraction_correction_deg(0.0, 30.0, 700.0) < refraction_correction_deg(0.0, 10.0, 1010.0)
+    );
+    assert_eq!(refraction_correction_deg(-5.0, 10.0, 1010.0), 0.0);
+}
+
+#[test]
+fn refraction_only_applies_with_an_atmosphere() {
+    let geometric = Observer {
+        lat: 0.0,
+        lon: 0.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let apparent = Observer {
+        atmosphere: Some(Atmosphere::STANDARD),
+        ..geometric.clone()
+    };
+    // Low in the east.
+    let gamma = 75.0_f64.to_radians();
+    let sat = [42_164.0 * gamma.cos(), 42_164.0 * gamma.sin(), 0.0];
+    let g = look_angles(&geometric, sat);
+    let a = look_angles(&apparent, sat);
+    assert_close(
+        a.elevation - g.elevation,
+        refraction_correction_deg(g.elevation, 10.0, 1010.0),
+        1e-12,
+    );
+    assert_eq!(a.azimuth, g.azimuth);
+    assert_eq!(a.range_km, g.range_km);
+}
+
+#[test]
+fn angular_separation_simple_cases() {
+    assert_close(angular_separation(123.0, 45.0, 123.0, 45.0), 0.0, 1e-12);
+    // Along the horizon, separation is just the difference in azimuth,
+    // including across north.
+    assert_close(angular_separation(10.0, 0.0, 20.0, 0.0), 10.0, 1e-9);
+    assert_close(angular_separation(355.0, 0.0, 5.0, 0.0), 10.0, 1e-9);
+    // The zenith is 90 degrees from everything on the horizon, whatever
+    // azimuth it's given.
+    assert_close(angular_separation(0.0, 90.0, 200.0, 0.0), 90.0, 1e-9);
+    assert_close(angular_separation(0.0, 90.0, 77.0, 90.0), 0.0, 1e-9);
+    // Opposite sides of the zenith.
+    assert_close(angular_separation(0.0, 80.0, 180.0, 80.0), 20.0, 1e-9);
+    assert_close(angular_separation(90.0, 0.0, 270.0, 0.0), 180.0, 1e-9);
+}
+
+#[test]
+fn line_of_sight_separation_matches_the_look_angles() {
+    let observer = Observer {
+        lat: 34.56,
+        lon: -118.76,
+        alt_m: 300.0,
+        atmosphere: None,
+    };
+    let o = observer_ecef(&observer);
+    let a = [o[0] + 300.0, o[1] - 150.0, o[2] + 400.0];
+    let b = [o[0] + 310.0, o[1] - 140.0, o[2] + 420.0];
+    let (la, lb) = (look_angles(&observer, a), look_angles(&observer, b));
+    assert_close(
+        line_of_sight_separation(o, a, b),
+        angular_separation(la.azimuth, la.elevation, lb.azimuth, lb.elevation),
+        1e-9,
+    );
+    // Two satellites in the same direction are together on the sky
+    // however far apart they are.
+    let farther = [o[0] + 600.0, o[1] - 300.0, o[2] + 800.0];
+    assert_close(line_of_sight_separation(o, a, farther), 0.0, 1e-9);
+}
+
+#[test]
+fn angular_separation_near_zenith_shrinks_with_azimuth() {
+    // At elevation h, a difference in azimuth of d is roughly d * cos(h).
+    let sep = angular_separation(0.0, 60.0, 1.0, 60.0);
+    assert_close(sep, 0.5, 1e-4);
+}
+
+#[test]
+fn angular_separation_is_precise_for_tiny_angles() {
+    // An arcsecond apart; acos of the dot product can't resolve this.
+    let arcsec = 1.0 / 3600.0;
+    assert_close(
+        angular_separation(200.0, 30.0, 200.0, 30.0 + arcsec),
+        arcsec,
+        1e-12,
+    );
+}
+
+#[test]
+fn range_rate_is_velocity_along_line_of_sight() {
+    let observer = Observer {
+        lat: 0.0,
+        lon: 0.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let sat = [WGS84_A + 1000.0, 0.0, 0.0];
+    // Straight down toward the observer: approaching, so negative.
+    assert_close(range_rate(&observer, sat, [-2.0, 0.0, 0.0]), -2.0, 1e-12);
+    assert_close(range_rate(&observer, sat, [3.0, 0.0, 0.0]), 3.0, 1e-12);
+    // Across the line of sight the range isn't changing.
+    assert_close(range_rate(&observer, sat, [0.0, 7.5, 0.0]), 0.0, 1e-12);
+}
+
+#[test]
+fn observer_altitude_changes_range_to_a_low_satellite() {
+    let sea_level = Observer {
+        lat: 34.56,
+        lon: -118.76,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let mountain = Observer {
+        alt_m: 4000.0,
+        ..sea_level.clone()
+    };
+    // A LEO satellite 400 km up, off to the northeast.
+    let sat = observer_ecef(&Observer {
+        lat: 40.0,
+        lon: -112.0,
+        alt_m: 400_000.0,
+        ..sea_level.clone()
+    });
+    let low = look_angles(&sea_level, sat);
+    let high = look_angles(&mountain, sat);
+    assert!(
+        low.elevation > 5.0 && low.elevation < 60.0,
+        "{}",
+        low.elevation
+    );
+    // Climbing 4 km brings it closer, but by less than 4 km since it isn't
+    // straight up. It also ends up a little lower in the sky, since there's
+    // less height left between the observer and the satellite.
+    let closer = low.range_km - high.range_km;
+    assert!(closer > 1.0 && closer < 4.0, "{}", closer);
+    assert!(high.elevation < low.elevation);
+}
+
+#[test]
+fn geodetic_longitudes_are_wrapped_and_poles_are_stable() {
+    for (lat, lon, expected_lon) in [
+        (0.0, 179.9, 179.9),
+        (0.0, -179.9, -179.9),
+        (0.0, 180.0, -180.0),
+        (10.0, 359.2, -0.8),
+        (89.5, 0.0, 0.0),
+        (-89.99, 45.0, 45.0),
+    ] {
+        let observer = Observer {
+            lat,
+            lon,
+            alt_m: 400_000.0,
+            atmosphere: None,
+        };
+        let geo = ecef_to_geodetic(observer_ecef(&observer));
+        assert!((-180.0..180.0).contains(&geo.lon), "{}", geo.lon);
+        assert_close(geo.lon, expected_lon, 1e-9);
+        assert_close(geo.lat, lat, 1e-9);
+        assert_close(geo.alt_km, 400.0, 1e-6);
+    }
+    // Right at the pole.
+    let geo = ecef_to_geodetic([0.0, 0.0, 6356.752 + 400.0]);
+    assert_close(geo.lat, 90.0, 1e-9);
+    assert_close(geo.alt_km, 400.0, 1e-3);
+}
+
+#[test]
+fn look_angles_across_the_antimeridian_are_symmetric() {
+    // A satellite right over the antimeridian, seen from either side of it.
+    let sat = observer_ecef(&Observer {
+        lat: 0.0,
+        lon: 180.0,
+        alt_m: 500_000.0,
+        atmosphere: None,
+    });
+    let east = Observer {
+        lat: 0.0,
+        lon: 179.9,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let west = Observer {
+        lon: -179.9,
+        ..east.clone()
+    };
+    let from_east = look_angles(&east, sat);
+    let from_west = look_angles(&west, sat);
+    assert_close(from_east.range_km, from_west.range_km, 1e-6);
+    assert_close(from_east.elevation, from_west.elevation, 1e-9);
+    assert_close(from_east.azimuth, 90.0, 1e-6);
+    assert_close(from_west.azimuth, 270.0, 1e-6);
+}
+
+#[test]
+fn elevation_rate_matches_a_finite_difference() {
+    let observer = Observer {
+        lat: 34.56,
+        lon: -118.76,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let sat = observer_ecef(&Observer {
+        lat: 36.0,
+        lon: -116.0,
+        alt_m: 420_000.0,
+        ..observer.clone()
+    });
+    let velocity = [-3.1, 5.2, 4.4];
+    let dt = 1e-3;
+    let later = [
+        sat[0] + velocity[0] * dt,
+        sat[1] + velocity[1] * dt,
+        sat[2] + velocity[2] * dt,
+    ];
+    let expected =
+        (look_angles(&observer, later).elevation - look_angles(&observer, sat).elevation) / dt;
+    assert_close(elevation_rate(&observer, sat, velocity), expected, 1e-5);
+}
+
+#[test]
+fn azimuth_rate_matches_a_finite_difference() {
+    let observer = Observer {
+        lat: 34.56,
+        lon: -118.76,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let sat = observer_ecef(&Observer {
+        lat: 36.0,
+        lon: -116.0,
+        alt_m: 420_000.0,
+        ..observer.clone()
+    });
+    let velocity = [-3.1, 5.2, 4.4];
+    let dt = 1e-3;
+    let later = [
+        sat[0] + velocity[0] * dt,
+        sat[1] + velocity[1] * dt,
+        sat[2] + velocity[2] * dt,
+    ];
+    let expected =
+        (look_angles(&observer, later).azimuth - look_angles(&observer, sat).azimuth) / dt;
+    assert_close(azimuth_rate(&observer, sat, velocity), expected, 1e-5);
+    let (az_rate, el_rate) = look_angle_rates(&observer, sat, velocity);
+    assert_eq!(az_rate, azimuth_rate(&observer, sat, velocity));
+    assert_eq!(el_rate, elevation_rate(&observer, sat, velocity));
+
+    // Straight overhead the azimuth has no rate to speak of.
+    let zenith = observer_ecef(&Observer {
+        alt_m: 420_000.0,
+        ..observer.clone()
+    });
+    assert_eq!(azimuth_rate(&observer, zenith, velocity), 0.0);
+    // And its elevation is at the top of its arc, not NaN.
+    assert_eq!(elevation_rate(&observer, zenith, velocity), 0.0);
+    assert_eq!(look_angle_rates(&observer, zenith, velocity), (0.0, 0.0));
+}
+
+#[test]
+fn geocentric_latitude_is_closer_to_the_equator() {
+    // 45° geodetic is 44.8076° geocentric, the biggest difference anywhere.
+    assert_close(geodetic_to_geocentric_latitude(45.0), 44.807577, 1e-6);
+    assert_close(geocentric_to_geodetic_latitude(44.807577), 45.0, 1e-6);
+    assert_close(geodetic_to_geocentric_latitude(-30.0), -29.833635, 1e-6);
+    for lat in [0.0, 90.0, -90.0] {
+        assert_close(geodetic_to_geocentric_latitude(lat), lat, 1e-9);
+    }
+    for lat in [-89.0, -60.0, -10.0, 5.0, 34.56, 80.0] {
+        assert_close(
+            geocentric_to_geodetic_latitude(geodetic_to_geocentric_latitude(lat)),
+            lat,
+            1e-9,
+        );
+    }
+    // It's the latitude of the observer's ECEF position.
+    let observer = Observer {
+        lat: 45.0,
+        lon: 10.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let [x, y, z] = observer_ecef(&observer);
+    let geocentric = z.atan2((x * x + y * y).sqrt()).to_degrees();
+    assert_close(geodetic_to_geocentric_latitude(45.0), geocentric, 1e-9);
+}
@@ -0,0 +1,379 @@
+use chrono::{DateTime, Duration, Utc};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use whatsoverhead::coords::{self, look_angles, Observer};
+use whatsoverhead::data::{self, Catalog};
+use whatsoverhead::orbit::OrbitClass;
+use whatsoverhead::query;
+
+// Copies a fixture into its own scratch directory, so tests can write a
+// cache next to it or change it without touching the original.
+fn scratch_catalog(test: &str, fixture: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("whatsoverhead-data-{}-{}", process::id(), test));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(fixture);
+    fs::copy(
+        format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture),
+        &path,
+    )
+    .unwrap();
+    path
+}
+
+fn at(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+}
+
+// Positions and velocities for every satellite every ten minutes for a day.
+fn propagate_all(catalog: &Catalog) -> Vec<Option<([f64; 3], [f64; 3])>> {
+    let start = at("2026-02-28T00:00:00Z");
+    catalog
+        .satellites
+        .iter()
+        .flat_map(|sat| (0..144).map(move |i| sat.ecef_at(start + Duration::minutes(10 * i))))
+        .collect()
+}
+
+#[test]
+fn load_catalog_matches_load_omm() {
+    let path = scratch_catalog("load", "decay.json");
+    let catalog = data::load_catalog(&path).unwrap();
+    let records = data::load_omm(&path).unwrap();
+    assert_eq!(catalog.records, records.len());
+    let ids: Vec<u64> = catalog.satellites.iter().map(|s| s.norad_id).collect();
+    let expected: Vec<u64> = records.iter().map(|r| r.elements.norad_id).collect();
+    assert_eq!(ids, expected);
+}
+
+#[test]
+fn malformed_records_are_skipped_and_reported() {
+    let path = scratch_catalog("malformed", "malformed.json");
+    let catalog = data::load_catalog(&path).unwrap();
+    assert_eq!(catalog.records, 5);
+    let ids: Vec<u64> = catalog.satellites.iter().map(|s| s.norad_id).collect();
+    assert_eq!(ids, [25544]);
+    let rejected: Vec<Option<u64>> = catalog.rejected.iter().map(|r| r.norad_id).collect();
+    // The second one has its ID as a string, which space-track also does.
+    assert_eq!(rejected, [Some(99001), Some(99002), Some(99003), None]);
+    assert!(catalog.rejected[0].reason.contains("MEAN_MOTION"));
+    assert!(catalog.rejected[1].reason.contains("INCLINATION"));
+}
+
+#[test]
+fn a_catalog_that_isnt_an_array_is_an_error() {
+    let path = scratch_catalog("not-array", "decay.json");
+    fs::write(&path, r#"{"OBJECT_NAME": "ISS (ZARYA)"}"#).unwrap();
+    assert!(data::load_catalog(&path).is_err());
+}
+
+#[test]
+fn cache_round_trip_propagates_identically() {
+    let path = scratch_catalog("round-trip", "decay.json");
+    let cache = data::cache_path(&path);
+    let parsed = data::load_catalog(&path).unwrap();
+    data::write_cache(&cache, &path, &parsed).unwrap();
+    let cached = data::read_cache(&cache, &path).expect("cache should be fresh");
+
+    assert_eq!(cached.records, parsed.records);
+    assert_eq!(cached.satellites.len(), parsed.satellites.len());
+    for (a, b) in parsed.satellites.iter().zip(&cached.satellites) {
+        assert_eq!(a.name, b.name);
+        assert_eq!(a.norad_id, b.norad_id);
+        assert_eq!(a.epoch, b.epoch);
+        assert_eq!(a.decay_date, b.decay_date);
+    }
+    // Bit-for-bit, not approximately: the cache should be indistinguishable
+    // from parsing.
+    assert_eq!(propagate_all(&cached), propagate_all(&parsed));
+}
+
+#[test]
+fn cache_is_ignored_when_catalog_changes() {
+    let path = scratch_catalog("stale", "decay.json");
+    let cache = data::cache_path(&path);
+    let parsed = data::load_catalog(&path).unwrap();
+    data::write_cache(&cache, &path, &parsed).unwrap();
+
+    // Dropping a record changes the length even if the mtime doesn't move
+    // on a filesystem with coarse timestamps.
+    let records: Vec<serde_json::Value> =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    fs::write(&path, serde_json::to_string(&records[1..]).unwrap()).unwrap();
+    assert!(data::read_cache(&cache, &path).is_none());
+}
+
+#[test]
+fn missing_or_corrupt_cache_is_a_miss() {
+    let path = scratch_catalog("corrupt", "decay.json");
+    let cache = data::cache_path(&path);
+    assert!(data::read_cache(&cache, &path).is_none());
+    fs::write(&cache, b"not a cache").unwrap();
+    assert!(data::read_cache(&cache, &path).is_none());
+}
+
+#[test]
+fn range_rate_matches_change_in_range() {
+    let catalog = data::load_catalog(scratch_catalog("range-rate", "decay.json")).unwrap();
+    let observer = Observer {
+        lat: 34.56,
+        lon: -118.76,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let t = at("2026-02-28T12:00:00Z");
+    for sat in &catalog.satellites {
+        let report = query::report(sat, &observer, t).unwrap();
+        let range_at = |t| look_angles(&observer, sat.ecef_at(t).unwrap().0).range_km;
+        let numeric =
+            (range_at(t + Duration::seconds(1)) - range_at(t - Duration::seconds(1))) / 2.0;
+        assert!(
+            (report.range_rate_kms - numeric).abs() < 1e-3,
+            "{}: {} vs {}",
+            sat.name,
+            report.range_rate_kms,
+            numeric
+        );
+        assert_eq!(report.approaching, numeric < 0.0);
+    }
+}
+
+#[test]
+fn geostationary_satellite_hangs_still_over_the_ground() {
+    let catalog = data::load_catalog(scratch_catalog("geo", "geo.json")).unwrap();
+    let observer = Observer {
+        lat: 0.0,
+        lon: -137.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let t = at("2026-10-14T06:00:00Z");
+    let report = query::report(&catalog.satellites[0], &observer, t).unwrap();
+    // Without the ω × r term this would come out near the inertial speed.
+    assert!(
+        report.ground_speed_kms < 0.01,
+        "{}",
+        report.ground_speed_kms
+    );
+    // sqrt(μ / 42164 km)
+    assert!(
+        (report.inertial_speed_kms - 3.075).abs() < 0.005,
+        "{}",
+        report.inertial_speed_kms
+    );
+    // One sidereal day.
+    assert!(
+        (report.period_minutes - 1436.07).abs() < 0.1,
+        "{}",
+        report.period_minutes
+    );
+}
+
+#[test]
+fn find_satellite_by_id_or_name() {
+    let catalog = data::load_catalog(scratch_catalog("find", "decay.json")).unwrap();
+    let sats = &catalog.satellites;
+    assert_eq!(
+        data::find_satellite(sats, "25544").unwrap().name,
+        "ISS (ZARYA)"
+    );
+    assert_eq!(data::find_satellite(sats, "iss").unwrap().norad_id, 25544);
+    assert_eq!(
+        data::find_satellite(sats, "Cz-2C R/B").unwrap().norad_id,
+        66123
+    );
+
+    let err = data::find_satellite(sats, "a").unwrap_err().to_string();
+    assert!(err.contains("ISS (ZARYA) (25544)"), "{}", err);
+    assert!(err.contains("STARLINK-1001 (44714)"), "{}", err);
+    assert!(!err.contains("66123"), "{}", err);
+
+    assert!(data::find_satellite(sats, "hubble").is_err());
+    assert!(data::find_satellite(sats, "12345").is_err());
+}
+
+#[test]
+fn find_satellite_wont_guess_between_objects_with_the_same_name() {
+    let catalog = data::load_catalog(scratch_catalog("twins", "decay.json")).unwrap();
+    let mut sats = catalog.satellites;
+    let starlink = sats.iter().position(|s| s.norad_id == 44714).unwrap();
+    sats[starlink].name = "ISS (ZARYA)".to_string();
+    let err = data::find_satellite(&sats, "iss (zarya)")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("2 satellites are named"), "{}", err);
+    assert!(err.contains("ISS (ZARYA) (25544)"), "{}", err);
+    assert!(err.contains("ISS (ZARYA) (44714)"), "{}", err);
+    assert!(!err.contains("66123"), "{}", err);
+    assert_eq!(
+        data::find_satellite(&sats, "44714").unwrap().norad_id,
+        44714
+    );
+}
+
+#[test]
+fn newest_element_set_wins() {
+    let catalog = data::load_catalog(scratch_catalog("duplicates", "duplicates.json")).unwrap();
+    let mut sats = catalog.satellites;
+    assert_eq!(sats.len(), 3);
+    assert_eq!(data::drop_duplicates(&mut sats), 1);
+    let ids = sats.iter().map(|s| s.norad_id).collect::<Vec<_>>();
+    assert_eq!(ids, [25544, 66123]);
+    assert_eq!(sats[0].epoch, at("2026-02-28T12:00:00Z"));
+    assert_eq!(data::drop_duplicates(&mut sats), 0);
+}
+
+#[test]
+fn merging_keeps_every_field_of_the_newest_record() {
+    let group = |json: &str| serde_json::from_str::<Vec<serde_json::Value>>(json).unwrap();
+    let stations = group(
+        r#"[{"NORAD_CAT_ID": 25544, "EPOCH": "2026-02-28T12:00:00", "RCS_SIZE": "LARGE"},
+            {"NORAD_CAT_ID": 48274, "EPOCH": "2026-02-28T09:00:00"},
+            {"OBJECT_NAME": "MYSTERY", "EPOCH": "2026-02-28T09:00:00"}]"#,
+    );
+    let visual = group(
+        r#"[{"NORAD_CAT_ID": "25544", "EPOCH": "2026-02-28T18:30:00.5", "RCS_SIZE": "SMALL"},
+            {"NORAD_CAT_ID": 20580, "EPOCH": "2026-02-27T00:00:00"},
+            {"NORAD_CAT_ID": 48274, "EPOCH": "2026-02-28T08:00:00"}]"#,
+    );
+    let merged = data::merge_omm([stations, visual]);
+    let epochs: Vec<&str> = merged
+        .iter()
+        .map(|r| r["EPOCH"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        epochs,
+        [
+            "2026-02-27T00:00:00",
+            "2026-02-28T18:30:00.5",
+            "2026-02-28T09:00:00",
+            "2026-02-28T09:00:00"
+        ]
+    );
+    assert_eq!(merged[1]["RCS_SIZE"], "SMALL");
+    assert_eq!(merged[3]["OBJECT_NAME"], "MYSTERY");
+}
+
+#[test]
+fn find_by_name_puts_exact_matches_first() {
+    let json = r#"[
+        {"OBJECT_NAME": "ISS DEB", "NORAD_CAT_ID": 1, "EPOCH": "2026-02-28T12:00:00",
+         "MEAN_MOTION": 15.5, "ECCENTRICITY": 0.001, "INCLINATION": 51.6,
+         "RA_OF_ASC_NODE": 0, "ARG_OF_PERICENTER": 0, "MEAN_ANOMALY": 0,
+         "EPHEMERIS_TYPE": 0, "CLASSIFICATION_TYPE": "U", "ELEMENT_SET_NO": 999,
+         "REV_AT_EPOCH": 1, "BSTAR": 0, "MEAN_MOTION_DOT": 0, "MEAN_MOTION_DDOT": 0},
+        {"OBJECT_NAME": "NOAA 19", "NORAD_CAT_ID": 2, "EPOCH": "2026-02-28T12:00:00",
+         "MEAN_MOTION": 14.1, "ECCENTRICITY": 0.001, "INCLINATION": 99.0,
+         "RA_OF_ASC_NODE": 0, "ARG_OF_PERICENTER": 0, "MEAN_ANOMALY": 0,
+         "EPHEMERIS_TYPE": 0, "CLASSIFICATION_TYPE": "U", "ELEMENT_SET_NO": 999,
+         "REV_AT_EPOCH": 1, "BSTAR": 0, "MEAN_MOTION_DOT": 0, "MEAN_MOTION_DDOT": 0},
+        {"OBJECT_NAME": "ISS", "NORAD_CAT_ID": 3, "EPOCH": "2026-02-28T12:00:00",
+         "MEAN_MOTION": 15.5, "ECCENTRICITY": 0.001, "INCLINATION": 51.6,
+         "RA_OF_ASC_NODE": 0, "ARG_OF_PERICENTER": 0, "MEAN_ANOMALY": 0,
+         "EPHEMERIS_TYPE": 0, "CLASSIFICATION_TYPE": "U", "ELEMENT_SET_NO": 999,
+         "REV_AT_EPOCH": 1, "BSTAR": 0, "MEAN_MOTION_DOT": 0, "MEAN_MOTION_DDOT": 0},
+        {"OBJECT_NAME": "ISS (ZARYA)", "NORAD_CAT_ID": 4, "EPOCH": "2026-02-28T12:00:00",
+         "MEAN_MOTION": 15.5, "ECCENTRICITY": 0.001, "INCLINATION": 51.6,
+         "RA_OF_ASC_NODE": 0, "ARG_OF_PERICENTER": 0, "MEAN_ANOMALY": 0,
+         "EPHEMERIS_TYPE": 0, "CLASSIFICATION_TYPE": "U", "ELEMENT_SET_NO": 999,
+         "REV_AT_EPOCH": 1, "BSTAR": 0, "MEAN_MOTION_DOT": 0, "MEAN_MOTION_DDOT": 0}
+    ]"#;
+    let elements: Vec<sgp4::Elements> = serde_json::from_str(json).unwrap();
+    assert_eq!(data::find_by_name(&elements, "iss"), [2, 0, 3]);
+    assert_eq!(data::find_by_name(&elements, " Zarya "), [3]);
+    assert_eq!(data::find_by_name(&elements, "noaa"), [1]);
+    assert!(data::find_by_name(&elements, "hubble").is_empty());
+
+    let catalog = data::load_catalog(scratch_catalog("by-name", "decay.json")).unwrap();
+    let sats = &catalog.satellites;
+    let found = data::find_satellites_by_name(sats, "A");
+    let ids: Vec<u64> = found.iter().map(|&i| sats[i].norad_id).collect();
+    assert_eq!(ids, [25544, 44714]);
+}
+
+#[test]
+fn bundled_catalog_is_usable() {
+    let catalog = data::bundled_catalog();
+    assert!(catalog.rejected.is_empty(), "{:?}", catalog.rejected);
+    assert_eq!(catalog.records, catalog.satellites.len());
+    for id in [25544, 20580] {
+        assert!(
+            catalog.satellites.iter().any(|s| s.norad_id == id),
+            "{}",
+            id
+        );
+    }
+}
+
+#[test]
+fn propagation_keeps_sub_second_precision() {
+    let catalog = data::bundled_catalog();
+    let iss = catalog
+        .satellites
+        .iter()
+        .find(|sat| sat.norad_id == 25544)
+        .unwrap();
+    let t = at("2026-10-02T12:00:00Z");
+    let (p0, v0) = iss.ecef_at(t).unwrap();
+    // At 7.7 km/s, a millisecond is several meters.
+    let (p1, _) = iss.ecef_at(t + Duration::milliseconds(1)).unwrap();
+    let moved = coords::sub(p1, p0);
+    let moved_m = (moved[0].powi(2) + moved[1].powi(2) + moved[2].powi(2)).sqrt() * 1000.0;
+    assert!((moved_m - 7.4).abs() < 0.5, "{moved_m}");
+    // Dropping the fraction would put this at p0.
+    let (p2, _) = iss.ecef_at(t + Duration::milliseconds(750)).unwrap();
+    for i in 0..3 {
+        assert!(
+            (p2[i] - (p0[i] + 0.75 * v0[i])).abs() < 0.01,
+            "{p2:?} {p0:?}"
+        );
+    }
+}
+
+#[test]
+fn catalog_stats_summarize_ages_and_orbits() {
+    let path = format!(
+        "{}/tests/fixtures/pipeline.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let satellites = data::load_catalog(path).unwrap().satellites;
+    let stats = data::catalog_stats(&satellites, at("2026-03-01T00:00:00Z"));
+    assert_eq!(stats.satellites, 4);
+    assert_eq!(stats.oldest_epoch, Some(at("2026-02-20T00:00:00Z")));
+    assert_eq!(stats.newest_epoch, Some(at("2026-02-28T12:00:00Z")));
+    // Half way between the two middle ages.
+    assert_eq!(stats.median_epoch_age_days, Some(1.125));
+    assert_eq!(stats.epoch_ages, [2, 1, 0, 1, 0]);
+    assert_eq!(
+        stats.epoch_ages.iter().sum::<usize>(),
+        stats.satellites,
+        "{:?}",
+        stats
+    );
+    let classes = stats
+        .orbit_classes
+        .iter()
+        .map(|(class, count)| (*class, *count))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        classes,
+        [
+            (OrbitClass::Leo, 2),
+            (OrbitClass::Meo, 1),
+            (OrbitClass::Geo, 1)
+        ]
+    );
+
+    // Elements from after the time count as new.
+    let early = data::catalog_stats(&satellites, at("2026-02-01T00:00:00Z"));
+    assert_eq!(early.epoch_ages, [4, 0, 0, 0, 0]);
+    assert!(early.median_epoch_age_days.unwrap() < 0.0);
+
+    let empty = data::catalog_stats(&[], at("2026-03-01T00:00:00Z"));
+    assert_eq!(empty.satellites, 0);
+    assert_eq!(empty.oldest_epoch, None);
+    assert_eq!(empty.median_epoch_age_days, None);
+    assert_eq!(empty.epoch_ages, [0; 5]);
+}
@@ -0,0 +1,120 @@
+use chrono::{DateTime, Duration, Utc};
+
+use whatsoverhead::coords::Observer;
+use whatsoverhead::data::{self, Satellite};
+use whatsoverhead::eclipse::{find_eclipse_events, EclipseKind};
+use whatsoverhead::sun::{shadow, solar_terminator, sun_ecef, sun_elevation, Shadow};
+
+fn satellites(fixture: &str) -> Vec<Satellite> {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+    data::load_catalog(path).unwrap().satellites
+}
+
+fn at(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+}
+
+fn shadow_at(sat: &Satellite, t: DateTime<Utc>) -> Shadow {
+    shadow(sat.ecef_at(t).unwrap().0, sun_ecef(t, 0.0))
+}
+
+#[test]
+fn leo_eclipses_go_through_the_penumbra_into_the_umbra_and_out() {
+    let sats = satellites("decay.json");
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let start = at("2026-03-01T00:00:00Z");
+    let events = find_eclipse_events(iss, |t| sun_ecef(t, 0.0), start, 24.0);
+    // Once an orbit, give or take where the day starts and ends.
+    assert!((60..=64).contains(&events.len()), "{}", events.len());
+    assert!(events.windows(2).all(|w| w[0].time < w[1].time));
+
+    let kinds = [
+        EclipseKind::PenumbraEntry,
+        EclipseKind::UmbraEntry,
+        EclipseKind::UmbraExit,
+        EclipseKind::PenumbraExit,
+    ];
+    let first = kinds.iter().position(|k| *k == events[0].kind).unwrap();
+    for (i, e) in events.iter().enumerate() {
+        assert_eq!(e.kind, kinds[(first + i) % 4], "{:?}", e);
+        assert!((400.0..450.0).contains(&e.alt_km), "{:?}", e);
+    }
+    for w in events.windows(2) {
+        let (a, b) = (&w[0], &w[1]);
+        let middle = a.time + (b.time - a.time) / 2;
+        match (a.kind, b.kind) {
+            // Seconds to cross the penumbra, and half an hour or so in the
+            // umbra.
+            (EclipseKind::PenumbraEntry, EclipseKind::UmbraEntry)
+            | (EclipseKind::UmbraExit, EclipseKind::PenumbraExit) => {
+                assert!(b.time - a.time < Duration::seconds(30), "{:?}", w);
+                assert_eq!(shadow_at(iss, middle), Shadow::Penumbra);
+            }
+            (EclipseKind::UmbraEntry, EclipseKind::UmbraExit) => {
+                assert!(b.time - a.time > Duration::minutes(20), "{:?}", w);
+                assert_eq!(shadow_at(iss, middle), Shadow::Umbra);
+            }
+            _ => assert_eq!(shadow_at(iss, middle), Shadow::Sunlit),
+        }
+    }
+}
+
+#[test]
+fn shadow_regions() {
+    let sun = [149_597_870.7, 0.0, 0.0];
+    // On the day side, and far off to the side of the night side.
+    assert_eq!(shadow([7000.0, 0.0, 0.0], sun), Shadow::Sunlit);
+    assert_eq!(shadow([-7000.0, 7000.0, 0.0], sun), Shadow::Sunlit);
+    // Straight behind the Earth.
+    assert_eq!(shadow([-7000.0, 0.0, 0.0], sun), Shadow::Umbra);
+    assert_eq!(shadow([-42_164.0, 0.0, 0.0], sun), Shadow::Umbra);
+    // Just outside the umbra's edge at GEO distance, where the penumbra is
+    // a few hundred km wide.
+    assert_eq!(shadow([-42_164.0, 6478.0, 0.0], sun), Shadow::Penumbra);
+    assert_eq!(shadow([-42_164.0, 6878.0, 0.0], sun), Shadow::Sunlit);
+}
+
+#[test]
+fn the_terminator_is_where_the_sun_is_on_the_horizon() {
+    for t in [
+        at("2026-06-21T12:00:00Z"),
+        at("2026-12-21T03:00:00Z"),
+        at("2026-03-20T18:00:00Z"),
+    ] {
+        let line = solar_terminator(t, 5.0);
+        assert_eq!(line.len(), 73);
+        assert_eq!(line[0].1, -180.0);
+        assert_eq!(line[72].1, 180.0);
+        let sun = sun_ecef(t, 0.0);
+        for &(lat, lon) in &line {
+            let observer = Observer {
+                lat,
+                lon,
+                alt_m: 0.0,
+                atmosphere: None,
+            };
+            // The line is for a round Earth; the ellipsoid normal leans a
+            // little differently.
+            let elevation = sun_elevation(&observer, sun);
+            assert!(elevation.abs() < 0.3, "{} at {}, {}", elevation, lat, lon);
+        }
+    }
+}
+
+#[test]
+fn the_terminator_leaves_the_summer_pole_in_daylight() {
+    // In June the line reaches up to the Arctic Circle opposite the Sun and
+    // down to the Antarctic Circle under it, so it never gets near the
+    // north pole.
+    let line = solar_terminator(at("2026-06-21T12:00:00Z"), 1.0);
+    assert_eq!(line.len(), 361);
+    let (lowest, highest) = line
+        .iter()
+        .fold((90.0_f64, -90.0_f64), |(lo, hi), &(lat, _)| {
+            (lo.min(lat), hi.max(lat))
+        });
+    assert!((lowest + 66.56).abs() < 0.1, "{}", lowest);
+    assert!((highest - 66.56).abs() < 0.1, "{}", highest);
+    let at_noon = line.iter().find(|&&(_, lon)| lon == 0.0).unwrap();
+    assert!(at_noon.0 < -66.0, "{:?}", at_noon);
+}
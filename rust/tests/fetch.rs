@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use whatsoverhead::data;
+use whatsoverhead::fetch::{
+    fetch_celestrak_group, fetch_celestrak_groups, fetch_spacetrack, load_celestrak_group,
+    Credentials, RetryPolicy,
+};
+
+// A stand-in for space-track.org that answers one request per connection
+// with the next of `responses`, and hands back the requests it got.
+fn serve(responses: Vec<String>) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        let mut requests = Vec::new();
+        for response in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let request = read_request(&mut reader);
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            requests.push(request);
+        }
+        requests
+    });
+    (url, server)
+}
+
+// A stand-in for CelesTrak that answers each GROUP with its response, on
+// as many connections as it gets, and counts the requests for each group.
+// A group without a response never answers at all.
+fn serve_groups(
+    groups: Vec<(&'static str, Option<String>)>,
+) -> (String, Arc<Mutex<HashMap<String, usize>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let counts = Arc::new(Mutex::new(HashMap::new()));
+    let groups: Arc<HashMap<_, _>> = Arc::new(groups.into_iter().collect());
+    let server_counts = counts.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let (groups, counts) = (groups.clone(), server_counts.clone());
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.unwrap());
+                let request = read_request(&mut reader);
+                let group = request
+                    .split(['?', '&', ' '])
+                    .find_map(|part| part.strip_prefix("GROUP="))
+                    .unwrap()
+                    .to_string();
+                *counts.lock().unwrap().entry(group.clone()).or_insert(0) += 1;
+                match &groups[group.as_str()] {
+                    Some(response) => reader.get_mut().write_all(response.as_bytes()).unwrap(),
+                    None => thread::sleep(Duration::from_secs(30)),
+                }
+            });
+        }
+    });
+    (url, counts)
+}
+
+fn read_request(reader: &mut BufReader<TcpStream>) -> String {
+    let mut request = String::new();
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+            length = value.trim().parse().unwrap();
+        }
+        request.push_str(&line);
+        if line == "\r\n" {
+            break;
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    request.push_str(&String::from_utf8(body).unwrap());
+    request
+}
+
+fn response(status: &str, headers: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+// Retries without the waiting, so tests stay quick.
+fn impatient(retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        retries,
+        base_delay: Duration::ZERO,
+        max_delay: Duration::from_secs(5),
+        timeout: Duration::from_secs(5),
+    }
+}
+
+#[test]
+fn logs_in_and_queries_with_the_session_cookie() {
+    let (url, server) = serve(vec![
+        response(
+            "200 OK",
+            "Set-Cookie: chocolatechip=abc123; path=/\r\n",
+            "\"\"",
+        ),
+        response("200 OK", "", data::BUNDLED_OMM),
+    ]);
+    let credentials = Credentials::new("me@example.com", "p&ss word");
+    let (records, json) = fetch_spacetrack(
+        &url,
+        &credentials,
+        "/basicspacedata/query/class/gp",
+        &impatient(0),
+    )
+    .unwrap();
+    assert_eq!(records.len(), 8);
+    assert_eq!(json, data::BUNDLED_OMM.as_bytes());
+
+    let requests = server.join().unwrap();
+    assert!(
+        requests[0].starts_with("POST /ajaxauth/login "),
+        "{}",
+        requests[0]
+    );
+    assert!(
+        requests[0].ends_with("identity=me%40example.com&password=p%26ss%20word"),
+        "{}",
+        requests[0]
+    );
+    assert!(
+        requests[1].starts_with("GET /basicspacedata/query/class/gp "),
+        "{}",
+        requests[1]
+    );
+    assert!(
+        requests[1].contains("Cookie: chocolatechip=abc123\r\n"),
+        "{}",
+        requests[1]
+    );
+}
+
+#[test]
+fn a_rejected_login_names_the_user_but_not_the_password() {
+    let (url, server) = serve(vec![response("200 OK", "", "{\"Login\":\"Failed\"}")]);
+    let credentials = Credentials::new("me@example.com", "hunter2");
+    let Err(err) = fetch_spacetrack(&url, &credentials, "/query", &impatient(3)) else {
+        panic!("the login should have failed");
+    };
+    let message = format!("{err:#}");
+    assert!(
+        message.contains("rejected the login for me@example.com"),
+        "{message}"
+    );
+    assert!(!message.contains("hunter2"), "{message}");
+    assert_eq!(server.join().unwrap().len(), 1);
+}
+
+#[test]
+fn credentials_come_from_a_file_and_keep_the_password_to_themselves() {
+    let path = env::temp_dir().join(format!("whatsoverhead-fetch-{}.toml", process::id()));
+    fs::write(
+        &path,
+        "username = \"me@example.com\"\npassword = \"hunter2\"\n",
+    )
+    .unwrap();
+    let credentials = Credentials::from_file(&path).unwrap();
+    assert_eq!(credentials.username, "me@example.com");
+    let shown = format!("{credentials:?}");
+    assert!(shown.contains("me@example.com"), "{shown}");
+    assert!(!shown.contains("hunter2"), "{shown}");
+
+    fs::write(&path, "user = \"me@example.com\"\n").unwrap();
+    assert!(Credentials::from_file(&path).is_err());
+}
+
+#[test]
+fn transient_failures_are_retried() {
+    let (url, server) = serve(vec![
+        response("503 Service Unavailable", "Retry-After: 0\r\n", ""),
+        response("200 OK", "Set-Cookie: chocolatechip=abc123\r\n", "\"\""),
+        response("429 Too Many Requests", "", ""),
+        response("500 Internal Server Error", "", ""),
+        response("200 OK", "", data::BUNDLED_OMM),
+    ]);
+    let credentials = Credentials::new("me@example.com", "hunter2");
+    let (records, _) = fetch_spacetrack(&url, &credentials, "/query", &impatient(3)).unwrap();
+    assert_eq!(records.len(), 8);
+    assert_eq!(server.join().unwrap().len(), 5);
+}
+
+#[test]
+fn giving_up_reports_the_last_status() {
+    let (url, server) = serve(vec![
+        response("502 Bad Gateway", "", ""),
+        response("504 Gateway Timeout", "", ""),
+        response("503 Service Unavailable", "", ""),
+    ]);
+    let credentials = Credentials::new("me@example.com", "hunter2");
+    let Err(err) = fetch_spacetrack(&url, &credentials, "/query", &impatient(2)) else {
+        panic!("every attempt failed");
+    };
+    let message = format!("{err:#}");
+    assert!(
+        message.contains("login failed with HTTP status 503 after 3 attempts"),
+        "{message}"
+    );
+    assert_eq!(server.join().unwrap().len(), 3);
+}
+
+#[test]
+fn retry_after_is_respected() {
+    let (url, server) = serve(vec![
+        response("429 Too Many Requests", "Retry-After: 1\r\n", ""),
+        response("200 OK", "Set-Cookie: chocolatechip=abc123\r\n", "\"\""),
+        response("200 OK", "", data::BUNDLED_OMM),
+    ]);
+    let credentials = Credentials::new("me@example.com", "hunter2");
+    let start = Instant::now();
+    fetch_spacetrack(&url, &credentials, "/query", &impatient(1)).unwrap();
+    assert!(
+        start.elapsed() >= Duration::from_secs(1),
+        "{:?}",
+        start.elapsed()
+    );
+    assert_eq!(server.join().unwrap().len(), 3);
+}
+
+// An OMM record with just enough in it for merging.
+fn omm(norad_id: u64, name: &str, epoch: &str) -> String {
+    format!(r#"{{"OBJECT_NAME":"{name}","NORAD_CAT_ID":{norad_id},"EPOCH":"{epoch}"}}"#)
+}
+
+#[test]
+fn groups_that_fail_are_left_out_of_the_merge() {
+    let stations = format!(
+        "[{},{}]",
+        omm(25544, "ISS (ZARYA)", "2026-10-13T12:00:00"),
+        omm(48274, "CSS (TIANHE)", "2026-10-13T08:00:00")
+    );
+    let visitors = format!(
+        "[{},{}]",
+        omm(25544, "ISS (ZARYA)", "2026-10-14T06:00:00"),
+        omm(63204, "PROGRESS MS-31", "2026-10-13T20:00:00")
+    );
+    let (url, counts) = serve_groups(vec![
+        ("stations", Some(response("200 OK", "", &stations))),
+        ("visual", Some(response("200 OK", "", &visitors))),
+        (
+            "oneweb",
+            Some(response("500 Internal Server Error", "", "")),
+        ),
+        ("starlink", None),
+    ]);
+    let retry = RetryPolicy {
+        timeout: Duration::from_millis(300),
+        ..impatient(2)
+    };
+    let groups = ["stations", "oneweb", "starlink", "visual"].map(String::from);
+    let download = fetch_celestrak_groups(&url, &groups, &retry);
+
+    let ids: Vec<u64> = download
+        .records
+        .iter()
+        .map(|r| r["NORAD_CAT_ID"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, [25544, 48274, 63204]);
+    assert_eq!(download.records[0]["EPOCH"], "2026-10-14T06:00:00");
+
+    let failed: Vec<&str> = download.failed.iter().map(|(g, _)| g.as_str()).collect();
+    assert_eq!(failed, ["oneweb", "starlink"]);
+    let oneweb = format!("{:#}", download.failed[0].1);
+    assert!(
+        oneweb.contains("oneweb failed with HTTP status 500 after 3 attempts"),
+        "{oneweb}"
+    );
+    let starlink = format!("{:#}", download.failed[1].1);
+    assert!(starlink.contains("after 3 attempts"), "{starlink}");
+
+    let counts = counts.lock().unwrap();
+    assert_eq!(counts["stations"], 1);
+    assert_eq!(counts["visual"], 1);
+    assert_eq!(counts["oneweb"], 3);
+    assert_eq!(counts["starlink"], 3);
+}
+
+#[test]
+fn an_unknown_group_is_an_error() {
+    let (url, _) = serve_groups(vec![(
+        "nonesuch",
+        Some(response("200 OK", "", "No GP data found")),
+    )]);
+    let Err(err) = fetch_celestrak_group(&url, "nonesuch", &impatient(0)) else {
+        panic!("there's no such group");
+    };
+    let message = format!("{err:#}");
+    assert!(
+        message.contains("didn't send OMM JSON for nonesuch: No GP data found"),
+        "{message}"
+    );
+}
+
+#[test]
+fn a_group_loads_as_sgp4_elements() {
+    let (url, counts) = serve_groups(vec![(
+        "active",
+        Some(response("200 OK", "", data::BUNDLED_OMM)),
+    )]);
+    let elements = load_celestrak_group(&url, "active", &impatient(0)).unwrap();
+    assert_eq!(elements.len(), 8);
+    assert!(elements.iter().any(|e| e.norad_id == 25544));
+    assert!(sgp4::Constants::from_elements(&elements[0]).is_ok());
+    assert_eq!(counts.lock().unwrap()["active"], 1);
+
+    let (url, _) = serve_groups(vec![(
+        "broken",
+        Some(response("200 OK", "", r#"[{"OBJECT_NAME": "NOTHING"}]"#)),
+    )]);
+    let Err(err) = load_celestrak_group(&url, "broken", &impatient(0)) else {
+        panic!("the elements are missing");
+    };
+    assert!(
+        format!("{err:#}").contains("elements for broken that SGP4 can't read"),
+        "{err:#}"
+    );
+}
@@ -0,0 +1,208 @@
+use whatsoverhead::geo::{
+    bearing, cardinal_direction, ground_bearing, haversine_distance, longitude_difference,
+    normalize_longitude, Area, BoundingBox,
+};
+
+const LOS_ANGELES: (f64, f64) = (34.0522, -118.2437);
+const NEW_YORK: (f64, f64) = (40.7128, -74.0060);
+const LONDON: (f64, f64) = (51.5074, -0.1278);
+const PARIS: (f64, f64) = (48.8566, 2.3522);
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {} ± {}, got {}",
+        expected,
+        tolerance,
+        actual
+    );
+}
+
+#[test]
+fn distance_between_cities() {
+    let d = haversine_distance(LOS_ANGELES.0, LOS_ANGELES.1, NEW_YORK.0, NEW_YORK.1);
+    assert_close(d, 3935.7, 0.5);
+    let d = haversine_distance(LONDON.0, LONDON.1, PARIS.0, PARIS.1);
+    assert_close(d, 343.6, 0.5);
+}
+
+#[test]
+fn bearing_between_cities() {
+    let b = bearing(LOS_ANGELES.0, LOS_ANGELES.1, NEW_YORK.0, NEW_YORK.1);
+    assert_close(b, 65.92, 0.01);
+    // Great circles aren't rhumb lines, so the way back isn't just b + 180.
+    let b = bearing(NEW_YORK.0, NEW_YORK.1, LOS_ANGELES.0, LOS_ANGELES.1);
+    assert_close(b, 273.69, 0.01);
+    let b = bearing(LONDON.0, LONDON.1, PARIS.0, PARIS.1);
+    assert_close(b, 148.12, 0.01);
+    assert_eq!(cardinal_direction(b), "southeast");
+}
+
+#[test]
+fn bearing_across_the_pole_is_north() {
+    // The shortest way from one side of the pole to the other goes straight
+    // over it.
+    for (lon1, lon2) in [(0.0, 180.0), (-10.0, 170.0), (100.0, -80.0)] {
+        let b = bearing(80.0, lon1, 80.0, lon2);
+        assert!((0.0..360.0).contains(&b), "{}", b);
+        assert!(b.min(360.0 - b) < 1e-6, "{}", b);
+        assert_eq!(cardinal_direction(b), "north");
+        assert_close(haversine_distance(80.0, lon1, 80.0, lon2), 2223.9, 0.5);
+    }
+}
+
+#[test]
+fn ground_bearing_is_undefined_for_coincident_points() {
+    assert_eq!(ground_bearing(34.0, -118.0, 34.0, -118.0), None);
+    assert_eq!(ground_bearing(34.0, -118.0, 34.001, -118.001), None);
+    let b = ground_bearing(34.0, -118.0, 33.0, -119.0).unwrap();
+    assert_eq!(cardinal_direction(b), "southwest");
+}
+
+#[test]
+fn longitudes_wrap_into_the_usual_range() {
+    assert_close(normalize_longitude(359.2), -0.8, 1e-9);
+    assert_close(normalize_longitude(-180.0), -180.0, 1e-12);
+    assert_close(normalize_longitude(180.0), -180.0, 1e-12);
+    assert_close(normalize_longitude(540.5), -179.5, 1e-9);
+    assert_close(normalize_longitude(-190.0), 170.0, 1e-9);
+    assert_close(normalize_longitude(-1e-17), 0.0, 1e-12);
+    assert_close(longitude_difference(179.9, -179.9), 0.2, 1e-9);
+    assert_close(longitude_difference(-179.9, 179.9), -0.2, 1e-9);
+}
+
+#[test]
+fn distances_across_the_antimeridian_are_symmetric() {
+    // Two observers 0.2 degrees apart across the antimeridian, and the same
+    // pair written with out-of-range longitudes.
+    let east = (0.0, 179.9);
+    let west = (0.0, -179.9);
+    let d = haversine_distance(east.0, east.1, west.0, west.1);
+    assert_close(d, 22.24, 0.01);
+    assert_close(haversine_distance(west.0, west.1, east.0, east.1), d, 1e-9);
+    assert_close(haversine_distance(0.0, 179.9, 0.0, 180.1), d, 1e-9);
+
+    // Going east from 179.9 and west from -179.9.
+    assert_close(bearing(east.0, east.1, west.0, west.1), 90.0, 1e-9);
+    assert_close(bearing(west.0, west.1, east.0, east.1), 270.0, 1e-9);
+
+    // Somewhere a bit north of the antimeridian is the same distance from
+    // both.
+    let north = (1.0, 180.0);
+    assert_close(
+        haversine_distance(east.0, east.1, north.0, north.1),
+        haversine_distance(west.0, west.1, north.0, north.1),
+        1e-9,
+    );
+    assert_close(
+        haversine_distance(east.0, east.1, north.0, -180.0),
+        haversine_distance(west.0, west.1, north.0, 180.0),
+        1e-9,
+    );
+}
+
+#[test]
+fn two_degrees_across_the_dateline_is_two_degrees() {
+    // 2 degrees of a 6371 km great circle, not 358.
+    assert_close(haversine_distance(0.0, 179.0, 0.0, -179.0), 222.39, 0.01);
+    assert_close(haversine_distance(0.0, -179.0, 0.0, 179.0), 222.39, 0.01);
+    // Meridians close up away from the equator.
+    assert_close(haversine_distance(60.0, 179.0, 60.0, -179.0), 111.2, 0.1);
+    assert_eq!(ground_bearing(0.0, 179.0, 0.0, -179.0), Some(90.0));
+}
+
+#[test]
+fn near_the_pole_the_short_way_is_over_it() {
+    // Two points 0.1 degrees from the pole on opposite meridians are 0.2
+    // degrees apart over the top, across the dateline or not.
+    for (lon1, lon2) in [(0.0, 180.0), (90.0, -90.0), (179.0, -1.0)] {
+        assert_close(haversine_distance(89.9, lon1, 89.9, lon2), 22.24, 0.01);
+        assert_close(bearing(89.9, lon1, 89.9, lon2), 0.0, 1e-6);
+    }
+    assert_close(haversine_distance(-89.9, 179.5, -89.9, -179.5), 0.19, 0.01);
+}
+
+#[test]
+fn distances_near_the_pole_and_antipodes() {
+    // 89.5 degrees north is 55.6 km from the pole whatever the longitude.
+    for lon in [0.0, 90.0, 180.0, -135.0] {
+        assert_close(haversine_distance(89.5, 0.0, 90.0, lon), 55.6, 0.1);
+    }
+    assert_close(
+        haversine_distance(89.5, 0.0, 89.5, 180.0),
+        haversine_distance(89.5, 180.0, 89.5, 0.0),
+        1e-9,
+    );
+    let half_way_round = std::f64::consts::PI * 6371.0;
+    assert_close(
+        haversine_distance(0.0, 0.0, 0.0, 180.0),
+        half_way_round,
+        1e-6,
+    );
+    assert_close(
+        haversine_distance(45.0, 10.0, -45.0, -170.0),
+        half_way_round,
+        1e-6,
+    );
+}
+
+#[test]
+fn bounding_boxes_can_wrap_across_the_antimeridian() {
+    let pacific = BoundingBox {
+        min_lon: 170.0,
+        min_lat: -10.0,
+        max_lon: -170.0,
+        max_lat: 10.0,
+    };
+    assert!(pacific.contains(0.0, 179.9));
+    assert!(pacific.contains(0.0, -180.0));
+    assert!(pacific.contains(5.0, -175.0));
+    assert!(pacific.contains(0.0, 170.0));
+    assert!(!pacific.contains(0.0, 0.0));
+    assert!(!pacific.contains(0.0, 160.0));
+    assert!(!pacific.contains(0.0, -160.0));
+    assert!(!pacific.contains(11.0, 180.0));
+
+    // The same edges the other way round are everything else.
+    let rest = BoundingBox {
+        min_lon: -170.0,
+        max_lon: 170.0,
+        ..pacific
+    };
+    assert!(rest.contains(0.0, 0.0));
+    assert!(!rest.contains(0.0, 179.9));
+
+    // Longitudes outside [-180, 180) mean the same places.
+    assert!(pacific.contains(0.0, 185.0));
+    let world = BoundingBox {
+        min_lon: -180.0,
+        min_lat: -90.0,
+        max_lon: 180.0,
+        max_lat: 90.0,
+    };
+    assert!(world.contains(0.0, -180.0) && world.contains(0.0, 179.9) && world.contains(90.0, 0.0));
+}
+
+#[test]
+fn circles_stay_round_near_the_pole() {
+    let circle = Area::Circle {
+        lat: 89.0,
+        lon: 0.0,
+        radius_km: 500.0,
+    };
+    // Across the pole, 180 degrees of longitude away but only 3 degrees of
+    // arc. A box of +/- 4.5 degrees of longitude would miss it.
+    assert!(circle.contains(88.0, 180.0));
+    assert!(circle.contains(88.0, -90.0));
+    // 4 degrees of arc is 445 km; 4.6 degrees is 511 km.
+    assert!(circle.contains(85.0, 0.0));
+    assert!(!circle.contains(84.4, 0.0));
+    // And radius still means distance at the equator.
+    let equator = Area::Circle {
+        lat: 0.0,
+        lon: 179.0,
+        radius_km: 500.0,
+    };
+    assert!(equator.contains(0.0, -177.0));
+    assert!(!equator.contains(0.0, -175.0));
+}
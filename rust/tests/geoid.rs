@@ -0,0 +1,64 @@
+use whatsoverhead::geoid::{self, AltitudeDatum};
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "expected {} ± {}, got {}",
+        expected,
+        tolerance,
+        actual
+    );
+}
+
+#[test]
+fn undulation_matches_egm96() {
+    // EGM96 values at a few places. The 5 degree grid is coarse, so allow a
+    // few meters.
+    assert_close(geoid::undulation(29.7604, -95.3698), -28.4, 3.0); // Houston
+    assert_close(geoid::undulation(32.7157, -117.1611), -35.2, 3.0); // San Diego
+    assert_close(geoid::undulation(51.4779, -0.0015), 45.9, 3.0); // Greenwich
+                                                                  // The big low south of India and high over New Guinea.
+    assert!(geoid::undulation(5.0, 78.0) < -90.0);
+    assert!(geoid::undulation(-5.0, 145.0) > 60.0);
+}
+
+#[test]
+fn undulation_is_continuous_across_the_grid_edges() {
+    assert_close(
+        geoid::undulation(10.0, 179.999),
+        geoid::undulation(10.0, -180.0),
+        0.01,
+    );
+    assert_close(
+        geoid::undulation(10.0, 540.0),
+        geoid::undulation(10.0, 180.0),
+        1e-9,
+    );
+    let north = geoid::undulation(90.0, 0.0);
+    assert_close(geoid::undulation(90.0, 100.0), north, 0.01);
+    assert!(geoid::undulation(-90.0, 0.0).is_finite());
+}
+
+#[test]
+fn datums_convert_to_ellipsoidal_height() {
+    let (lat, lon) = (34.56, -118.76);
+    assert_eq!(
+        AltitudeDatum::Ellipsoid.to_ellipsoidal(lat, lon, 1000.0),
+        1000.0
+    );
+    let h = AltitudeDatum::Msl.to_ellipsoidal(lat, lon, 1000.0);
+    assert_close(h, 1000.0 + geoid::undulation(lat, lon), 1e-9);
+    // Southern California's geoid is well below the ellipsoid.
+    assert!(h < 970.0, "{}", h);
+}
+
+#[test]
+fn orthometric_and_ellipsoidal_heights_round_trip() {
+    for (lat, lon) in [(0.0, 0.0), (34.56, -118.76), (-5.0, 145.0), (5.0, 78.0)] {
+        let h = geoid::orthometric_to_ellipsoidal(lat, lon, 2500.0);
+        assert_close(geoid::ellipsoidal_to_orthometric(lat, lon, h), 2500.0, 1e-9);
+    }
+    // South of India, mean sea level is far below the ellipsoid, so a point
+    // on the ellipsoid is well above sea level.
+    assert!(geoid::ellipsoidal_to_orthometric(5.0, 78.0, 0.0) > 90.0);
+}
@@ -0,0 +1,87 @@
+use whatsoverhead::groups::{constellation, count, find_formations};
+
+#[test]
+fn groups_real_object_names() {
+    for (name, group) in [
+        ("STARLINK-1007", "STARLINK"),
+        ("STARLINK-30123", "STARLINK"),
+        ("Starlink 5000", "STARLINK"),
+        ("ONEWEB-0012", "ONEWEB"),
+        ("IRIDIUM 106", "IRIDIUM"),
+        ("IRIDIUM 33 DEB", "IRIDIUM"),
+        ("COSMOS 2251", "COSMOS"),
+        ("COSMOS 2251 DEB", "COSMOS"),
+        ("ISS (ZARYA)", "ISS"),
+        ("FLOCK 4V-7", "FLOCK"),
+        ("LEMUR-2-JOHNGRANT", "LEMUR"),
+        ("GPS BIIF-2  (PRN 01)", "GPS"),
+        ("NOAA 19", "NOAA"),
+        ("GOES 18", "GOES"),
+        ("CZ-2C R/B", "CZ"),
+        ("SL-16 R/B", "SL"),
+        ("GLOBALSTAR M087", "GLOBALSTAR"),
+        ("METEOR-M2 3", "METEOR"),
+        ("  TDRS 3  ", "TDRS"),
+        ("1998-067A", "OTHER"),
+        ("", "OTHER"),
+    ] {
+        assert_eq!(constellation(name), group, "{:?}", name);
+    }
+}
+
+#[test]
+fn counts_each_constellation() {
+    let counts = count([
+        "STARLINK-1007",
+        "STARLINK-30123",
+        "ISS (ZARYA)",
+        "OneWeb-0012",
+    ]);
+    assert_eq!(
+        counts.into_iter().collect::<Vec<_>>(),
+        [
+            ("ISS".to_string(), 1),
+            ("ONEWEB".to_string(), 1),
+            ("STARLINK".to_string(), 2)
+        ]
+    );
+}
+
+// An orbit like the Starlink fixture's, with the given inclination and
+// mean motion.
+fn orbit(inclination: f64, mean_motion: f64) -> sgp4::Elements {
+    let path = format!("{}/tests/fixtures/decay.json", env!("CARGO_MANIFEST_DIR"));
+    let mut records: Vec<sgp4::Elements> =
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    sgp4::Elements {
+        inclination,
+        mean_motion,
+        ..records.swap_remove(1)
+    }
+}
+
+#[test]
+fn finds_formations_by_shell() {
+    let elements = [
+        orbit(53.05, 15.06),
+        orbit(97.6, 15.2),
+        orbit(53.2, 15.05),
+        orbit(51.64, 15.5),
+        orbit(97.5, 15.21),
+        orbit(53.0, 15.061),
+    ];
+    assert_eq!(
+        find_formations(&elements, 0.5, 10.0),
+        vec![vec![0, 2, 5], vec![1, 4], vec![3]]
+    );
+    // With tight enough tolerances, everything flies alone.
+    assert_eq!(find_formations(&elements, 0.01, 0.1).len(), 6);
+}
+
+#[test]
+fn formations_straddling_grid_lines_stay_together() {
+    // 0.98 and 1.02 degrees are in different one-degree cells, but
+    // neighbouring ones.
+    let elements = [orbit(0.98, 15.06), orbit(1.02, 15.06)];
+    assert_eq!(find_formations(&elements, 1.0, 10.0), vec![vec![0, 1]]);
+}
@@ -0,0 +1,191 @@
+// Satellites loaded from OMM JSON the way any catalog is, and propagated to
+// Earth-fixed positions and velocities, checked against the TEME states
+// published with Vallado's SGP4 verification set (Vallado, Crawford, Hujsak
+// and Kelso, "Revisiting Spacetrack Report #3", AIAA 2006-6753).
+//
+// The fixture has the set's TLEs for four real objects written out as OMM:
+// Vanguard 1, in an eccentric orbit, a low, draggy Delta 1 fragment,
+// CBERS 2 in a sun-synchronous orbit and a GPS satellite. The references are
+// rotated into the Earth-fixed frame to compare, which selftest checks
+// against Vallado's worked examples separately.
+
+use chrono::Duration;
+
+use whatsoverhead::coords;
+use whatsoverhead::data::{self, Satellite};
+
+const POSITION_TOLERANCE_KM: f64 = 1.0;
+const VELOCITY_TOLERANCE_KMS: f64 = 0.0001;
+
+struct Reference {
+    norad_id: u64,
+    minutes: f64,
+    // TEME, km and km/s.
+    position: [f64; 3],
+    velocity: [f64; 3],
+}
+
+const REFERENCES: &[Reference] = &[
+    Reference {
+        norad_id: 5,
+        minutes: 0.0,
+        position: [7022.46529266, -1400.08296755, 0.03995155],
+        velocity: [1.893841015, 6.405893759, 4.53480725],
+    },
+    Reference {
+        norad_id: 5,
+        minutes: 2160.0,
+        position: [190.19796988, 7746.96653614, 5110.00675412],
+        velocity: [-6.112325142, 1.527008184, -0.139152358],
+    },
+    Reference {
+        norad_id: 5,
+        minutes: 4320.0,
+        position: [-9060.47373569, 4658.70952502, 813.68673153],
+        velocity: [-2.232832783, -4.11045349, -3.157345433],
+    },
+    Reference {
+        norad_id: 6251,
+        minutes: 0.0,
+        position: [3988.31022699, 5498.96657235, 0.90055879],
+        velocity: [-3.290032738, 2.35765282, 6.496623475],
+    },
+    Reference {
+        norad_id: 6251,
+        minutes: 1440.0,
+        position: [-2777.14682335, -5663.16031708, -2462.54889123],
+        velocity: [4.915493146, 0.123328992, -5.896495091],
+    },
+    Reference {
+        norad_id: 6251,
+        minutes: 2880.0,
+        position: [1159.27802897, 5056.60175495, 4353.49418579],
+        velocity: [-5.968060341, -2.314790406, 4.230722669],
+    },
+    Reference {
+        norad_id: 28057,
+        minutes: 0.0,
+        position: [-2715.28237486, -6619.26436889, -0.01341443],
+        velocity: [-1.008587273, 0.422782003, 7.385272942],
+    },
+    Reference {
+        norad_id: 28057,
+        minutes: 1440.0,
+        position: [688.16056594, 4124.87618964, 5794.55994449],
+        velocity: [2.810973665, 5.479585563, -4.224866316],
+    },
+    Reference {
+        norad_id: 28057,
+        minutes: 2880.0,
+        position: [1788.4233458, 1990.50530957, -6640.59337725],
+        velocity: [-2.074169091, -6.683381288, -2.562777776],
+    },
+    Reference {
+        norad_id: 28129,
+        minutes: 0.0,
+        position: [21707.46412351, -15318.6175239, 0.13551152],
+        velocity: [1.304029214, 1.816904974, 3.161919976],
+    },
+    Reference {
+        norad_id: 28129,
+        minutes: 720.0,
+        position: [21858.23838148, -15101.51661554, 387.34517048],
+        velocity: [1.247973967, 1.856017403, 3.161439948],
+    },
+    Reference {
+        norad_id: 28129,
+        minutes: 1440.0,
+        position: [22002.20074562, -14879.72595593, 774.32827099],
+        velocity: [1.191573619, 1.894561165, 3.159953047],
+    },
+];
+
+fn satellites() -> Vec<Satellite> {
+    let path = format!(
+        "{}/tests/fixtures/reference.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let catalog = data::load_catalog(path).unwrap();
+    assert!(catalog.rejected.is_empty());
+    catalog.satellites
+}
+
+fn distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let d = coords::sub(a, b);
+    (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+}
+
+// An Earth-fixed position (km) and velocity (km/s).
+type State = ([f64; 3], [f64; 3]);
+
+// Where the satellite is at the reference's time, and where the reference
+// says it should be, both Earth-fixed.
+fn compare(sat: &Satellite, reference: &Reference) -> (State, State) {
+    let t = sat.epoch + Duration::nanoseconds((reference.minutes * 60e9).round() as i64);
+    let theta = coords::gmst(t, 0.0);
+    let expected = (
+        coords::teme_to_ecef(reference.position, theta),
+        coords::teme_to_ecef_velocity(reference.position, reference.velocity, theta),
+    );
+    (sat.ecef_at(t).unwrap(), expected)
+}
+
+#[test]
+fn fixture_has_the_reference_objects() {
+    let mut ids = satellites().iter().map(|s| s.norad_id).collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, vec![5, 6251, 28057, 28129]);
+}
+
+#[test]
+fn positions_and_velocities_match_the_references() {
+    let satellites = satellites();
+    for reference in REFERENCES {
+        let sat = satellites
+            .iter()
+            .find(|s| s.norad_id == reference.norad_id)
+            .unwrap();
+        let ((position, velocity), (expected_position, expected_velocity)) =
+            compare(sat, reference);
+        let label = format!("{} after {} minutes", sat.name, reference.minutes);
+        let error = distance(position, expected_position);
+        assert!(
+            error <= POSITION_TOLERANCE_KM,
+            "{}: {} km off",
+            label,
+            error
+        );
+        let error = distance(velocity, expected_velocity);
+        assert!(
+            error <= VELOCITY_TOLERANCE_KMS,
+            "{}: {} m/s off",
+            label,
+            error * 1000.0
+        );
+    }
+}
+
+// A kilometer in ECEF is about a hundredth of a degree on the ground, and a
+// kilometer in altitude.
+#[test]
+fn geodetic_positions_match_the_references() {
+    let satellites = satellites();
+    for reference in REFERENCES {
+        let sat = satellites
+            .iter()
+            .find(|s| s.norad_id == reference.norad_id)
+            .unwrap();
+        let ((position, _), (expected, _)) = compare(sat, reference);
+        let actual = coords::ecef_to_geodetic(position);
+        let expected = coords::ecef_to_geodetic(expected);
+        let label = format!("{} after {} minutes", sat.name, reference.minutes);
+        assert!((actual.lat - expected.lat).abs() < 0.01, "{}", label);
+        let dlon = (actual.lon - expected.lon + 540.0).rem_euclid(360.0) - 180.0;
+        assert!(dlon.abs() < 0.01, "{}", label);
+        assert!(
+            (actual.alt_km - expected.alt_km).abs() < POSITION_TOLERANCE_KM,
+            "{}",
+            label
+        );
+    }
+}
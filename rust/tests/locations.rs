@@ -0,0 +1,58 @@
+use whatsoverhead::locations::{self, Place};
+
+#[test]
+fn finds_cities_ignoring_case() {
+    let la = locations::find("los angeles").unwrap();
+    assert_eq!(la.name, "Los Angeles");
+    assert_eq!(la.country, "US");
+    assert!((la.lat - 34.05).abs() < 0.1, "{}", la.lat);
+    assert!((la.lon + 118.24).abs() < 0.1, "{}", la.lon);
+    assert_eq!(locations::find("  LOS ANGELES ").unwrap(), la);
+}
+
+#[test]
+fn ambiguous_names_need_a_qualifier() {
+    let err = locations::find("Hamilton").unwrap_err().to_string();
+    assert!(err.contains("\"Hamilton, CA\""), "{}", err);
+    assert!(err.contains("\"Hamilton, NZ\""), "{}", err);
+    assert_eq!(locations::find("Hamilton, nz").unwrap().region, "Waikato");
+    assert_eq!(locations::find("Hamilton, Ontario").unwrap().country, "CA");
+}
+
+#[test]
+fn unknown_names_are_an_error() {
+    assert!(locations::find("Atlantis").is_err());
+    assert!(locations::find("Los Angeles, FR").is_err());
+}
+
+#[test]
+fn every_city_can_be_found_by_name_and_country() {
+    let all = locations::all();
+    assert!(all.len() > 500);
+    for city in all {
+        let query = format!("{}, {}", city.name, city.country);
+        assert_eq!(locations::find(&query).unwrap(), city);
+        assert!(
+            city.lat.abs() <= 90.0 && city.lon.abs() <= 180.0,
+            "{}",
+            city
+        );
+    }
+}
+
+#[test]
+fn places_are_coordinates_or_cities() {
+    let place = Place::parse("35.4267, -116.89").unwrap();
+    assert_eq!(
+        (place.lat, place.lon, place.alt_m),
+        (35.4267, -116.89, None)
+    );
+    let place = Place::parse("Goldstone: 35.4267, -116.89, 1000").unwrap();
+    assert_eq!(place.name, "Goldstone");
+    assert_eq!(place.alt_m, Some(1000.0));
+    let place = Place::parse("Hamilton, NZ").unwrap();
+    assert_eq!(place.name, "Hamilton, Waikato, NZ");
+    assert_eq!(Place::parse("Home: Tokyo").unwrap().name, "Home");
+    assert!(Place::parse("35.4, -116.9, 1000, 5").is_err());
+    assert!(Place::parse("Atlantis").is_err());
+}
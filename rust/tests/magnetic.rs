@@ -0,0 +1,183 @@
+// The model evaluation checked against fields worked out by hand for
+// simple models. The real WMM coefficients aren't bundled, so these use a
+// tilted dipole and single higher-degree terms, whose fields have closed
+// forms.
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use whatsoverhead::coords::{WGS84_A, WGS84_F};
+use whatsoverhead::magnetic::{
+    self, decimal_year, magnetic_azimuth, parse_cof, MagneticModel, REFERENCE_RADIUS_KM,
+};
+
+fn dipole() -> MagneticModel {
+    magnetic::load(format!(
+        "{}/tests/fixtures/dipole.cof",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .unwrap()
+}
+
+fn at_epoch() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+}
+
+// A model with a single coefficient.
+fn single(n: usize, m: usize, g: f64) -> MagneticModel {
+    parse_cof(&format!(
+        "2025.0 TEST\n{} {} {} 0.0 0.0 0.0\n9999999999\n",
+        n, m, g
+    ))
+    .unwrap()
+}
+
+fn assert_close(what: &str, actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "{}: expected {} ± {}, got {}",
+        what,
+        expected,
+        tolerance,
+        actual
+    );
+}
+
+#[test]
+fn reads_the_header() {
+    let model = dipole();
+    assert_eq!(model.name, "DIPOLE-2025");
+    assert_eq!(model.epoch, 2025.0);
+    assert_eq!(model.valid_until(), 2030.0);
+}
+
+#[test]
+fn malformed_models_are_errors() {
+    assert!(parse_cof("").is_err());
+    assert!(parse_cof("2025.0 WMM\n").is_err());
+    assert!(parse_cof("WMM 2025.0\n1 0 1 0 0 0\n").is_err());
+    assert!(parse_cof("2025.0 WMM\n1 0 -30000.0 0.0 0.0\n").is_err());
+    assert!(parse_cof("2025.0 WMM\n1 2 1 0 0 0\n").is_err());
+}
+
+// With h11 only, the dipole is tipped toward 90 degrees west, so at the
+// equator on the prime meridian the field is deflected west by
+// atan(h11 / g10), and at 90 degrees east it isn't deflected at all.
+#[test]
+fn tilted_dipole_declination() {
+    let model = dipole();
+    assert_close(
+        "declination at 0, 0",
+        model.declination(0.0, 0.0, 0.0, at_epoch()).unwrap(),
+        -(3000.0f64 / 30000.0).atan().to_degrees(),
+        1e-9,
+    );
+    assert_close(
+        "declination at 0, 90",
+        model.declination(0.0, 90.0, 0.0, at_epoch()).unwrap(),
+        0.0,
+        1e-9,
+    );
+    // h11 grows by 500 nT a year.
+    let later = Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap();
+    assert_close(
+        "declination at 0, 0 two years on",
+        model.declination(0.0, 0.0, 0.0, later).unwrap(),
+        -(4000.0f64 / 30000.0).atan().to_degrees(),
+        1e-4,
+    );
+}
+
+// An axial dipole's field at the equator is horizontal and points north,
+// and at the pole it points straight down, twice as strong at the same
+// radius, both falling off with the cube of the distance.
+#[test]
+fn axial_dipole_field() {
+    let model = single(1, 0, -30000.0);
+    let equator = model.field(0.0, 45.0, 0.0, at_epoch()).unwrap();
+    let k = (REFERENCE_RADIUS_KM / WGS84_A).powi(3);
+    assert_close("north at the equator", equator.north, 30000.0 * k, 1e-6);
+    assert_close("east at the equator", equator.east, 0.0, 1e-6);
+    assert_close("down at the equator", equator.down, 0.0, 1e-6);
+    assert_close(
+        "inclination at the equator",
+        equator.inclination(),
+        0.0,
+        1e-9,
+    );
+
+    let polar_radius = WGS84_A * (1.0 - WGS84_F);
+    let pole = model.field(90.0, 0.0, 0.0, at_epoch()).unwrap();
+    let k = (REFERENCE_RADIUS_KM / polar_radius).powi(3);
+    assert_close("down at the pole", pole.down, 60000.0 * k, 1e-6);
+    assert_close("inclination at the pole", pole.inclination(), 90.0, 1e-6);
+
+    // Off the axis, the declination is zero everywhere, and points on the
+    // same parallel see the same field.
+    for lon in [-170.0, -45.0, 0.0, 120.0] {
+        let f = model.field(40.0, lon, 1.0, at_epoch()).unwrap();
+        assert_close("declination", f.declination(), 0.0, 1e-9);
+        assert_eq!(
+            f.north.to_bits(),
+            model
+                .field(40.0, -170.0, 1.0, at_epoch())
+                .unwrap()
+                .north
+                .to_bits()
+        );
+    }
+}
+
+// Degree 2: g20 gives a field down of -3 g20 at the pole and 1.5 g20 at the
+// equator, and g21 a field north of -sqrt(3) g21 at the equator on the
+// prime meridian, each times (a / r)^4.
+#[test]
+fn quadrupole_field() {
+    let k = (REFERENCE_RADIUS_KM / WGS84_A).powi(4);
+    let g20 = single(2, 0, 1000.0);
+    let f = g20.field(0.0, 0.0, 0.0, at_epoch()).unwrap();
+    assert_close("g20 down at the equator", f.down, 1500.0 * k, 1e-6);
+    assert_close("g20 north at the equator", f.north, 0.0, 1e-6);
+    let polar_radius = WGS84_A * (1.0 - WGS84_F);
+    let f = g20.field(90.0, 0.0, 0.0, at_epoch()).unwrap();
+    let k_pole = (REFERENCE_RADIUS_KM / polar_radius).powi(4);
+    assert_close("g20 down at the pole", f.down, -3000.0 * k_pole, 1e-6);
+
+    let g21 = single(2, 1, 1000.0);
+    let f = g21.field(0.0, 0.0, 0.0, at_epoch()).unwrap();
+    assert_close(
+        "g21 north at the equator",
+        f.north,
+        -(3.0f64).sqrt() * 1000.0 * k,
+        1e-6,
+    );
+    assert_close("g21 down at the equator", f.down, 0.0, 1e-6);
+}
+
+#[test]
+fn the_model_only_covers_its_five_years() {
+    let model = dipole();
+    let before = Utc.with_ymd_and_hms(2024, 12, 31, 23, 59, 59).unwrap();
+    let after = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap();
+    assert!(model.declination(0.0, 0.0, 0.0, before).is_none());
+    assert!(model.declination(0.0, 0.0, 0.0, after).is_none());
+    assert!(model
+        .declination(0.0, 0.0, 0.0, after - chrono::Duration::seconds(1))
+        .is_some());
+}
+
+#[test]
+fn decimal_years() {
+    assert_eq!(decimal_year(at_epoch()), 2025.0);
+    let mid = Utc.with_ymd_and_hms(2025, 7, 2, 12, 0, 0).unwrap();
+    assert_close("mid 2025", decimal_year(mid), 2025.5, 1e-9);
+    // 2028 is a leap year, so it's halfway through at the start of July 2.
+    let mid = Utc.with_ymd_and_hms(2028, 7, 2, 0, 0, 0).unwrap();
+    assert_close("mid 2028", decimal_year(mid), 2028.5, 1e-9);
+}
+
+#[test]
+fn magnetic_azimuths_wrap() {
+    assert_eq!(magnetic_azimuth(100.0, 10.0), 90.0);
+    assert_eq!(magnetic_azimuth(5.0, 10.0), 355.0);
+    assert_eq!(magnetic_azimuth(355.0, -10.0), 5.0);
+}
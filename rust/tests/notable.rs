@@ -0,0 +1,42 @@
+use std::collections::BTreeMap;
+
+use whatsoverhead::notable::{Aliases, BUILT_IN};
+
+#[test]
+fn aliases_ignore_case_and_surrounding_space() {
+    let aliases = Aliases::built_in();
+    assert_eq!(aliases.resolve("iss"), Some(25544));
+    assert_eq!(aliases.resolve(" ISS "), Some(25544));
+    assert_eq!(aliases.resolve("Tiangong"), Some(48274));
+    assert_eq!(aliases.resolve("CSS"), Some(48274));
+    assert_eq!(aliases.resolve("hubble"), Some(20580));
+    // Aliases match whole queries, not parts of them.
+    assert_eq!(aliases.resolve("is"), None);
+    assert_eq!(aliases.resolve("iss deb"), None);
+}
+
+#[test]
+fn built_in_aliases_are_lower_case() {
+    for (alias, _) in BUILT_IN {
+        assert_eq!(*alias, alias.to_lowercase());
+    }
+}
+
+#[test]
+fn extending_adds_and_replaces_aliases() {
+    let mut aliases = Aliases::built_in();
+    let extra = BTreeMap::from([("Envisat".to_string(), 1), ("Goes 18".to_string(), 51850)]);
+    aliases.extend(&extra);
+    assert_eq!(aliases.resolve("envisat"), Some(1));
+    assert_eq!(aliases.resolve("goes 18"), Some(51850));
+    assert_eq!(aliases.resolve("iss"), Some(25544));
+    assert!(aliases.ids().contains(&51850));
+    assert!(!aliases.ids().contains(&27386));
+}
+
+#[test]
+fn ids_has_each_object_once() {
+    let ids = Aliases::built_in().ids();
+    assert_eq!(ids.len(), 4);
+    assert!(ids.contains(&25544));
+}
@@ -0,0 +1,54 @@
+use whatsoverhead::data;
+use whatsoverhead::orbit::OrbitClass;
+
+#[test]
+fn classifies_familiar_orbits() {
+    for (what, mean_motion, eccentricity, class) in [
+        ("ISS", 15.5, 0.0005, OrbitClass::Leo),
+        ("Starlink", 15.06, 0.0001, OrbitClass::Leo),
+        ("Iridium", 14.34, 0.0002, OrbitClass::Leo),
+        ("GPS", 2.005, 0.01, OrbitClass::Meo),
+        ("O3b", 5.0, 0.0003, OrbitClass::Meo),
+        ("GOES", 1.0027, 0.0001, OrbitClass::Geo),
+        ("drifting geostationary", 0.995, 0.005, OrbitClass::Geo),
+        ("Molniya", 2.006, 0.72, OrbitClass::Heo),
+        ("geostationary transfer", 2.25, 0.73, OrbitClass::Heo),
+        ("above geostationary", 0.9, 0.001, OrbitClass::Heo),
+        ("eccentric geosynchronous", 1.0027, 0.05, OrbitClass::Meo),
+    ] {
+        assert_eq!(
+            OrbitClass::classify(mean_motion, eccentricity),
+            class,
+            "{}",
+            what
+        );
+    }
+}
+
+#[test]
+fn catalog_satellites_know_their_class() {
+    let path = format!("{}/tests/fixtures/geo.json", env!("CARGO_MANIFEST_DIR"));
+    let catalog = data::load_catalog(path).unwrap();
+    assert_eq!(catalog.satellites[0].orbit_class(), OrbitClass::Geo);
+    let catalog = data::bundled_catalog();
+    let iss = catalog
+        .satellites
+        .iter()
+        .find(|s| s.norad_id == 25544)
+        .unwrap();
+    assert_eq!(iss.orbit_class(), OrbitClass::Leo);
+}
+
+#[test]
+fn parses_and_prints_class_names() {
+    for class in [
+        OrbitClass::Leo,
+        OrbitClass::Meo,
+        OrbitClass::Geo,
+        OrbitClass::Heo,
+    ] {
+        assert_eq!(class.to_string().parse::<OrbitClass>(), Ok(class));
+    }
+    assert_eq!("GEO".parse::<OrbitClass>(), Ok(OrbitClass::Geo));
+    assert!("polar".parse::<OrbitClass>().is_err());
+}
@@ -0,0 +1,202 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chrono::{DateTime, Duration, Utc};
+
+use whatsoverhead::coords::{look_angles, observer_ecef, Observer};
+use whatsoverhead::data::{self, Satellite};
+use whatsoverhead::passes::{
+    approach_step, closest_approach, contact_windows, find_passes, predict_all_passes,
+    predict_all_passes_with_progress, PassSearchConfig,
+};
+
+fn satellites(fixture: &str) -> Vec<Satellite> {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+    data::load_catalog(path).unwrap().satellites
+}
+
+fn at(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+}
+
+fn observer(lat: f64, lon: f64) -> Observer {
+    Observer {
+        lat,
+        lon,
+        alt_m: 0.0,
+        atmosphere: None,
+    }
+}
+
+fn elevation(sat: &Satellite, observer: &Observer, t: DateTime<Utc>) -> f64 {
+    look_angles(observer, sat.ecef_at(t).unwrap().0).elevation
+}
+
+#[test]
+fn rise_and_set_are_where_the_satellite_crosses_the_horizon() {
+    let sats = satellites("decay.json");
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let observer = observer(34.56, -118.76);
+    let config = PassSearchConfig {
+        min_elevation_deg: 10.0,
+        ..Default::default()
+    };
+    let passes = find_passes(iss, &observer, at("2026-03-01T00:00:00Z"), &config);
+    // The ISS comes over a mid-latitude observer a handful of times a day.
+    assert!((2..=8).contains(&passes.len()), "{:?}", passes);
+    for p in &passes {
+        assert!(p.rise < p.culmination && p.culmination < p.set, "{:?}", p);
+        assert!(p.set - p.rise < Duration::minutes(15), "{:?}", p);
+        assert!(p.max_elevation >= 10.0);
+        assert!(elevation(iss, &observer, p.rise) >= 10.0);
+        assert!(elevation(iss, &observer, p.rise - Duration::seconds(1)) < 10.0);
+        assert!(elevation(iss, &observer, p.set) < 10.0);
+        assert!(elevation(iss, &observer, p.set - Duration::seconds(1)) >= 10.0);
+        for offset in [-10, 10] {
+            let near = p.culmination + Duration::seconds(offset);
+            assert!(elevation(iss, &observer, near) < p.max_elevation);
+        }
+    }
+}
+
+#[test]
+fn all_passes_are_one_timeline() {
+    let sats = satellites("decay.json");
+    let observer = observer(34.56, -118.76);
+    let start = at("2026-02-28T12:00:00Z");
+    let config = PassSearchConfig::default();
+    let all = predict_all_passes(&sats, &observer, start, &config);
+    assert!(all.windows(2).all(|w| w[0].rise <= w[1].rise));
+    let mut separately = sats
+        .iter()
+        .flat_map(|sat| find_passes(sat, &observer, start, &config))
+        .collect::<Vec<_>>();
+    separately.sort_by(|a, b| a.rise.cmp(&b.rise).then(a.norad_id.cmp(&b.norad_id)));
+    assert_eq!(all, separately);
+    let ids = all.iter().map(|p| p.norad_id).collect::<Vec<_>>();
+    assert!(ids.contains(&25544) && ids.contains(&66123), "{:?}", ids);
+}
+
+#[test]
+fn geostationary_satellite_is_up_for_the_whole_window() {
+    let sats = satellites("geo.json");
+    let start = at("2026-10-14T00:00:00Z");
+    let config = PassSearchConfig {
+        window_hours: 6.0,
+        ..Default::default()
+    };
+    let passes = find_passes(&sats[0], &observer(0.0, 67.0), start, &config);
+    assert_eq!(passes.len(), 1);
+    assert_eq!(passes[0].rise, start);
+    assert_eq!(passes[0].set, start + Duration::hours(6));
+    assert!(
+        passes[0].max_elevation > 80.0,
+        "{}",
+        passes[0].max_elevation
+    );
+    // And it never comes up on the other side of the world.
+    assert!(find_passes(&sats[0], &observer(0.0, -113.0), start, &config).is_empty());
+}
+
+#[test]
+fn contact_windows_for_each_ground_station() {
+    let sats = satellites("decay.json");
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let start = at("2026-03-01T00:00:00Z");
+    let config = PassSearchConfig::default();
+    let stations = [
+        ("Los Angeles".to_string(), observer(34.05, -118.24)),
+        ("San Diego".to_string(), observer(32.72, -117.16)),
+        ("Sydney".to_string(), observer(-33.87, 151.21)),
+    ];
+    let windows = contact_windows(iss, &stations, start, &config);
+    assert_eq!(windows.len(), 3);
+    for (name, observer) in &stations {
+        assert_eq!(windows[name], find_passes(iss, observer, start, &config));
+    }
+    // Stations 200 km apart see the same passes at nearly the same times,
+    // and ones on opposite sides of the Pacific never see it together.
+    let overlapping = |a: &str, b: &str| {
+        windows[a]
+            .iter()
+            .any(|p| windows[b].iter().any(|q| p.overlaps(q)))
+    };
+    assert!(overlapping("Los Angeles", "San Diego"));
+    assert!(!overlapping("Los Angeles", "Sydney"));
+}
+
+#[test]
+fn progress_is_reported_once_per_satellite() {
+    let sats = satellites("decay.json");
+    let observer = observer(34.56, -118.76);
+    let start = at("2026-02-28T12:00:00Z");
+    let config = PassSearchConfig::default();
+    let searched = AtomicUsize::new(0);
+    let passes = predict_all_passes_with_progress(&sats, &observer, start, &config, || {
+        searched.fetch_add(1, Ordering::Relaxed);
+    });
+    assert_eq!(searched.into_inner(), sats.len());
+    assert_eq!(passes, predict_all_passes(&sats, &observer, start, &config));
+}
+
+#[test]
+fn closest_approach_matches_a_fine_scan() {
+    let sats = satellites("decay.json");
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let observer = observer(34.56, -118.76);
+    let start = at("2026-03-01T00:00:00Z");
+    let end = start + Duration::hours(6);
+    let step = approach_step(iss);
+    assert!(step > Duration::seconds(20) && step < Duration::seconds(40));
+    let (t, range_km) =
+        closest_approach(observer_ecef(&observer), iss, start, end, step, 0.0).unwrap();
+    let range = |t| look_angles(&observer, iss.ecef_at(t).unwrap().0).range_km;
+    let mut scanned = (start, range(start));
+    for s in 1..=6 * 3600 {
+        let u = start + Duration::seconds(s);
+        if range(u) < scanned.1 {
+            scanned = (u, range(u));
+        }
+    }
+    assert!(
+        (t - scanned.0).num_milliseconds().abs() <= 1000,
+        "{} {}",
+        t,
+        scanned.0
+    );
+    assert!(range_km <= scanned.1 + 1e-6, "{} {}", range_km, scanned.1);
+    assert!((range_km - range(t)).abs() < 1e-9);
+    // It's the bottom of a dip, not the edge of the window.
+    assert!(t > start && t < end);
+    assert!(range(t - Duration::seconds(5)) > range_km);
+    assert!(range(t + Duration::seconds(5)) > range_km);
+}
+
+#[test]
+fn closest_approach_can_be_at_the_end_of_the_window() {
+    let sats = satellites("decay.json");
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let observer = observer(34.56, -118.76);
+    let start = at("2026-03-01T00:00:00Z");
+    // The ISS is still getting closer an hour in.
+    let end = start + Duration::hours(1);
+    let (t, _) = closest_approach(
+        observer_ecef(&observer),
+        iss,
+        start,
+        end,
+        Duration::seconds(60),
+        0.0,
+    )
+    .unwrap();
+    assert!(end - t < Duration::seconds(1), "{}", t);
+    // A step that doesn't move on is an error rather than a panic.
+    assert!(closest_approach(
+        observer_ecef(&observer),
+        iss,
+        start,
+        end,
+        Duration::zero(),
+        0.0
+    )
+    .is_err());
+}
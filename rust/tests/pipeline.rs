@@ -0,0 +1,91 @@
+// The whole chain, from OMM JSON to distances from an observer, checked
+// against values worked out without this crate or the sgp4 crate.
+
+use chrono::{DateTime, Utc};
+
+use whatsoverhead::coords::{ecef_to_geodetic, Observer};
+use whatsoverhead::data;
+use whatsoverhead::query;
+
+// Ten minutes after the ISS elements' epoch.
+const TIME: &str = "2026-02-28T12:10:00Z";
+
+// Where the ISS is at TIME from a two-body propagation of its elements,
+// with the secular drift J2 causes in the node, argument of perigee and
+// mean anomaly, then IAU 1982 GMST and WGS84. SGP4 adds short-period terms
+// that move the ISS by a few km, which the tolerances allow for.
+const SUBPOINT_LAT: f64 = 36.0687;
+const SUBPOINT_LON: f64 = -84.6431;
+const ALT_KM: f64 = 424.81;
+// The same, seen from Oak Ridge, at sea level: slant range, and great
+// circle distance to the subpoint on a 6371 km sphere.
+const OBSERVER_LAT: f64 = 35.93;
+const OBSERVER_LON: f64 = -84.31;
+const RANGE_KM: f64 = 426.24;
+const GROUND_KM: f64 = 33.70;
+
+fn assert_close(what: &str, actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "{}: expected {} ± {}, got {}",
+        what,
+        expected,
+        tolerance,
+        actual
+    );
+}
+
+#[test]
+fn iss_lands_where_it_should() {
+    let path = format!(
+        "{}/tests/fixtures/pipeline.json",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    let catalog = data::load_catalog(path).unwrap();
+    assert!(catalog.rejected.is_empty(), "{:?}", catalog.rejected);
+    assert_eq!(catalog.satellites.len(), 4);
+
+    let t = DateTime::parse_from_rfc3339(TIME)
+        .unwrap()
+        .with_timezone(&Utc);
+    let propagated = query::propagate(&catalog.satellites, t);
+    assert_eq!(propagated.len(), 4);
+    let iss = propagated
+        .iter()
+        .find(|p| p.satellite.norad_id == 25544)
+        .unwrap();
+    let geo = ecef_to_geodetic(iss.position);
+    assert_close("latitude", geo.lat, SUBPOINT_LAT, 0.2);
+    assert_close("longitude", geo.lon, SUBPOINT_LON, 0.2);
+    assert_close("altitude", geo.alt_km, ALT_KM, 10.0);
+
+    let observer = Observer {
+        lat: OBSERVER_LAT,
+        lon: OBSERVER_LON,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let report = query::report_propagated(iss, &observer);
+    assert_close("range", report.range_km, RANGE_KM, 10.0);
+    assert_close("ground distance", report.ground_km, GROUND_KM, 10.0);
+    // It's nearly straight up.
+    assert!(report.elevation > 80.0, "{}", report.elevation);
+
+    // The other orbits come out where their regimes put them.
+    for p in &propagated {
+        let alt_km = ecef_to_geodetic(p.position).alt_km;
+        let expected = match p.satellite.norad_id {
+            25544 => 400.0..450.0,
+            44714 => 500.0..600.0,
+            51850 => 35_700.0..35_900.0,
+            37753 => 19_800.0..20_500.0,
+            id => panic!("unexpected satellite {}", id),
+        };
+        assert!(
+            expected.contains(&alt_km),
+            "{}: {}",
+            p.satellite.name,
+            alt_km
+        );
+    }
+}
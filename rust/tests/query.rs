@@ -0,0 +1,641 @@
+use chrono::{DateTime, Utc};
+
+use whatsoverhead::coords::{Atmosphere, Observer};
+use whatsoverhead::data::{self, Satellite};
+use whatsoverhead::geo::{Area, BoundingBox};
+use whatsoverhead::orbit::OrbitClass;
+use whatsoverhead::query;
+use whatsoverhead::sun::{Twilight, Visibility};
+
+fn satellites() -> Vec<Satellite> {
+    let path = format!("{}/tests/fixtures/decay.json", env!("CARGO_MANIFEST_DIR"));
+    data::load_catalog(path).unwrap().satellites
+}
+
+fn at(s: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+}
+
+// An observer a few hundred km south of the ISS's subpoint at `t`, so it's
+// well up in their sky, and where they'd see it.
+fn observer_under_iss(sats: &[Satellite], t: DateTime<Utc>) -> (Observer, f64, f64) {
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let anywhere = Observer {
+        lat: 0.0,
+        lon: 0.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let sub = query::report(iss, &anywhere, t).unwrap();
+    let observer = Observer {
+        lat: sub.lat - 5.0,
+        lon: sub.lon,
+        alt_m: 0.0,
+        atmosphere: Some(Atmosphere::STANDARD),
+    };
+    let seen = query::report(iss, &observer, t).unwrap();
+    assert!(seen.elevation > 20.0, "{}", seen.elevation);
+    (observer, seen.azimuth, seen.elevation)
+}
+
+#[test]
+fn identifies_a_satellite_from_where_it_was_seen() {
+    let sats = satellites();
+    let t = at("2026-02-28T12:00:00Z");
+    let (observer, az, el) = observer_under_iss(&sats, t);
+    // A slightly sloppy observation.
+    let id = query::identify(&sats, &observer, t, az + 0.5, el - 0.5, 2.0);
+    assert!(!id.widened);
+    assert_eq!(id.tolerance_deg, 2.0);
+    assert_eq!(id.candidates[0].report.norad_id, 25544);
+    assert!(id.candidates[0].separation_deg < 1.0);
+    for pair in id.candidates.windows(2) {
+        assert!(pair[0].separation_deg <= pair[1].separation_deg);
+    }
+}
+
+#[test]
+fn widens_the_search_once_when_nothing_matches() {
+    let sats = satellites();
+    let t = at("2026-02-28T12:00:00Z");
+    let (observer, az, el) = observer_under_iss(&sats, t);
+    // 4 degrees off: outside 2, inside the widened 6.
+    let id = query::identify(&sats, &observer, t, az, el + 4.0, 2.0);
+    assert!(id.widened);
+    assert_eq!(id.tolerance_deg, 2.0 * query::WIDENED_TOLERANCE_FACTOR);
+    assert_eq!(id.candidates[0].report.norad_id, 25544);
+
+    // Too far off even for the widened search.
+    let id = query::identify(&sats, &observer, t, az, el + 15.0, 2.0);
+    assert!(id.widened);
+    assert!(id.candidates.iter().all(|c| c.report.norad_id != 25544));
+}
+
+#[test]
+fn one_propagation_serves_every_observer() {
+    let sats = satellites();
+    let t = at("2026-03-01T00:00:00Z");
+    let propagated = query::propagate(&sats, t);
+    for (lat, lon) in [(0.0, 0.0), (34.0, -118.0), (-60.0, 150.0)] {
+        let observer = Observer {
+            lat,
+            lon,
+            alt_m: 0.0,
+            atmosphere: None,
+        };
+        let shared = query::overhead_propagated(&propagated, &observer, -90.0);
+        let direct = query::overhead(&sats, &observer, t, -90.0);
+        assert_eq!(shared.len(), direct.len());
+        for (a, b) in shared.iter().zip(&direct) {
+            assert_eq!(a.norad_id, b.norad_id);
+            assert_eq!(a.range_km, b.range_km);
+        }
+    }
+}
+
+#[test]
+fn peak_search_refuses_a_step_under_a_millisecond() {
+    let sats = satellites();
+    let t = at("2026-03-01T00:00:00Z");
+    let (observer, _, _) = observer_under_iss(&sats, t);
+    assert!(query::max_simultaneous_overhead(&sats, &observer, 10.0, t, 0.0004).is_err());
+    let (_, count, _) =
+        query::max_simultaneous_overhead(&sats, &observer, 10.0, t, 3600.0).unwrap();
+    assert!(count >= 1);
+}
+
+#[test]
+fn propagation_failures_say_why() {
+    // The rocket body's orbit has decayed too far by this time for SGP4.
+    let sats = satellites();
+    let (propagated, errors) = query::propagate_with_errors(&sats, at("2026-02-21T00:00:00Z"));
+    assert_eq!(propagated.len() + errors.len(), sats.len());
+    assert!(propagated.iter().all(|p| p.satellite.norad_id != 66123));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].norad_id, 66123);
+    assert_eq!(errors[0].kind, "eccentricity out of range");
+}
+
+#[test]
+fn identify_ignores_objects_at_impossible_altitudes() {
+    // Looking right where the underground rocket body's elements put it.
+    let sats = satellites();
+    let t = at("2026-06-01T00:00:00Z");
+    let observer = Observer {
+        lat: 0.0,
+        lon: 0.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let rocket = sats.iter().find(|s| s.norad_id == 66123).unwrap();
+    let r = query::report(rocket, &observer, t).unwrap();
+    let id = query::identify(&sats, &observer, t, r.azimuth, r.elevation, 2.0);
+    assert!(id.candidates.iter().all(|c| c.report.norad_id != 66123));
+}
+
+#[test]
+fn decayed_objects_at_impossible_altitudes_are_dropped() {
+    // By June the rocket body's elements put it thousands of km underground.
+    let sats = satellites();
+    let t = at("2026-06-01T00:00:00Z");
+    let (mut propagated, errors) = query::propagate_with_errors(&sats, t);
+    assert!(errors.is_empty());
+    assert_eq!(propagated.len(), 3);
+    let implausible = query::drop_implausible(&mut propagated);
+    assert_eq!(implausible.len(), 1);
+    assert_eq!(implausible[0].norad_id, 66123);
+    assert!(implausible[0].alt_km < 0.0, "{}", implausible[0].alt_km);
+    let ids = |p: &[query::Propagated]| p.iter().map(|p| p.satellite.norad_id).collect::<Vec<_>>();
+    assert_eq!(ids(&propagated), ids(&query::propagate(&sats, t)));
+    assert!(!ids(&propagated).contains(&66123));
+}
+
+#[test]
+fn rising_satellites_say_when_they_peak() {
+    let sats = satellites();
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let observer = Observer {
+        lat: 34.56,
+        lon: -118.76,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let passes = whatsoverhead::passes::find_passes(
+        iss,
+        &observer,
+        at("2026-03-01T00:00:00Z"),
+        &Default::default(),
+    );
+    let pass = &passes[0];
+
+    let rising = query::report(iss, &observer, pass.rise + chrono::Duration::minutes(1)).unwrap();
+    assert!(rising.rising(), "{}", rising.elevation_rate_deg_per_s);
+    let expected = (pass.culmination - rising.time).num_milliseconds() as f64 / 1000.0;
+    let culmination = rising.culmination_in_seconds.unwrap();
+    assert!(
+        (culmination - expected).abs() < 2.0,
+        "{} vs {}",
+        culmination,
+        expected
+    );
+
+    let setting = query::report(iss, &observer, pass.set - chrono::Duration::minutes(1)).unwrap();
+    assert!(setting.setting(), "{}", setting.elevation_rate_deg_per_s);
+    assert_eq!(setting.culmination_in_seconds, None);
+}
+
+#[test]
+fn geostationary_satellites_hang_still() {
+    let path = format!("{}/tests/fixtures/geo.json", env!("CARGO_MANIFEST_DIR"));
+    let sats = data::load_catalog(path).unwrap().satellites;
+    let observer = Observer {
+        lat: 0.0,
+        lon: 67.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let report = query::report(&sats[0], &observer, at("2026-10-14T00:00:00Z")).unwrap();
+    assert!(
+        report.elevation_rate_deg_per_s.abs() < 1e-4,
+        "{}",
+        report.elevation_rate_deg_per_s
+    );
+    assert!(!report.rising() && !report.setting());
+    assert_eq!(report.culmination_in_seconds, None);
+}
+
+#[test]
+fn ephemeris_velocity_is_in_the_earth_fixed_frame() {
+    let sats = satellites();
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let start = at("2026-03-01T00:00:00Z");
+    let points =
+        query::ephemeris(iss, start, start + chrono::Duration::minutes(10), 1.0, 0.0).unwrap();
+    assert_eq!(points.len(), 601);
+    assert_eq!(points[600].time, start + chrono::Duration::minutes(10));
+    // Over a second, the ECEF position moves by the ECEF velocity, which it
+    // wouldn't with the inertial velocity.
+    for w in points.windows(2).step_by(60) {
+        for i in 0..3 {
+            let moved = w[1].position[i] - w[0].position[i];
+            let mean = (w[0].velocity[i] + w[1].velocity[i]) / 2.0;
+            assert!((moved - mean).abs() < 1e-3, "{} vs {}", moved, mean);
+        }
+        assert!((-180.0..180.0).contains(&w[0].lon));
+    }
+}
+
+#[test]
+fn visibility_needs_a_dark_sky_and_a_sunlit_satellite() {
+    let path = format!("{}/tests/fixtures/geo.json", env!("CARGO_MANIFEST_DIR"));
+    let sats = data::load_catalog(path).unwrap().satellites;
+    // GOES 18 is straight up from here.
+    let observer = Observer {
+        lat: 0.0,
+        lon: 67.6,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let report = |t| query::report(&sats[0], &observer, at(t)).unwrap();
+
+    // Local noon at the December solstice.
+    let noon = report("2026-12-22T07:30:00Z");
+    assert!(noon.sun_elevation > 60.0, "{}", noon.sun_elevation);
+    assert_eq!(noon.twilight, Twilight::Day);
+    assert_eq!(noon.visibility, Visibility::Daylight);
+
+    // Local midnight then: the Sun is far enough south that geostationary
+    // orbit is out of Earth's shadow.
+    let midnight = report("2026-12-21T19:30:00Z");
+    assert!(midnight.sun_elevation < -60.0, "{}", midnight.sun_elevation);
+    assert_eq!(midnight.twilight, Twilight::Night);
+    assert!(midnight.sunlit);
+    assert_eq!(midnight.visibility, Visibility::Visible);
+
+    // Local midnight at the September equinox, when the Sun is right behind
+    // the Earth.
+    let eclipse = report("2026-09-22T19:22:00Z");
+    assert_eq!(eclipse.twilight, Twilight::Night);
+    assert!(!eclipse.sunlit);
+    assert_eq!(eclipse.visibility, Visibility::Eclipsed);
+}
+
+#[test]
+fn twilight_follows_the_sun_below_the_horizon() {
+    let cases = [
+        (10.0, Twilight::Day),
+        (-0.5, Twilight::Day),
+        (-3.0, Twilight::Civil),
+        (-9.0, Twilight::Nautical),
+        (-15.0, Twilight::Astronomical),
+        (-30.0, Twilight::Night),
+    ];
+    for (elevation, twilight) in cases {
+        assert_eq!(
+            Twilight::from_sun_elevation(elevation),
+            twilight,
+            "{elevation}"
+        );
+    }
+    // Civil twilight is still too bright, even for a sunlit satellite.
+    assert_eq!(Visibility::new(Twilight::Civil, true), Visibility::Daylight);
+    assert_eq!(
+        Visibility::new(Twilight::Nautical, true),
+        Visibility::Visible
+    );
+    assert_eq!(
+        Visibility::new(Twilight::Night, false),
+        Visibility::Eclipsed
+    );
+}
+
+#[test]
+fn area_queries_find_subpoints_across_the_antimeridian() {
+    let sats = satellites();
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    // Find a moment when the ISS is within a few degrees of 180.
+    let t = (0..180)
+        .map(|m| at("2026-03-01T00:00:00Z") + chrono::Duration::minutes(m))
+        .find(|&t| {
+            let (p, _) = iss.ecef_at(t).unwrap();
+            whatsoverhead::ecef_to_geodetic(p).lon.abs() > 176.0
+        })
+        .expect("the ISS crosses the antimeridian every orbit");
+    let propagated = query::propagate(&sats, t);
+    let observer = Observer {
+        lat: 0.0,
+        lon: 180.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let pacific = BoundingBox {
+        min_lon: 175.0,
+        min_lat: -90.0,
+        max_lon: -175.0,
+        max_lat: 90.0,
+    };
+    let found = query::in_area(&propagated, &observer, &Area::Box(pacific));
+    assert!(found.iter().any(|r| r.norad_id == 25544));
+    for r in &found {
+        assert!(r.lon.abs() >= 175.0, "{} at {}", r.name, r.lon);
+    }
+    let rest = BoundingBox {
+        min_lon: -175.0,
+        max_lon: 175.0,
+        ..pacific
+    };
+    let found = query::in_area(&propagated, &observer, &Area::Box(rest));
+    assert!(!found.iter().any(|r| r.norad_id == 25544));
+}
+
+#[test]
+fn area_queries_ignore_the_horizon_and_sort_by_distance() {
+    let sats = satellites();
+    let t = at("2026-03-01T00:00:00Z");
+    let propagated = query::propagate(&sats, t);
+    // On the other side of the Earth from both satellites.
+    let observer = Observer {
+        lat: 40.0,
+        lon: 160.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let everywhere = Area::Box(BoundingBox {
+        min_lon: -180.0,
+        min_lat: -90.0,
+        max_lon: 180.0,
+        max_lat: 90.0,
+    });
+    let found = query::in_area(&propagated, &observer, &everywhere);
+    assert_eq!(found.len(), propagated.len());
+    assert!(found.iter().all(|r| r.elevation < 0.0));
+    assert!(found.windows(2).all(|w| w[0].ground_km <= w[1].ground_km));
+}
+
+#[test]
+fn close_overhead_passes_swing_the_azimuth_fast() {
+    let sats = satellites();
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let t = at("2026-03-01T00:00:00Z");
+    let anywhere = Observer {
+        lat: 0.0,
+        lon: 0.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let sub = query::report(iss, &anywhere, t).unwrap();
+    let near = Observer {
+        lat: sub.lat + 0.3,
+        lon: sub.lon,
+        ..anywhere.clone()
+    };
+    let overhead = query::report(iss, &near, t).unwrap();
+    assert!(overhead.elevation > 80.0, "{}", overhead.elevation);
+    assert!(
+        overhead.azimuth_rate_deg_per_s.abs() > 2.0,
+        "{}",
+        overhead.azimuth_rate_deg_per_s
+    );
+    // From far away the same satellite crawls across the sky.
+    let far = Observer {
+        lat: sub.lat + 15.0,
+        lon: sub.lon,
+        ..anywhere
+    };
+    let low = query::report(iss, &far, t).unwrap();
+    assert!(
+        low.azimuth_rate_deg_per_s.abs() < 0.5,
+        "{}",
+        low.azimuth_rate_deg_per_s
+    );
+}
+
+#[test]
+fn trail_is_centered_on_the_report() {
+    let sats = satellites();
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let t = at("2026-03-01T00:00:00Z");
+    let (observer, _, _) = observer_under_iss(&sats, t);
+    let report = query::report(iss, &observer, t).unwrap();
+    let trail = query::trail(iss, &observer, t, 10.0, 30.0, 0.0);
+    assert_eq!(trail.len(), 41);
+    assert!(trail.windows(2).all(|w| w[0].time < w[1].time));
+    assert_eq!(trail[0].offset_seconds, -600.0);
+    assert_eq!(trail[40].offset_seconds, 600.0);
+
+    let center = &trail[20];
+    assert_eq!(center.time, report.time);
+    assert_eq!(center.offset_seconds, 0.0);
+    assert_eq!(center.azimuth, report.azimuth);
+    assert_eq!(center.elevation, report.elevation);
+    assert_eq!(center.range_km, report.range_km);
+    assert_eq!(center.lat, report.lat);
+    assert_eq!(center.lon, report.lon);
+    assert_eq!(center.alt_km, report.alt_km);
+    assert!(center.above_horizon);
+
+    // A pass over someone lasts well under 20 minutes, so the ends of the
+    // trail are below the horizon, and marked.
+    for p in [&trail[0], &trail[40]] {
+        assert!(p.elevation < 0.0 && !p.above_horizon, "{:?}", p);
+    }
+    assert!(trail
+        .iter()
+        .all(|p| p.above_horizon == (p.elevation >= 0.0)));
+
+    // Steps that don't divide the window evenly stop short of it.
+    assert_eq!(query::trail(iss, &observer, t, 1.0, 25.0, 0.0).len(), 5);
+    assert_eq!(query::trail(iss, &observer, t, 0.0, 60.0, 0.0).len(), 1);
+}
+
+#[test]
+fn elements_report_the_catalog_values_and_the_orbit() {
+    let sats = satellites();
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let e = query::elements(iss, at("2026-03-01T00:00:00Z"));
+    assert_eq!(e.name, "ISS (ZARYA)");
+    assert_eq!(e.international_designator.as_deref(), Some("1998-067A"));
+    assert_eq!(e.epoch, at("2026-02-28T12:00:00Z"));
+    assert_eq!(e.epoch_age_hours, 12.0);
+    assert_eq!(e.inclination, 51.6461);
+    assert_eq!(e.right_ascension, 221.2784);
+    assert_eq!(e.argument_of_perigee, 89.1723);
+    assert_eq!(e.mean_anomaly, 280.4612);
+    assert_eq!(e.element_set_number, 999);
+    assert_eq!(e.revolution_number, 23600);
+    assert_eq!(e.orbit_class, OrbitClass::Leo);
+
+    // About 6800 km across, with the two ends of it a couple of km apart.
+    assert!((e.semi_major_axis_km - 6796.3).abs() < 0.1, "{:?}", e);
+    assert!((e.period_minutes - 92.93).abs() < 0.01, "{:?}", e);
+    let spread = e.semi_major_axis_km * 2.0 * e.eccentricity;
+    assert!(
+        (e.apogee_km - e.perigee_km - spread).abs() < 1e-9,
+        "{:?}",
+        e
+    );
+    assert!(e.perigee_km < e.apogee_km && e.perigee_km > 400.0);
+}
+
+#[test]
+fn subpoints_across_the_dateline_are_close_to_the_observer() {
+    // Step along the ISS's track until its subpoint is within a degree of
+    // the dateline.
+    let sats = satellites();
+    let iss = sats.iter().find(|s| s.norad_id == 25544).unwrap();
+    let anywhere = Observer {
+        lat: 0.0,
+        lon: 0.0,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let sub = (0..200)
+        .map(|i| at("2026-02-28T12:00:00Z") + chrono::Duration::minutes(i))
+        .filter_map(|t| query::report(iss, &anywhere, t))
+        .find(|r| r.lon.abs() > 179.0)
+        .unwrap();
+    assert!((-180.0..180.0).contains(&sub.lon), "{}", sub.lon);
+
+    // An observer a degree of longitude away on the other side.
+    let lon = if sub.lon > 0.0 {
+        sub.lon - 359.0
+    } else {
+        sub.lon + 359.0
+    };
+    let observer = Observer {
+        lat: sub.lat,
+        lon,
+        alt_m: 0.0,
+        atmosphere: None,
+    };
+    let seen = query::report(iss, &observer, sub.time).unwrap();
+    assert_eq!(seen.lon, sub.lon);
+    let expected = 111.19 * sub.lat.to_radians().cos();
+    assert!((seen.ground_km - expected).abs() < 0.5, "{:?}", seen);
+    assert!(seen.elevation > 70.0, "{:?}", seen);
+    let bearing = seen.ground_bearing.unwrap();
+    let toward = if sub.lon > 0.0 { 270.0 } else { 90.0 };
+    assert!((bearing - toward).abs() < 2.0, "{}", bearing);
+}
+
+fn iss_elements() -> sgp4::Elements {
+    sgp4::Elements::from_tle(
+        Some("ISS (ZARYA)".to_string()),
+        b"1 25544U 98067A   20194.88612269 -.00002218  00000-0 -31515-4 0  9992",
+        b"2 25544  51.6461 221.2784 0001413  89.1723 280.4612 15.49507896236008",
+    )
+    .unwrap()
+}
+
+#[test]
+fn propagator_answers_like_the_free_functions() {
+    let t = at("2026-03-01T00:00:00Z");
+    let sats = satellites();
+    let (observer, _, _) = observer_under_iss(&sats, t);
+    let propagator = query::Propagator::new(sats.clone());
+    let summary = |reports: Vec<query::SatelliteReport>| {
+        reports
+            .iter()
+            .map(|r| (r.norad_id, r.range_km))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        summary(propagator.overhead(&observer, t, -90.0)),
+        summary(query::overhead(&sats, &observer, t, -90.0))
+    );
+    assert_eq!(propagator.satellites().len(), sats.len());
+}
+
+#[test]
+fn nearest_is_closest_first_whatever_the_elevation() {
+    let t = at("2026-03-01T00:00:00Z");
+    let sats = satellites();
+    let (observer, _, _) = observer_under_iss(&sats, t);
+    let propagator = query::Propagator::new(sats);
+    let nearest = propagator.nearest(&observer, t, 1);
+    assert_eq!(nearest.len(), 1);
+    assert_eq!(nearest[0].norad_id, 25544);
+    // Asking for more than there are gets every one that propagates, some
+    // below the horizon.
+    let all = propagator.nearest(&observer, t, 100);
+    assert_eq!(all.len(), propagator.propagate(t).len());
+    assert!(all.windows(2).all(|w| w[0].range_km <= w[1].range_km));
+    assert!(all.iter().any(|r| r.elevation < 0.0));
+}
+
+#[test]
+fn propagators_from_elements_leave_out_unusable_ones() {
+    let mut broken = iss_elements();
+    broken.norad_id = 99999;
+    broken.eccentricity = 1.5;
+    let propagator = query::Propagator::from_elements(vec![iss_elements(), broken]);
+    let ids = propagator
+        .satellites()
+        .iter()
+        .map(|s| s.norad_id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![25544]);
+    assert_eq!(propagator.rejected().len(), 1);
+    assert_eq!(propagator.rejected()[0].norad_id, Some(99999));
+    assert!(propagator.rejected()[0]
+        .reason
+        .starts_with("SGP4 can't use these elements"));
+}
+
+#[test]
+fn overhead_queries_rank_and_limit() {
+    let t = at("2026-03-01T00:00:00Z");
+    let sats = satellites();
+    let (observer, _, _) = observer_under_iss(&sats, t);
+    let query = query::OverheadQuery::new(observer)
+        .at(t)
+        .min_elevation(-90.0);
+    let by_range = query.run(&sats);
+    assert!(by_range.len() >= 2);
+    let ids = |sightings: &[query::SatelliteReport]| {
+        sightings.iter().map(|s| s.norad_id).collect::<Vec<_>>()
+    };
+    let propagator = query::Propagator::new(sats.clone());
+    assert_eq!(ids(&query.run(&propagator)), ids(&by_range));
+    assert!(by_range.windows(2).all(|w| w[0].range_km <= w[1].range_km));
+    let by_elevation = query.clone().rank_by(query::RankBy::Elevation).run(&sats);
+    assert!(by_elevation
+        .windows(2)
+        .all(|w| w[0].elevation >= w[1].elevation));
+    let by_ground = query.clone().rank_by(query::RankBy::Ground).run(&sats);
+    assert!(by_ground
+        .windows(2)
+        .all(|w| w[0].ground_km <= w[1].ground_km));
+    let top = query.clone().top(1).run(&sats);
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0].norad_id, by_range[0].norad_id);
+    // The default is everything above the horizon.
+    let up = query::OverheadQuery::new(observer_under_iss(&sats, t).0)
+        .at(t)
+        .run(&sats);
+    assert!(!up.is_empty() && up.len() < by_range.len());
+    assert!(up.iter().all(|r| r.elevation >= 0.0));
+}
+
+#[test]
+fn overhead_queries_filter_on_brightness_and_visibility() {
+    let t = at("2026-03-01T00:00:00Z");
+    let sats = satellites();
+    let (observer, _, _) = observer_under_iss(&sats, t);
+    let query = query::OverheadQuery::new(observer)
+        .at(t)
+        .min_elevation(-90.0);
+    let all = query.run(&sats);
+    let visible = query.clone().visible_only(true).run(&sats);
+    assert_eq!(
+        visible.len(),
+        all.iter()
+            .filter(|r| r.visibility == Visibility::Visible)
+            .count()
+    );
+    let bright = query.clone().max_magnitude(100.0).run(&sats);
+    assert!(bright.iter().all(|r| r.magnitude.is_some()));
+    assert_eq!(
+        bright.len(),
+        all.iter().filter(|r| r.magnitude.is_some()).count()
+    );
+    // Ranking by brightness puts the unknown ones last.
+    let ranked = query.clone().rank_by(query::RankBy::Magnitude).run(&sats);
+    let known = ranked.iter().take_while(|r| r.magnitude.is_some()).count();
+    assert!(ranked[known..].iter().all(|r| r.magnitude.is_none()));
+}
+
+#[test]
+fn rank_by_names() {
+    for rank_by in [
+        query::RankBy::SlantRange,
+        query::RankBy::Ground,
+        query::RankBy::Elevation,
+        query::RankBy::Magnitude,
+    ] {
+        assert_eq!(rank_by.to_string().parse::<query::RankBy>(), Ok(rank_by));
+    }
+    assert_eq!("Elevation".parse(), Ok(query::RankBy::Elevation));
+    assert_eq!("range".parse(), Ok(query::RankBy::SlantRange));
+    assert!("brightness".parse::<query::RankBy>().is_err());
+}
@@ -0,0 +1,64 @@
+use whatsoverhead::selftest::{self, TestCase};
+
+fn cases() -> Vec<TestCase> {
+    selftest::parse_vectors(selftest::BUNDLED_VECTORS).unwrap()
+}
+
+#[test]
+fn every_stage_passes() {
+    let stages = selftest::run();
+    let names = stages.iter().map(|s| s.stage.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "propagation",
+            "pipeline",
+            "gmst",
+            "teme_to_ecef",
+            "geodetic"
+        ]
+    );
+    for s in &stages {
+        assert!(s.passed(), "{:?}", s);
+        assert!(s.checks > 0, "{:?}", s);
+    }
+}
+
+#[test]
+fn the_bundled_vectors_are_the_verification_set() {
+    let cases = cases();
+    assert_eq!(cases.len(), 33);
+    assert_eq!(cases.iter().map(|c| c.states.len()).sum::<usize>(), 666);
+    assert_eq!(cases[0].label(), "00005");
+    for outlier in selftest::IMPROVED_MODE_OUTLIERS {
+        assert!(cases.iter().any(|c| c.label() == *outlier), "{}", outlier);
+    }
+}
+
+#[test]
+fn a_wrong_reference_fails_the_propagation_stage() {
+    let mut cases = cases();
+    cases.truncate(1);
+    cases[0].states[3].position[2] += 2.0;
+    for stage in [
+        selftest::check_propagation(&cases),
+        selftest::check_pipeline(&cases),
+    ] {
+        assert!(!stage.passed(), "{:?}", stage);
+        assert!((stage.max_error - 2.0).abs() < 0.1, "{:?}", stage);
+        assert_eq!(stage.worst, "00005 after 1080 minutes");
+    }
+}
+
+#[test]
+fn a_bad_tle_is_a_failure_not_a_skip() {
+    let mut cases = cases();
+    cases.truncate(2);
+    cases[1].line2 = "2 garbage".to_string();
+    let stage = selftest::check_propagation(&cases);
+    assert!(!stage.passed());
+    assert_eq!(stage.failures.len(), 1);
+    assert!(stage.failures[0].starts_with("04632: "), "{:?}", stage);
+    // The good case was still checked.
+    assert_eq!(stage.checks, cases[0].states.len());
+}
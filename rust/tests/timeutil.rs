@@ -0,0 +1,126 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use whatsoverhead::timeutil::{display_time, from_local, localize_json, parse_time};
+
+fn utc(s: &str) -> DateTime<Utc> {
+    s.parse().unwrap()
+}
+
+const LOS_ANGELES: Tz = chrono_tz::America::Los_Angeles;
+
+#[test]
+fn times_with_offsets_ignore_the_time_zone() {
+    let t = utc("2026-06-15T18:30:00Z");
+    assert_eq!(parse_time("2026-06-15T18:30:00Z", LOS_ANGELES).unwrap(), t);
+    assert_eq!(parse_time("2026-06-15T11:30:00-07:00", Tz::UTC).unwrap(), t);
+    assert_eq!(
+        parse_time(" 2026-06-15T20:30:00+02:00 ", LOS_ANGELES).unwrap(),
+        t
+    );
+}
+
+#[test]
+fn local_times_are_read_in_the_time_zone() {
+    let t = utc("2026-06-15T18:30:00Z");
+    for s in [
+        "2026-06-15T11:30:00",
+        "2026-06-15 11:30:00",
+        "2026-06-15T11:30",
+        "2026-06-15 11:30",
+    ] {
+        assert_eq!(parse_time(s, LOS_ANGELES).unwrap(), t, "{}", s);
+    }
+    assert_eq!(
+        parse_time("2026-06-15T11:30:00.25", LOS_ANGELES).unwrap(),
+        utc("2026-06-15T18:30:00.25Z")
+    );
+    assert_eq!(parse_time("2026-06-15T18:30", Tz::UTC).unwrap(), t);
+    // Winter is PST.
+    assert_eq!(
+        parse_time("2026-01-15T11:30", LOS_ANGELES).unwrap(),
+        utc("2026-01-15T19:30:00Z")
+    );
+
+    let err = parse_time("tomorrow", LOS_ANGELES).unwrap_err().to_string();
+    assert!(err.contains("RFC 3339"), "{}", err);
+}
+
+#[test]
+fn the_spring_forward_gap_lists_both_candidates() {
+    // Clocks in Los Angeles went from 02:00 PST to 03:00 PDT on
+    // 2026-03-08, so 02:30 never happened.
+    let err = parse_time("2026-03-08T02:30", LOS_ANGELES)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("doesn't exist"), "{}", err);
+    assert!(err.contains("America/Los_Angeles"), "{}", err);
+    assert!(
+        err.contains("-08:00") && err.contains("2026-03-08T10:30:00Z"),
+        "{}",
+        err
+    );
+    assert!(
+        err.contains("-07:00") && err.contains("2026-03-08T09:30:00Z"),
+        "{}",
+        err
+    );
+
+    // Either side of the gap is fine.
+    assert_eq!(
+        parse_time("2026-03-08T01:59", LOS_ANGELES).unwrap(),
+        utc("2026-03-08T09:59:00Z")
+    );
+    assert_eq!(
+        parse_time("2026-03-08T03:00", LOS_ANGELES).unwrap(),
+        utc("2026-03-08T10:00:00Z")
+    );
+}
+
+#[test]
+fn the_fall_back_overlap_lists_both_candidates() {
+    // Clocks in Los Angeles went back from 02:00 PDT to 01:00 PST on
+    // 2026-11-01, so 01:30 happened twice.
+    let local = NaiveDate::from_ymd_opt(2026, 11, 1)
+        .unwrap()
+        .and_hms_opt(1, 30, 0)
+        .unwrap();
+    let err = from_local(local, LOS_ANGELES).unwrap_err().to_string();
+    assert!(err.contains("happens twice"), "{}", err);
+    assert!(err.contains("2026-11-01T01:30:00-07:00"), "{}", err);
+    assert!(err.contains("2026-11-01T08:30:00Z"), "{}", err);
+    assert!(err.contains("2026-11-01T01:30:00-08:00"), "{}", err);
+    assert!(err.contains("2026-11-01T09:30:00Z"), "{}", err);
+
+    // Giving the offset picks one.
+    assert_eq!(
+        parse_time("2026-11-01T01:30:00-08:00", LOS_ANGELES).unwrap(),
+        utc("2026-11-01T09:30:00Z")
+    );
+}
+
+#[test]
+fn times_are_shown_in_the_time_zone() {
+    let t = utc("2026-11-01T09:30:00Z");
+    assert_eq!(display_time(t, LOS_ANGELES), "2026-11-01 01:30:00 PST");
+    assert_eq!(display_time(t, Tz::UTC), "2026-11-01 09:30:00 UTC");
+}
+
+#[test]
+fn json_timestamps_are_localized() {
+    let mut value = serde_json::json!({
+        "time": "2026-06-15T18:30:00.250Z",
+        "name": "ISS (ZARYA)",
+        "passes": [{"rise": "2026-06-15T18:30:00Z", "note": "Z"}],
+        "norad_id": 25544,
+    });
+    localize_json(&mut value, LOS_ANGELES);
+    assert_eq!(
+        value,
+        serde_json::json!({
+            "time": "2026-06-15T11:30:00.250-07:00",
+            "name": "ISS (ZARYA)",
+            "passes": [{"rise": "2026-06-15T11:30:00-07:00", "note": "Z"}],
+            "norad_id": 25544,
+        })
+    );
+}
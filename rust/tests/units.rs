@@ -0,0 +1,49 @@
+use whatsoverhead::units::{NumberStyle, Units};
+
+#[test]
+fn distances_round_trip_through_imperial_units() {
+    let (miles, label) = Units::Imperial.distance(390.0);
+    assert_eq!(label, "miles");
+    assert!((miles - 242.33).abs() < 0.01, "{}", miles);
+    assert!((Units::Imperial.distance_to_km(miles) - 390.0).abs() < 1e-9);
+    assert!((Units::Imperial.distance_to_km(242.3) - 390.0).abs() < 0.1);
+
+    let (feet, label) = Units::Imperial.altitude(0.3048);
+    assert_eq!(label, "ft");
+    assert!((feet - 1000.0).abs() < 1e-9, "{}", feet);
+    assert!((Units::Imperial.altitude_to_km(feet) - 0.3048).abs() < 1e-12);
+
+    assert_eq!(Units::Metric.distance(390.0), (390.0, "km"));
+    assert_eq!(Units::Metric.distance_to_km(390.0), 390.0);
+    assert_eq!(Units::Metric.altitude(420.0), (420.0, "km"));
+}
+
+#[test]
+fn precision_rounds_rather_than_truncates() {
+    let imperial = NumberStyle {
+        units: Units::Imperial,
+        precision: Some(1),
+    };
+    assert_eq!(imperial.distance(390.0), "242.3 miles");
+    // 242.36 is 390.04 km; truncating would give 242.3.
+    assert_eq!(imperial.distance(390.04), "242.4 miles");
+    assert_eq!(imperial.number(45.96, 3), "46.0");
+    assert_eq!(imperial.number(0.04, 3), "0.0");
+
+    let metric = NumberStyle::default();
+    // Without a precision, each number gets its usual places.
+    assert_eq!(metric.distance(679.6), "680 km");
+    assert_eq!(metric.number(45.96, 1), "46.0");
+    assert_eq!(metric.number(45.96, 3), "45.960");
+    assert_eq!(metric.altitude(419.5001), "420 km");
+    assert_eq!(metric.speed(7.666), "7.67 km/s");
+    assert_eq!(imperial.speed(7.666), "4.76 mi/s");
+
+    let exact = NumberStyle {
+        units: Units::Metric,
+        precision: Some(4),
+    };
+    assert_eq!(exact.distance(679.56789), "679.5679 km");
+    // Speeds aren't angles or distances, so they keep their 2 places.
+    assert_eq!(exact.speed(7.666), "7.67 km/s");
+}